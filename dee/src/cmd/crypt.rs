@@ -107,7 +107,7 @@ pub async fn decrypt(
     };
 
     let dst = file_or_stdout(output)?;
-    tlock_age::decrypt(dst, src, &header.hash(), &beacon.signature()).map(|()| String::from(""))
+    tlock_age::decrypt(dst, src, &header.hash(), beacon.signature()).map(|()| String::from(""))
 }
 
 // Reader buffering every read, and with the ability to re-read what's been read already.
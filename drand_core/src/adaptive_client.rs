@@ -0,0 +1,202 @@
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+use crate::chain::ChainOptions;
+use crate::{
+    beacon::RandomnessBeacon,
+    error::{Error, Result},
+    HttpClient,
+};
+
+/// Weight of the latest request in the rolling latency of a relay.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct RelayStats {
+    // exponentially weighted moving average
+    latency: Option<Duration>,
+    consecutive_failures: u32,
+    last_used: Option<Instant>,
+}
+
+/// Client sending each request to the fastest healthy relay of the same chain
+/// Relays are ranked by consecutive failures, then by rolling latency. If the preferred relay fails, the request falls back to the next one
+/// Relays which have not been used for the probe interval are tried first, so that their statistics stay current
+/// This relies on [`std::time::Instant`], which is not available on wasm32-unknown-unknown
+pub struct AdaptiveClient {
+    clients: Vec<HttpClient>,
+    stats: Vec<Mutex<RelayStats>>,
+    probe_interval: Duration,
+}
+
+impl AdaptiveClient {
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn new(base_urls: &[&str], options: Option<ChainOptions>) -> Result<Self> {
+        let clients = base_urls
+            .iter()
+            .map(|base_url| HttpClient::new(base_url, options.clone()))
+            .collect::<Result<Vec<HttpClient>>>()?;
+        Self::from_clients(clients)
+    }
+
+    /// Select among already configured clients.
+    pub fn from_clients(clients: Vec<HttpClient>) -> Result<Self> {
+        if clients.is_empty() {
+            return Err(Error::Other(anyhow!("at least one relay is required")));
+        }
+        let stats = clients.iter().map(|_| Mutex::default()).collect();
+        Ok(Self {
+            clients,
+            stats,
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+        })
+    }
+
+    /// Probe relays which have not been used for `probe_interval`. It defaults to 60 seconds.
+    pub fn with_probe_interval(mut self, probe_interval: Duration) -> Self {
+        self.probe_interval = probe_interval;
+        self
+    }
+
+    pub fn clients(&self) -> &[HttpClient] {
+        &self.clients
+    }
+
+    /// Rolling latency of each relay, in the order of [`AdaptiveClient::clients`]. It is `None` until a relay has responded.
+    pub fn latencies(&self) -> Vec<Option<Duration>> {
+        self.stats
+            .iter()
+            .map(|stats| stats.lock().unwrap().latency)
+            .collect()
+    }
+
+    pub async fn latest(&self) -> Result<RandomnessBeacon> {
+        self.select(|client| client.latest()).await
+    }
+
+    pub async fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        self.select(|client| client.get(round_number)).await
+    }
+
+    async fn select<'a, F, Fut>(&'a self, request: F) -> Result<RandomnessBeacon>
+    where
+        F: Fn(&'a HttpClient) -> Fut,
+        Fut: Future<Output = Result<RandomnessBeacon>>,
+    {
+        let mut last_err = None;
+        for i in self.ranking() {
+            let start = Instant::now();
+            let result = request(&self.clients[i]).await;
+            self.record(i, start, result.is_ok());
+            match result {
+                Ok(beacon) => return Ok(beacon),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| Error::Other(anyhow!("no relay available"))))
+    }
+
+    /// Relay indices, in the order requests should be attempted.
+    fn ranking(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let stats: Vec<_> = self
+            .stats
+            .iter()
+            .map(|stats| {
+                let stats = stats.lock().unwrap();
+                let is_due = stats
+                    .last_used
+                    .map_or(true, |last_used| now >= last_used + self.probe_interval);
+                (is_due, stats.consecutive_failures, stats.latency)
+            })
+            .collect();
+
+        let mut ranking: Vec<usize> = (0..self.clients.len()).collect();
+        ranking.sort_by_key(|&i| {
+            let (_, consecutive_failures, latency) = stats[i];
+            (consecutive_failures, latency.unwrap_or(Duration::ZERO))
+        });
+        // a single relay is probed per request, to bound the latency it costs
+        if let Some(position) = ranking.iter().position(|&i| stats[i].0) {
+            let probed = ranking.remove(position);
+            ranking.insert(0, probed);
+        }
+        ranking
+    }
+
+    fn record(&self, i: usize, start: Instant, is_success: bool) {
+        let mut stats = self.stats[i].lock().unwrap();
+        stats.last_used = Some(Instant::now());
+        if !is_success {
+            stats.consecutive_failures += 1;
+            return;
+        }
+        stats.consecutive_failures = 0;
+        let elapsed = start.elapsed();
+        stats.latency = Some(match stats.latency {
+            Some(latency) => {
+                latency.mul_f64(1.0 - LATENCY_WEIGHT) + elapsed.mul_f64(LATENCY_WEIGHT)
+            }
+            None => elapsed,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn adaptive_client_works() {
+        let mut failing_server = mockito::Server::new_async().await;
+        let failing_mock = failing_server
+            .mock("GET", "/public/latest")
+            .with_status(500)
+            .expect(1)
+            .create_async()
+            .await;
+        let mut valid_server = mockito::Server::new_async().await;
+        valid_server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let valid_mock = valid_server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = AdaptiveClient::new(
+            &[failing_server.url().as_str(), valid_server.url().as_str()],
+            None,
+        )
+        .unwrap();
+        // the failing relay is probed first, and the request falls back to the valid one
+        for _ in 0..3 {
+            assert_eq!(client.latest().await.unwrap().beacon(), chained_beacon());
+        }
+        failing_mock.assert_async().await;
+        valid_mock.assert_async().await;
+        let latencies = client.latencies();
+        assert!(latencies[0].is_none() && latencies[1].is_some());
+
+        assert!(AdaptiveClient::new(&[], None).is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use std::{future::Future, sync::Arc, time::Instant};
+
+use futures::{Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(all(feature = "hyper", not(feature = "reqwest")))]
+use crate::transport::HyperTransport;
+use crate::{
+    beacon::ApiBeacon,
+    chain::ChainInfo,
+    error::{RelayError, Result},
+    metrics::MetricsSink,
+    strict::{self, Validate},
+    transport::{ChainTransport, Conditional, Transport},
+};
+#[cfg(feature = "reqwest")]
+use crate::{http_options::HttpOptions, transport::HttpTransport};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Progress of a relay, as reported by `/health`.
+pub struct RelayHealth {
+    current: u64,
+    expected: u64,
+}
+
+impl RelayHealth {
+    pub fn new(current: u64, expected: u64) -> Self {
+        Self { current, expected }
+    }
+
+    /// Latest round the relay has.
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// Round the chain is expected to be at, given the current time.
+    pub fn expected(&self) -> u64 {
+        self.expected
+    }
+
+    /// Number of rounds the relay is behind the chain.
+    pub fn lag(&self) -> u64 {
+        self.expected.saturating_sub(self.current)
+    }
+
+    /// Whether the relay is at most one round behind, the tolerance relays apply themselves.
+    pub fn is_healthy(&self) -> bool {
+        self.lag() <= 1
+    }
+}
+
+/// Low-level HTTP client for drand
+/// Each method maps to a single relay endpoint, and returns the raw response
+/// Responses are neither cached nor verified. Use [`crate::HttpClient`] for these
+#[derive(Clone)]
+pub struct ApiClient {
+    transport: Arc<dyn Transport>,
+    is_cache: bool,
+    is_strict: bool,
+    metrics: Option<Arc<dyn MetricsSink>>,
+}
+
+impl ApiClient {
+    #[cfg(feature = "reqwest")]
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_http_options(base_url, HttpOptions::default())
+    }
+
+    /// Create a client querying the relay with [`HyperTransport`], when reqwest is not enabled.
+    #[cfg(all(feature = "hyper", not(feature = "reqwest")))]
+    pub fn new(base_url: &str) -> Result<Self> {
+        Ok(Self::with_transport(HyperTransport::new(base_url)?))
+    }
+
+    /// Create a client whose HTTP transport follows `http_options`.
+    /// With the `unix` feature, `http+unix://` base URLs are reached over a Unix domain socket, and only the maximum response size applies.
+    #[cfg(feature = "reqwest")]
+    pub fn with_http_options(base_url: &str, http_options: HttpOptions) -> Result<Self> {
+        #[cfg(all(feature = "unix", unix))]
+        if base_url.starts_with("http+unix://") {
+            let transport = crate::transport::UnixTransport::new(base_url)?
+                .with_max_response_size(http_options.max_response_size());
+            return Ok(Self::with_transport(transport));
+        }
+        let transport = HttpTransport::new(http_options.build_client()?, base_url)?
+            .with_max_response_size(http_options.max_response_size());
+        Ok(Self::with_transport(transport))
+    }
+
+    /// Create a client reusing an existing reqwest client, with its connection pool, proxy, and TLS configuration.
+    #[cfg(feature = "reqwest")]
+    pub fn with_http_client(http_client: reqwest::Client, base_url: &str) -> Result<Self> {
+        Ok(Self::with_transport(HttpTransport::new(
+            http_client,
+            base_url,
+        )?))
+    }
+
+    /// Create a client retrieving relay responses through a custom transport.
+    pub fn with_transport(transport: impl Transport + 'static) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            is_cache: true,
+            is_strict: false,
+            metrics: None,
+        }
+    }
+
+    /// Client for the chain identified by `chain_hash`, on a relay serving multiple chains. It shares this client transport.
+    pub(crate) fn for_chain(&self, chain_hash: &[u8]) -> Self {
+        Self {
+            transport: Arc::new(ChainTransport::new(self.transport.clone(), chain_hash)),
+            is_cache: self.is_cache,
+            is_strict: self.is_strict,
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    /// Report each request, with its latency and outcome, to `metrics`.
+    /// Latencies rely on [`std::time::Instant`], which is not available on wasm32-unknown-unknown.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Reject relay responses which are not exactly the documents drand relays produce, instead of ignoring what is not understood.
+    /// Unknown fields, byte fields which are not lowercase hex, and inconsistent fields, such as a randomness which is not the hash of the signature, are then deserialization errors.
+    pub fn with_strict(mut self, is_strict: bool) -> Self {
+        self.is_strict = is_strict;
+        self
+    }
+
+    pub(crate) fn metrics(&self) -> Option<&dyn MetricsSink> {
+        self.metrics.as_deref()
+    }
+
+    async fn observe<T>(&self, path: &str, request: impl Future<Output = Result<T>>) -> Result<T> {
+        let Some(metrics) = self.metrics() else {
+            return request.await;
+        };
+        let start = Instant::now();
+        let result = request.await;
+        // cache busting query parameters would make every path unique
+        let path = path.split('?').next().unwrap_or_default();
+        metrics.request(path, start.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Allow intermediate caches to serve beacon responses.
+    /// When disabled, a random query parameter is added to each beacon request.
+    pub(crate) fn with_cache(mut self, is_cache: bool) -> Self {
+        self.is_cache = is_cache;
+        self
+    }
+
+    fn beacon_path(&self, round: String) -> String {
+        if self.is_cache {
+            format!("public/{round}")
+        } else {
+            format!("public/{round}?{}", rand::random::<u64>())
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.transport.base_url()
+    }
+
+    /// Hashes of the chains served by the relay, as returned by `/chains`.
+    pub async fn chains(&self) -> Result<Vec<Vec<u8>>> {
+        let chains = self
+            .observe("chains", self.transport.get_json("chains"))
+            .await?;
+        let chains: Vec<String> = parse(chains, self.is_strict)?;
+        chains
+            .iter()
+            .map(|chain_hash| Ok(hex::decode(chain_hash).map_err(anyhow::Error::from)?))
+            .collect()
+    }
+
+    /// Chain info as returned by `/info`.
+    pub async fn chain_info(&self) -> Result<ChainInfo> {
+        let info = self
+            .observe("info", self.transport.get_json("info"))
+            .await?;
+        Ok(parse(info, self.is_strict)?)
+    }
+
+    /// Chain info as returned by `/info`, unless it still matches `etag`.
+    pub async fn chain_info_if_none_match(
+        &self,
+        etag: Option<&str>,
+    ) -> Result<Conditional<ChainInfo>> {
+        match self
+            .observe("info", self.transport.get_json_if_none_match("info", etag))
+            .await?
+        {
+            Conditional::Modified(info, etag) => {
+                Ok(Conditional::Modified(parse(info, self.is_strict)?, etag))
+            }
+            Conditional::NotModified => Ok(Conditional::NotModified),
+        }
+    }
+
+    /// Relay progress as returned by `/health`.
+    pub async fn health(&self) -> Result<RelayHealth> {
+        let health = match self
+            .observe("health", self.transport.get_json("health"))
+            .await
+        {
+            Ok(health) => health,
+            Err(err) => {
+                // a lagging relay answers with a server error, and still reports its progress
+                let body = match err.relay() {
+                    Some(RelayError::Server { body, .. }) => body.clone(),
+                    _ => return Err(err),
+                };
+                serde_json::from_str(&body).map_err(|_| err)?
+            }
+        };
+        Ok(parse(health, self.is_strict)?)
+    }
+
+    /// Latest beacon as returned by `/public/latest`.
+    pub async fn latest(&self) -> Result<ApiBeacon> {
+        self.beacon("latest".to_string()).await
+    }
+
+    /// Beacon for a specific round as returned by `/public/{round_number}`.
+    pub async fn get(&self, round_number: u64) -> Result<ApiBeacon> {
+        self.beacon(round_number.to_string()).await
+    }
+
+    /// Beacons pushed by the relay on `/public/stream`, as they are emitted.
+    /// Fails if the relay does not expose a stream.
+    pub async fn stream(&self) -> Result<impl Stream<Item = Result<ApiBeacon>>> {
+        let beacons = self
+            .observe("public/stream", self.transport.stream_json("public/stream"))
+            .await?;
+        let is_strict = self.is_strict;
+        Ok(beacons.map(move |beacon| Ok(parse(beacon?, is_strict)?)))
+    }
+
+    async fn beacon(&self, round: String) -> Result<ApiBeacon> {
+        let path = self.beacon_path(round);
+        let beacon = self.observe(&path, self.transport.get_json(&path)).await?;
+        Ok(parse(beacon, self.is_strict)?)
+    }
+}
+
+/// Parse a relay response, checking it is well formed when `is_strict`. See [`ApiClient::with_strict`].
+fn parse<T: DeserializeOwned + Serialize + Validate>(
+    value: Value,
+    is_strict: bool,
+) -> Result<T, RelayError> {
+    match is_strict {
+        true => strict::from_value(value),
+        false => Ok(serde_json::from_value(value)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, invalid_beacon};
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn api_client_does_not_verify_works() {
+        let mut server = mockito::Server::new_async().await;
+        let _info_mock = server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let _round_mock = server
+            .mock("GET", "/public/1234")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&invalid_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(server.url().as_str()).unwrap();
+
+        let info = client.chain_info().await.unwrap();
+        assert_eq!(info, chained_chain_info());
+
+        // beacon is returned as is, even though it does not verify against the chain
+        let beacon = match client.get(1234).await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(beacon, invalid_beacon());
+    }
+
+    #[tokio::test]
+    async fn api_client_health_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/health")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"current":1000,"expected":1001}"#)
+            .create_async()
+            .await;
+        let client = ApiClient::new(server.url().as_str()).unwrap();
+        let health = client.health().await.unwrap();
+        assert_eq!(health, RelayHealth::new(1000, 1001));
+        assert!(health.is_healthy());
+
+        // lagging relays still report their progress
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/health")
+            .with_status(500)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"current":990,"expected":1001}"#)
+            .create_async()
+            .await;
+        let client = ApiClient::new(server.url().as_str()).unwrap();
+        let health = client.health().await.unwrap();
+        assert_eq!(health.lag(), 11);
+        assert!(!health.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn api_client_strict_works() {
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["extra"] = "field".into();
+        let mut server = mockito::Server::new_async().await;
+        let _round_mock = server
+            .mock("GET", "/public/1000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(beacon.to_string())
+            .expect(2)
+            .create_async()
+            .await;
+
+        // unknown fields are ignored by default
+        let client = ApiClient::new(server.url().as_str()).unwrap();
+        assert_eq!(client.get(1000000).await.unwrap(), chained_beacon());
+
+        let client = client.with_strict(true);
+        let err = client.get(1000000).await.unwrap_err();
+        assert!(matches!(err.relay(), Some(RelayError::Deserialization(_))));
+    }
+
+    #[tokio::test]
+    async fn api_client_errors_works() {
+        let mut server = mockito::Server::new_async().await;
+        let _round_mock = server
+            .mock("GET", "/public/1234")
+            .with_status(404)
+            .with_body("round not found")
+            .create_async()
+            .await;
+        let _info_mock = server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create_async()
+            .await;
+
+        let client = ApiClient::new(server.url().as_str()).unwrap();
+
+        // round not produced yet can be told apart from other failures
+        let err = client.get(1234).await.unwrap_err();
+        match err.relay() {
+            Some(RelayError::NotFound { body }) => assert_eq!(body, "round not found"),
+            _ => panic!("Error should be not found, got {err}"),
+        }
+
+        let err = client.chain_info().await.unwrap_err();
+        assert!(matches!(err.relay(), Some(RelayError::Deserialization(_))));
+    }
+}
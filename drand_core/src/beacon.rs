@@ -1,13 +1,16 @@
-use anyhow::Result;
+use std::{fmt, str::FromStr};
+
+use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::chain::ChainInfo;
+use crate::chain::{Chain, ChainInfo, Scheme};
+use crate::error::{Error, Result};
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
 pub struct RandomnessBeacon {
     #[serde(flatten)]
-    beacon: ApiBeacon,
+    pub(crate) beacon: ApiBeacon,
     #[serde(skip_serializing)]
     time: u64,
 }
@@ -21,14 +24,20 @@ impl RandomnessBeacon {
         self.beacon.verify(info)
     }
 
+    /// Verify the beacon against `info`, telling why it does not verify. See [`Chain::verify_detailed`].
+    pub fn verify_detailed(&self, info: ChainInfo) -> Result<()> {
+        Chain::new(info)?.verify_detailed(self)
+    }
+
     pub fn round(&self) -> u64 {
         self.beacon.round()
     }
 
-    pub fn randomness(&self) -> Vec<u8> {
+    pub fn randomness(&self) -> &[u8] {
         self.beacon.randomness()
     }
 
+    /// Scheme guessed from the beacon structure. See [`ApiBeacon::scheme_id`].
     pub fn scheme_id(&self) -> String {
         self.beacon.scheme_id()
     }
@@ -37,21 +46,187 @@ impl RandomnessBeacon {
         self.beacon.is_unchained()
     }
 
-    pub fn signature(&self) -> Vec<u8> {
+    pub fn signature(&self) -> &[u8] {
         self.beacon.signature()
     }
 
+    /// Signature of the previous round, which this beacon signs over. It is `None` for unchained beacons.
+    pub fn previous_signature(&self) -> Option<&[u8]> {
+        self.beacon.previous_signature()
+    }
+
     pub fn time(&self) -> u64 {
         self.time
     }
 
+    /// Time of the beacon (in epoch seconds), computed from the chain described by `info`. It is [`RandomnessBeacon::time`] for beacons retrieved from that chain.
+    pub fn time_with(&self, info: &ChainInfo) -> u64 {
+        info.time_of_round(self.round())
+    }
+
+    /// Seconds elapsed between the beacon time and `now` (in epoch seconds), given the chain described by `info`. It is zero if `now` is before the beacon time.
+    pub fn age(&self, info: &ChainInfo, now: u64) -> u64 {
+        now.saturating_sub(self.time_with(info))
+    }
+
+    /// Whether this beacon is the latest one emitted by the chain described by `info` at `now` (in epoch seconds).
+    /// It is from [`RandomnessBeacon::time_with`] until the next round is emitted. See [`crate::chain::Chain::round_at`].
+    pub fn is_latest(&self, info: &ChainInfo, now: u64) -> bool {
+        self.round() == info.round_at(now)
+    }
+
+    /// Compact binary encoding, as in drand archives: the round as a big-endian `u64`, the signature, then the previous signature for chained beacons.
+    /// The randomness is not encoded, as it is derived from the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.round().to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.signature());
+        if let Some(previous_signature) = self.previous_signature() {
+            bytes.extend_from_slice(previous_signature);
+        }
+        bytes
+    }
+
+    /// Parse the binary encoding of a beacon of the chain described by `info`. See [`RandomnessBeacon::to_bytes`].
+    /// The signature length and chaining are the ones of the chain scheme. The randomness is derived from the signature, and the time from `info`.
+    /// The beacon is not verified.
+    pub fn from_bytes(bytes: &[u8], info: &ChainInfo) -> Result<Self> {
+        let scheme = Scheme::from_str(&info.scheme_id())?;
+        let invalid = || Error::Other(anyhow!("invalid {scheme} beacon encoding"));
+        if bytes.len() < 8 + scheme.signature_len() {
+            return Err(invalid());
+        }
+        let (round, rest) = bytes.split_at(8);
+        let round = u64::from_be_bytes(round.try_into().map_err(|_| invalid())?);
+        let (signature, previous_signature) = rest.split_at(scheme.signature_len());
+        let previous_signature = match scheme.is_unchained() {
+            true if previous_signature.is_empty() => None,
+            // the first round signs over the 32 bytes genesis seed
+            false if previous_signature.len() == if round == 1 { 32 } else { 96 } => {
+                Some(previous_signature.to_vec())
+            }
+            _ => return Err(invalid()),
+        };
+        let beacon = ApiBeacon::from_signature(round, signature.to_vec(), previous_signature);
+        Ok(Self::new(beacon, info.time_of_round(round)))
+    }
+
+    /// Bundle the beacon with `info`, so that it can be verified later without contacting a relay. See [`Proof`].
+    pub fn to_proof(&self, info: &ChainInfo) -> Proof {
+        Proof {
+            chain_info: info.clone(),
+            beacon: self.beacon.clone(),
+        }
+    }
+
+    /// Deterministic stream of bytes as long as needed, expanded from the beacon randomness. See [`crate::draw::DrawRng`].
+    pub fn keystream(&self) -> crate::draw::DrawRng {
+        crate::draw::DrawRng::new(self)
+    }
+
     #[cfg(test)]
     pub(crate) fn beacon(&self) -> ApiBeacon {
         self.beacon.clone()
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+/// Beacon which has verified against its chain.
+/// It is only obtained through verification, such as [`VerifiedBeacon::new`], or from a client verifying beacons. Functions taking one do not need to trust their caller to have verified it.
+pub struct VerifiedBeacon(RandomnessBeacon);
+
+impl VerifiedBeacon {
+    /// Verify `beacon` against `info`. Fails with [`Error::InvalidBeacon`] if it does not verify.
+    pub fn new(beacon: RandomnessBeacon, info: ChainInfo) -> Result<Self> {
+        Chain::new(info)?.verified(beacon)
+    }
+
+    /// Wrap a beacon which has already been verified.
+    pub(crate) fn new_unchecked(beacon: RandomnessBeacon) -> Self {
+        Self(beacon)
+    }
+
+    pub fn into_inner(self) -> RandomnessBeacon {
+        self.0
+    }
+}
+
+/// Same as the inner beacon. See [`ApiBeacon`].
+impl fmt::Display for RandomnessBeacon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.beacon.fmt(f)
+    }
+}
+
+impl fmt::Display for VerifiedBeacon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::ops::Deref for VerifiedBeacon {
+    type Target = RandomnessBeacon;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<VerifiedBeacon> for RandomnessBeacon {
+    fn from(beacon: VerifiedBeacon) -> Self {
+        beacon.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Self-contained proof of a beacon, to archive or forward it.
+/// It holds the beacon and the info of its chain, including the chain hash, public key, and scheme. It verifies offline, years later.
+pub struct Proof {
+    chain_info: ChainInfo,
+    beacon: ApiBeacon,
+}
+
+impl Proof {
+    pub fn chain_info(&self) -> ChainInfo {
+        self.chain_info.clone()
+    }
+
+    pub fn round(&self) -> u64 {
+        self.beacon.round()
+    }
+
+    pub fn randomness(&self) -> &[u8] {
+        self.beacon.randomness()
+    }
+
+    /// Beacon, with its emission time computed from the chain info.
+    pub fn beacon(&self) -> RandomnessBeacon {
+        let time = self.chain_info.time_of_round(self.round());
+        RandomnessBeacon::new(self.beacon.clone(), time)
+    }
+
+    /// Whether the chain info is consistent with its hash, and the beacon verifies against it.
+    /// Check [`ChainInfo::hash`] is the expected chain, as anyone can produce a proof for a chain of their own.
+    pub fn verify(&self) -> Result<bool> {
+        if !self.chain_info.verify_hash() {
+            return Ok(false);
+        }
+        Chain::new(self.chain_info.clone())?.verify_api_beacon(&self.beacon)
+    }
+}
+
+/// Verify many beacons of the chain described by `info` at once, which is far faster than verifying them one by one.
+/// It is `false` if any beacon does not verify, without telling which. See [`Chain::verify_batch`].
+pub fn verify_batch(beacons: &[RandomnessBeacon], info: &ChainInfo) -> Result<bool> {
+    Chain::new(info.clone())?.verify_batch(beacons)
+}
+
+/// Verify consecutive beacons of the chained chain described by `info`, including the link between each of them. See [`Chain::verify_chain`].
+pub fn verify_chain(beacons: &[RandomnessBeacon], info: &ChainInfo) -> Result<bool> {
+    Chain::new(info.clone())?.verify_chain(beacons)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 /// Random beacon as generated by drand.
 /// These can be chained or unchained, and should be verifiable against a chain.
@@ -62,19 +237,7 @@ pub enum ApiBeacon {
 
 impl ApiBeacon {
     pub fn verify(&self, info: ChainInfo) -> Result<bool> {
-        if self.scheme_id() != info.scheme_id() {
-            return Ok(false);
-        }
-
-        let signature_verify =
-            crate::bls_signatures::verify(&self.signature(), &self.message()?, &info.public_key())?;
-
-        let mut hasher = Sha256::new();
-        hasher.update(self.signature());
-        let randomness = hasher.finalize().to_vec();
-        let randomness_verify = randomness == self.randomness();
-
-        Ok(signature_verify && randomness_verify)
+        Chain::new(info)?.verify_api_beacon(self)
     }
 
     pub fn round(&self) -> u64 {
@@ -84,13 +247,14 @@ impl ApiBeacon {
         }
     }
 
-    pub fn randomness(&self) -> Vec<u8> {
+    pub fn randomness(&self) -> &[u8] {
         match self {
-            Self::ChainedBeacon(chained) => chained.randomness.clone(),
-            Self::UnchainedBeacon(unchained) => unchained.randomness.clone(),
+            Self::ChainedBeacon(chained) => &chained.randomness,
+            Self::UnchainedBeacon(unchained) => &unchained.randomness,
         }
     }
 
+    /// Scheme guessed from the beacon structure. Beacons do not carry their scheme: the authoritative one is the chain one, see [`Chain::scheme`].
     pub fn scheme_id(&self) -> String {
         match self {
             Self::ChainedBeacon(_) => "pedersen-bls-chained",
@@ -109,10 +273,51 @@ impl ApiBeacon {
         self.scheme_id().contains("unchained")
     }
 
-    pub fn signature(&self) -> Vec<u8> {
+    pub fn signature(&self) -> &[u8] {
+        match self {
+            Self::ChainedBeacon(chained) => &chained.signature,
+            Self::UnchainedBeacon(unchained) => &unchained.signature,
+        }
+    }
+
+    pub fn previous_signature(&self) -> Option<&[u8]> {
         match self {
-            Self::ChainedBeacon(chained) => chained.signature.clone(),
-            Self::UnchainedBeacon(unchained) => unchained.signature.clone(),
+            Self::ChainedBeacon(chained) => Some(&chained.previous_signature),
+            Self::UnchainedBeacon(_) => None,
+        }
+    }
+}
+
+impl ApiBeacon {
+    /// Beacon of `round` carrying `signature`, with its randomness derived from it. It is chained if it has a `previous_signature`.
+    pub(crate) fn from_signature(
+        round: u64,
+        signature: Vec<u8>,
+        previous_signature: Option<Vec<u8>>,
+    ) -> Self {
+        let randomness = Sha256::digest(&signature).to_vec();
+        match previous_signature {
+            Some(previous_signature) => Self::ChainedBeacon(ChainedBeacon {
+                round,
+                randomness,
+                signature,
+                previous_signature,
+            }),
+            None => Self::UnchainedBeacon(UnchainedBeacon {
+                round,
+                randomness,
+                signature,
+            }),
+        }
+    }
+}
+
+/// Round, then hex encoded randomness and signatures, such as `round 1, randomness 7d0a…, signature 8f3c…`. Chained beacons end with their previous signature.
+impl fmt::Display for ApiBeacon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ChainedBeacon(chained) => chained.fmt(f),
+            Self::UnchainedBeacon(unchained) => unchained.fmt(f),
         }
     }
 }
@@ -139,50 +344,86 @@ impl From<UnchainedBeacon> for ApiBeacon {
 }
 
 /// Package item to be validated against a BLS signature given a public key.
-trait Message {
+pub(crate) trait Message {
     fn message(&self) -> Result<Vec<u8>>;
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Chained drand beacon.
 /// Each signature depends on the previous one, as well as on the round.
 pub struct ChainedBeacon {
     round: u64,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     randomness: Vec<u8>,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     signature: Vec<u8>,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     previous_signature: Vec<u8>,
 }
 
+impl fmt::Display for ChainedBeacon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round {}, randomness {}, signature {}, previous signature {}",
+            self.round,
+            hex::encode(&self.randomness),
+            hex::encode(&self.signature),
+            hex::encode(&self.previous_signature)
+        )
+    }
+}
+
 impl Message for ChainedBeacon {
     fn message(&self) -> Result<Vec<u8>> {
-        // First round signature is on the genesis seed, which size is 32B, and not 96B like G2 signatures.
-        let len = if self.round == 1 { 32 } else { 96 };
-        let mut buf = vec![0; len + 8];
-        let (signature_buf, round_buf) = buf.split_at_mut(len);
-
-        signature_buf.clone_from_slice(&self.previous_signature);
-        round_buf.clone_from_slice(&self.round.to_be_bytes());
+        chained_message(self.round, &self.previous_signature)
+    }
+}
 
-        let mut hasher = Sha256::new();
-        hasher.update(buf);
-        Ok(hasher.finalize().to_vec())
+/// Message signed by the chained beacon of `round`: the hash of the compressed `previous_signature`, followed by the round.
+pub(crate) fn chained_message(round: u64, previous_signature: &[u8]) -> Result<Vec<u8>> {
+    // First round signature is on the genesis seed, which size is 32B, and not 96B like G2 signatures.
+    let len = if round == 1 { 32 } else { 96 };
+    if previous_signature.len() != len {
+        return Err(Error::Other(anyhow!(
+            "round {round} previous signature is {} bytes, instead of {len}",
+            previous_signature.len()
+        )));
     }
+    let mut buf = vec![0; len + 8];
+    let (signature_buf, round_buf) = buf.split_at_mut(len);
+
+    signature_buf.clone_from_slice(previous_signature);
+    round_buf.clone_from_slice(&round.to_be_bytes());
+
+    let mut hasher = Sha256::new();
+    hasher.update(&buf);
+    Ok(hasher.finalize().to_vec())
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Unchained drand beacon.
 /// Each signature only depends on the round number.
 pub struct UnchainedBeacon {
     round: u64,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     randomness: Vec<u8>,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     signature: Vec<u8>,
 }
 
+impl fmt::Display for UnchainedBeacon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round {}, randomness {}, signature {}",
+            self.round,
+            hex::encode(&self.randomness),
+            hex::encode(&self.signature)
+        )
+    }
+}
+
 impl Message for UnchainedBeacon {
     fn message(&self) -> Result<Vec<u8>> {
         let buf = self.round.to_be_bytes();
@@ -250,33 +491,155 @@ pub mod tests {
         }"#).unwrap()
     }
 
-    impl PartialEq for ApiBeacon {
-        fn eq(&self, other: &Self) -> bool {
-            match (self, other) {
-                (Self::ChainedBeacon(chained), Self::ChainedBeacon(other)) => chained == other,
-                (Self::UnchainedBeacon(unchained), Self::UnchainedBeacon(other)) => {
-                    unchained == other
-                }
-                _ => false,
-            }
-        }
+    #[test]
+    fn proof_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        let proof = chain
+            .beacon(chained_beacon())
+            .to_proof(&chained_chain_info());
+        assert!(proof.verify().unwrap());
+
+        // the proof survives a round trip through JSON
+        let json = serde_json::to_value(&proof).unwrap();
+        let parsed: Proof = serde_json::from_value(json.clone()).unwrap();
+        assert!(parsed.verify().unwrap());
+        assert_eq!(parsed.round(), 1000000);
+        assert_eq!(parsed.chain_info(), chained_chain_info());
+        assert_eq!(parsed.beacon().beacon(), chained_beacon());
+
+        let mut tampered = json.clone();
+        tampered["beacon"]["randomness"] = hex::encode([0u8; 32]).into();
+        let tampered: Proof = serde_json::from_value(tampered).unwrap();
+        assert!(!tampered.verify().unwrap());
+
+        // a public key which is not the one of the chain hash
+        let mut tampered = json;
+        tampered["chain_info"]["public_key"] =
+            hex::encode(unchained_chain_info().public_key()).into();
+        let tampered: Proof = serde_json::from_value(tampered).unwrap();
+        assert!(!tampered.verify().unwrap());
     }
 
-    impl PartialEq for ChainedBeacon {
-        fn eq(&self, other: &Self) -> bool {
-            self.randomness == other.randomness
-                && self.round == other.round
-                && self.signature == other.signature
-                && self.previous_signature == other.previous_signature
+    #[test]
+    fn beacon_bytes_works() {
+        let fixtures = [
+            (chained_beacon(), chained_chain_info(), 200),
+            (chained_beacon_1(), chained_chain_info(), 136),
+            (unchained_beacon(), unchained_chain_info(), 104),
+            (unchained_beacon_on_g1(), unchained_chain_on_g1_info(), 56),
+        ];
+        for (beacon, info, len) in fixtures {
+            let beacon = Chain::new(info.clone()).unwrap().beacon(beacon);
+            let bytes = beacon.to_bytes();
+            assert_eq!(bytes.len(), len);
+            assert_eq!(bytes[..8], beacon.round().to_be_bytes());
+
+            let parsed = RandomnessBeacon::from_bytes(&bytes, &info).unwrap();
+            assert_eq!(parsed.beacon(), beacon.beacon());
+            assert_eq!(parsed.time(), beacon.time());
+            assert!(parsed.verify(info.clone()).unwrap());
+
+            assert!(RandomnessBeacon::from_bytes(&bytes[..len - 1], &info).is_err());
+            assert!(
+                RandomnessBeacon::from_bytes(&[bytes.as_slice(), &[0]].concat(), &info).is_err()
+            );
         }
     }
 
-    impl PartialEq for UnchainedBeacon {
-        fn eq(&self, other: &Self) -> bool {
-            self.randomness == other.randomness
-                && self.round == other.round
-                && self.signature == other.signature
-        }
+    #[test]
+    fn beacon_eq_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        let beacons = [
+            chain.beacon(chained_beacon()),
+            chain.beacon(chained_beacon_1()),
+            chain.beacon(chained_beacon()),
+        ];
+        let unique: std::collections::HashSet<&RandomnessBeacon> = beacons.iter().collect();
+        assert_eq!(unique.len(), 2);
+        assert_ne!(beacons[0], beacons[1]);
+        assert_ne!(chained_beacon(), unchained_beacon());
+
+        // the prepared public key cache is interior mutable, but is left out of the hash
+        #[allow(clippy::mutable_key_type)]
+        let infos: std::collections::HashSet<ChainInfo> = [
+            chained_chain_info(),
+            unchained_chain_info(),
+            chained_chain_info(),
+        ]
+        .into();
+        assert_eq!(infos.len(), 2);
+    }
+
+    #[test]
+    fn beacon_display_works() {
+        let beacon = Chain::new(unchained_chain_on_g1_info())
+            .unwrap()
+            .beacon(unchained_beacon_on_g1());
+        assert_eq!(
+            beacon.to_string(),
+            format!(
+                "round {}, randomness {}, signature {}",
+                beacon.round(),
+                hex::encode(beacon.randomness()),
+                hex::encode(beacon.signature())
+            )
+        );
+        assert!(chained_beacon().to_string().starts_with(
+            "round 1000000, randomness a26ba4d229c666f52a06f1a9be1278dcc7a80dbc1dd2004a1ae7b63cb79fd37e, signature 87e35516"
+        ));
+        assert!(chained_beacon()
+            .to_string()
+            .ends_with(", previous signature 86bbc40c9d9347568967add4ddf6e351aff604352a7e1eec9b20dea4ca531ed6c7d38de9956ffc3bb5a7fabe28b3a36b069c8113bd9824135c3bff9b03359476f6b03beec179d4aeff456f4d34bbf702b9af78c3bb44e1892ace8e581bf4afa9"));
+    }
+
+    #[test]
+    fn beacon_message_works() {
+        assert!(chained_beacon().message().is_ok());
+        assert!(chained_beacon_1().message().is_ok());
+        // a previous signature of another length is rejected, rather than copied into the message
+        let signature = chained_beacon().signature().to_vec();
+        let beacon = ApiBeacon::from_signature(1000000, signature.clone(), Some(vec![0; 32]));
+        assert!(beacon.message().is_err());
+        let beacon = ApiBeacon::from_signature(1, signature, Some(vec![0; 96]));
+        assert!(beacon.message().is_err());
+    }
+
+    #[test]
+    fn beacon_time_works() {
+        let info = chained_chain_info();
+        let beacon = Chain::new(info.clone()).unwrap().beacon(chained_beacon());
+        // round 1000000 is emitted 999999 periods after genesis
+        let time = info.genesis_time() + 999999 * info.period();
+        assert_eq!(beacon.time_with(&info), time);
+        assert_eq!(beacon.time_with(&info), beacon.time());
+
+        assert_eq!(beacon.age(&info, time + 45), 45);
+        assert_eq!(beacon.age(&info, time - 1), 0);
+
+        assert!(!beacon.is_latest(&info, time - 1));
+        assert!(beacon.is_latest(&info, time));
+        assert!(beacon.is_latest(&info, time + info.period() - 1));
+        assert!(!beacon.is_latest(&info, time + info.period()));
+        assert!(!beacon.is_latest(&info, 0));
+
+        // round 1 is emitted at genesis
+        let beacon = Chain::new(info.clone()).unwrap().beacon(chained_beacon_1());
+        assert_eq!(beacon.time_with(&info), info.genesis_time());
+        assert!(beacon.is_latest(&info, info.genesis_time()));
+        assert!(!beacon.is_latest(&info, info.genesis_time() - 1));
+    }
+
+    #[test]
+    fn verified_beacon_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        let beacon =
+            VerifiedBeacon::new(chain.beacon(chained_beacon()), chained_chain_info()).unwrap();
+        assert_eq!(beacon.round(), 1000000);
+        assert_eq!(beacon.into_inner().beacon(), chained_beacon());
+
+        let err =
+            VerifiedBeacon::new(chain.beacon(invalid_beacon()), chained_chain_info()).unwrap_err();
+        assert!(matches!(err, Error::InvalidBeacon { round: 1234, .. }));
     }
 
     #[test]
@@ -0,0 +1,96 @@
+//! In-memory cache of retrieved beacons.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::beacon::RandomnessBeacon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Lookups served by a beacon cache since it was created.
+pub struct BeaconCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Beacons keyed by round, evicting the least recently used one once `capacity` is reached.
+pub(crate) struct BeaconCache {
+    capacity: usize,
+    beacons: HashMap<u64, RandomnessBeacon>,
+    // least recently used round first
+    usage: VecDeque<u64>,
+    stats: BeaconCacheStats,
+}
+
+impl BeaconCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            beacons: HashMap::with_capacity(capacity),
+            usage: VecDeque::with_capacity(capacity),
+            stats: BeaconCacheStats::default(),
+        }
+    }
+
+    pub fn get(&mut self, round: u64) -> Option<RandomnessBeacon> {
+        match self.beacons.get(&round) {
+            Some(beacon) => {
+                let beacon = beacon.clone();
+                self.touch(round);
+                self.stats.hits += 1;
+                Some(beacon)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, beacon: RandomnessBeacon) {
+        if self.capacity == 0 {
+            return;
+        }
+        let round = beacon.round();
+        if self.beacons.insert(round, beacon).is_some() {
+            self.touch(round);
+            return;
+        }
+        if self.usage.len() == self.capacity {
+            if let Some(evicted) = self.usage.pop_front() {
+                self.beacons.remove(&evicted);
+            }
+        }
+        self.usage.push_back(round);
+    }
+
+    pub fn stats(&self) -> BeaconCacheStats {
+        self.stats
+    }
+
+    fn touch(&mut self, round: u64) {
+        if let Some(position) = self.usage.iter().position(|used| *used == round) {
+            self.usage.remove(position);
+        }
+        self.usage.push_back(round);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, chained_beacon_1, invalid_beacon};
+
+    use super::*;
+
+    #[test]
+    fn beacon_cache_works() {
+        let mut cache = BeaconCache::new(2);
+        cache.insert(RandomnessBeacon::new(chained_beacon_1(), 0));
+        cache.insert(RandomnessBeacon::new(chained_beacon(), 0));
+        assert!(cache.get(1).is_some());
+
+        // round 1000000 is the least recently used, and gets evicted
+        cache.insert(RandomnessBeacon::new(invalid_beacon(), 0));
+        assert!(cache.get(1000000).is_none());
+        assert!(cache.get(1).is_some());
+        assert_eq!(cache.stats(), BeaconCacheStats { hits: 2, misses: 1 });
+    }
+}
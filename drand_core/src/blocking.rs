@@ -0,0 +1,234 @@
+//! Blocking adapters for applications that do not run an async runtime.
+//!
+//! Async clients and streams of beacons are driven by an internal single threaded runtime. Clients expose plain synchronous methods, and streams a standard [`Iterator`].
+//! This must not be used from within an async context, as the runtime would block the current executor.
+
+use std::{ops::RangeInclusive, pin::Pin, sync::Arc};
+
+use futures::{Stream, StreamExt};
+
+use crate::{
+    beacon::RandomnessBeacon,
+    chain::{ChainInfo, RoundingMode},
+    error::{Error, Result},
+    ChainOptions,
+};
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    // Streams may sleep between items, and perform network requests. Both drivers are required.
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(anyhow::Error::from)?)
+}
+
+/// Blocking HTTP Client for drand
+/// Each call blocks the current thread until the underlying [`crate::HttpClient`] completes.
+pub struct HttpClient {
+    // shared with the streams of the client, which are driven on the runtime of its connections
+    runtime: Arc<tokio::runtime::Runtime>,
+    client: crate::HttpClient,
+}
+
+impl HttpClient {
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn new(base_url: &str, options: Option<ChainOptions>) -> Result<Self> {
+        Self::with_client(crate::HttpClient::new(base_url, options)?)
+    }
+
+    /// Wrap an existing async client, for instance one built with a custom transport.
+    pub fn with_client(client: crate::HttpClient) -> Result<Self> {
+        Ok(Self {
+            runtime: Arc::new(runtime()?),
+            client,
+        })
+    }
+
+    pub fn base_url(&self) -> String {
+        self.client.base_url()
+    }
+
+    pub fn options(&self) -> ChainOptions {
+        self.client.options()
+    }
+
+    pub fn chain_info(&self) -> Result<ChainInfo> {
+        self.runtime.block_on(self.client.chain_info())
+    }
+
+    pub fn latest(&self) -> Result<RandomnessBeacon> {
+        self.runtime.block_on(self.client.latest())
+    }
+
+    pub fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        self.runtime.block_on(self.client.get(round_number))
+    }
+
+    pub fn get_by_unix_time(&self, round_unix_time: u64) -> Result<RandomnessBeacon> {
+        self.runtime
+            .block_on(self.client.get_by_unix_time(round_unix_time))
+    }
+
+    pub fn get_by_unix_time_rounded(
+        &self,
+        round_unix_time: u64,
+        rounding: RoundingMode,
+    ) -> Result<RandomnessBeacon> {
+        self.runtime.block_on(
+            self.client
+                .get_by_unix_time_rounded(round_unix_time, rounding),
+        )
+    }
+
+    /// Beacons as they are emitted, starting with the next round. See [`crate::HttpClient::watch`].
+    pub fn watch(&self) -> Result<StreamIter<impl Stream<Item = Result<RandomnessBeacon>> + '_>> {
+        let beacons = self.runtime.block_on(self.client.watch())?;
+        Ok(StreamIter::with_runtime(self.runtime.clone(), beacons))
+    }
+
+    /// Verified beacons for `rounds`, in order. See [`crate::HttpClient::stream_range`].
+    pub fn stream_range(
+        &self,
+        rounds: RangeInclusive<u64>,
+    ) -> Result<StreamIter<impl Stream<Item = Result<RandomnessBeacon>> + '_>> {
+        let beacons = self.runtime.block_on(self.client.stream_range(rounds))?;
+        Ok(StreamIter::with_runtime(self.runtime.clone(), beacons))
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+impl TryFrom<&str> for HttpClient {
+    type Error = Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Self::new(value, None)
+    }
+}
+
+/// Iterator over the items of an async [`Stream`].
+/// Each call to `next` blocks the current thread until the stream yields its next item.
+pub struct StreamIter<S: Stream> {
+    runtime: Arc<tokio::runtime::Runtime>,
+    stream: Pin<Box<S>>,
+}
+
+impl<S: Stream> StreamIter<S> {
+    pub fn new(stream: S) -> Result<Self> {
+        Ok(Self::with_runtime(Arc::new(runtime()?), stream))
+    }
+
+    fn with_runtime(runtime: Arc<tokio::runtime::Runtime>, stream: S) -> Self {
+        Self {
+            runtime,
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<S: Stream> Iterator for StreamIter<S> {
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// Turn an async stream into a blocking iterator.
+pub fn iter<S: Stream>(stream: S) -> Result<StreamIter<S>> {
+    StreamIter::new(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::{chained_chain_info, rehash};
+
+    use super::*;
+
+    #[test]
+    fn stream_iter_works() {
+        let stream = futures::stream::iter(1..=3).then(|i| async move {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            i
+        });
+
+        let items: Vec<u64> = iter(stream).unwrap().collect();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn client_works() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create();
+        server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create();
+        server
+            .mock("GET", "/public/1000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create();
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+
+        assert_eq!(client.chain_info().unwrap(), chained_chain_info());
+        assert_eq!(client.latest().unwrap().beacon(), chained_beacon());
+        assert_eq!(client.get(1000000).unwrap().beacon(), chained_beacon());
+        let beacons: Vec<Result<RandomnessBeacon>> =
+            client.stream_range(1000000..=1000000).unwrap().collect();
+        assert_eq!(beacons.len(), 1);
+        assert_eq!(beacons[0].as_ref().unwrap().beacon(), chained_beacon());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn client_watch_works() {
+        // a chain emitting a round every second, so that beacons do not take long to be awaited
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        info["period"] = serde_json::json!(1);
+        info["genesis_time"] = serde_json::json!(now.as_secs() - 10);
+        rehash(&mut info);
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(info.to_string())
+            .create();
+        let round_mock = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/public/1[0-9]$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(2)
+            .create();
+
+        let client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(false, true, None)),
+        )
+        .unwrap();
+        let start = Instant::now();
+        let beacons: Vec<Result<RandomnessBeacon>> = client.watch().unwrap().take(2).collect();
+        assert!(beacons.iter().all(|beacon| beacon.is_ok()));
+        // one beacon per period
+        assert!(start.elapsed() <= Duration::from_secs(3));
+        round_mock.assert();
+    }
+}
@@ -1,8 +1,11 @@
-/// Verify BLS Signatures used in drand
+/// Verify BLS Signatures used in drand with arkworks, which is the default backend
+/// With the `blst` feature, beacon verification is left to `blst_signatures`, and batch verification and point compression are not compiled.
 /// inspired from https://github.com/noislabs/drand-verify/blob/1017235f6bcfcc9fb433926c0dc1b9a013bd4df3/src/verify.rs#L58
 use std::ops::Neg;
 
 use anyhow::{anyhow, Result};
+#[cfg(not(feature = "blst"))]
+use ark_bls12_381::Fr;
 use ark_bls12_381::{g1, g2, G1Affine, G1Projective, G2Affine, G2Projective};
 use ark_ec::{
     bls12::Bls12,
@@ -13,66 +16,179 @@ use ark_ec::{
 };
 use ark_ff::{field_hashers::DefaultFieldHasher, Zero};
 use ark_serialize::CanonicalDeserialize;
+#[cfg(not(feature = "blst"))]
+use ark_serialize::CanonicalSerialize;
+#[cfg(not(feature = "blst"))]
+use rand::Rng;
 
 const DOMAIN: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
 
-/// Check that signature is the actual aggregate of message and public key.
-/// Calculated by `e(g2, signature) == e(pk, hash)`.
-/// `signature` and `hash` are on G2, `public_key` is on G1.
-pub fn verify(signature: &[u8], hash: &[u8], public_key: &[u8]) -> Result<bool> {
-    // 48 is bytes of G1
-    // G1Affine::identity().to_compressed().len()
-    if signature.len() == 48 {
-        verify_g1_on_g2(signature, hash, public_key)
-    } else {
-        verify_g2_on_g1(signature, hash, public_key)
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Deserialized public key, ready to be used for multiple verifications.
+pub enum PublicKey {
+    G1(G1Affine),
+    G2(G2Affine),
+}
+
+impl PublicKey {
+    /// Parse a compressed public key. The group is determined by its length.
+    pub fn from_compressed(public_key: &[u8]) -> Result<Self> {
+        // 48 is bytes of G1
+        // G1Affine::identity().to_compressed().len()
+        if public_key.len() == 48 {
+            Ok(Self::G1(g1_from_variable(public_key)?))
+        } else {
+            Ok(Self::G2(g2_from_variable(public_key)?))
+        }
+    }
+
+    /// Check that signature is the actual aggregate of message and public key.
+    /// The signature is expected on the group opposite to the public key.
+    pub fn verify(&self, signature: &[u8], hash: &[u8]) -> Result<bool> {
+        match self {
+            Self::G1(public_key) => verify_g2_on_g1(signature, hash, public_key),
+            Self::G2(public_key) => verify_g1_on_g2(signature, hash, public_key),
+        }
+    }
+
+    /// Check that every `(signature, hash)` pair verifies, with a single pairing equality overall.
+    /// Signatures and hashes are combined with random coefficients, so that invalid signatures cannot cancel each other out.
+    /// It does not tell which signature is invalid.
+    #[cfg(not(feature = "blst"))]
+    pub fn verify_batch(&self, items: &[(Vec<u8>, Vec<u8>)]) -> Result<bool> {
+        let mut rng = rand::thread_rng();
+        match self {
+            Self::G1(public_key) => {
+                let mut sigma = G2Projective::zero();
+                let mut hashes = G2Projective::zero();
+                for (signature, hash) in items {
+                    let coefficient = Fr::from(rng.gen::<u128>());
+                    sigma += g2_from_variable(signature)? * coefficient;
+                    hashes += hash_to_g2(hash)? * coefficient;
+                }
+                Ok(fast_pairing_equality(
+                    &G1Affine::generator(),
+                    &sigma.into_affine(),
+                    public_key,
+                    &hashes.into_affine(),
+                ))
+            }
+            Self::G2(public_key) => {
+                let mut sigma = G1Projective::zero();
+                let mut hashes = G1Projective::zero();
+                for (signature, hash) in items {
+                    let coefficient = Fr::from(rng.gen::<u128>());
+                    sigma += g1_from_variable(signature)? * coefficient;
+                    hashes += hash_to_g1(hash)? * coefficient;
+                }
+                Ok(fast_pairing_equality(
+                    &sigma.into_affine(),
+                    &G2Affine::generator(),
+                    &hashes.into_affine(),
+                    public_key,
+                ))
+            }
+        }
     }
 }
 
+/// Validate a compressed or uncompressed point, and compress it. The group is determined by its length.
+/// Points must be in the prime order subgroup, and not at infinity.
+#[cfg(not(feature = "blst"))]
+pub fn compress_point(point: &[u8]) -> Result<Vec<u8>> {
+    let is_compressed = crate::point::is_compressed(point);
+    let mut compressed = vec![];
+    match (point.len(), is_compressed) {
+        (48, true) | (96, false) => {
+            let p = if is_compressed {
+                G1Affine::deserialize_compressed(point)
+            } else {
+                G1Affine::deserialize_uncompressed(point)
+            }
+            .map_err(|_| anyhow!("invalid G1 point"))?;
+            if p.is_zero() {
+                return Err(anyhow!("G1 point is at infinity"));
+            }
+            p.serialize_compressed(&mut compressed)
+        }
+        (96, true) | (192, false) => {
+            let p = if is_compressed {
+                G2Affine::deserialize_compressed(point)
+            } else {
+                G2Affine::deserialize_uncompressed(point)
+            }
+            .map_err(|_| anyhow!("invalid G2 point"))?;
+            if p.is_zero() {
+                return Err(anyhow!("G2 point is at infinity"));
+            }
+            p.serialize_compressed(&mut compressed)
+        }
+        _ => return Err(anyhow!("invalid point length {}", point.len())),
+    }
+    .map_err(|_| anyhow!("serialization failed"))?;
+    Ok(compressed)
+}
+
 /// Check that signature is the actual aggregate of message and public key.
 /// Calculated by `e(g2, signature) == e(pk, hash)`.
 /// `signature` and `hash` are on G2, `public_key` is on G1.
-pub fn verify_g2_on_g1(signature: &[u8], hash: &[u8], public_key: &[u8]) -> Result<bool> {
+pub fn verify_g2_on_g1(signature: &[u8], hash: &[u8], public_key: &G1Affine) -> Result<bool> {
+    let hash_on_curve = hash_to_g2(hash)?;
+
+    let g1 = G1Affine::generator();
+    let sigma = g2_from_variable(signature).map_err(|e| anyhow!("verification Error: {}", e))?;
+    Ok(fast_pairing_equality(
+        &g1,
+        &sigma,
+        public_key,
+        &hash_on_curve,
+    ))
+}
+
+/// Check that signature is the actual aggregate of message and public key.
+/// Calculated by `e(g1, signature) == e(pk, hash)`.
+/// `signature` is on G1, `public_key` and `hash` are on G2.
+pub fn verify_g1_on_g2(signature: &[u8], hash: &[u8], public_key: &G2Affine) -> Result<bool> {
+    let hash_on_curve = hash_to_g1(hash)?;
+
+    let g2 = G2Affine::generator();
+    let sigma = g1_from_variable(signature).map_err(|e| anyhow!("verification Error: {}", e))?;
+    Ok(fast_pairing_equality(
+        &sigma,
+        &g2,
+        &hash_on_curve,
+        public_key,
+    ))
+}
+
+pub(crate) fn hash_to_g2(hash: &[u8]) -> Result<G2Affine> {
     let mapper = MapToCurveBasedHasher::<
         short_weierstrass::Projective<g2::Config>,
         DefaultFieldHasher<sha2::Sha256, 128>,
         WBMap<g2::Config>,
     >::new(DOMAIN)
-    .map_err(|_| anyhow!("cannot initialise mapper for sha2 to BLS12-381 G1"))?;
-    let hash_on_curve = G2Projective::from(
+    .map_err(|_| anyhow!("cannot initialise mapper for sha2 to BLS12-381 G2"))?;
+    Ok(G2Projective::from(
         mapper
             .hash(hash)
-            .map_err(|_| anyhow!("hash cannot be mapped to G1"))?,
+            .map_err(|_| anyhow!("hash cannot be mapped to G2"))?,
     )
-    .into_affine();
-
-    let g1 = G1Affine::generator();
-    let sigma = g2_from_variable(signature).map_err(|e| anyhow!("verification Error: {}", e))?;
-    let r = g1_from_variable(public_key).map_err(|e| anyhow!("verification Error: {}", e))?;
-    Ok(fast_pairing_equality(&g1, &sigma, &r, &hash_on_curve))
+    .into_affine())
 }
 
-/// Check that signature is the actual aggregate of message and public key.
-/// Calculated by `e(g1, signature) == e(pk, hash)`.
-/// `signature` is on G1, `public_key` and `hash` are on G2.
-pub fn verify_g1_on_g2(signature: &[u8], hash: &[u8], public_key: &[u8]) -> Result<bool> {
+pub(crate) fn hash_to_g1(hash: &[u8]) -> Result<G1Affine> {
     let mapper = MapToCurveBasedHasher::<
         short_weierstrass::Projective<g1::Config>,
         DefaultFieldHasher<sha2::Sha256, 128>,
         WBMap<g1::Config>,
     >::new(DOMAIN)
     .map_err(|_| anyhow!("cannot initialise mapper for sha2 to BLS12-381 G1"))?;
-    let hash_on_curve = G1Projective::from(
+    Ok(G1Projective::from(
         mapper
             .hash(hash)
             .map_err(|_| anyhow!("hash cannot be mapped to G1"))?,
     )
-    .into_affine();
-
-    let g2 = G2Affine::generator();
-    let sigma = g1_from_variable(signature).map_err(|e| anyhow!("verification Error: {}", e))?;
-    let s = g2_from_variable(public_key).map_err(|e| anyhow!("verification Error: {}", e))?;
-    Ok(fast_pairing_equality(&sigma, &g2, &hash_on_curve, &s))
+    .into_affine())
 }
 
 /// Checks if e(p, q) == e(r, s)
@@ -0,0 +1,181 @@
+/// Verify BLS Signatures used in drand with [blst](https://github.com/supranational/blst)
+/// It exposes the same interface as `bls_signatures`, and takes precedence over it for beacon verification and point compression when both the `blst` and `arkworks` features are enabled.
+use anyhow::{anyhow, Result};
+use blst::{blst_scalar, min_pk, min_sig, BLST_ERROR};
+use rand::RngCore;
+
+const DOMAIN: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Bits of the random coefficients used in batch verification.
+const RAND_BITS: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Deserialized public key, ready to be used for multiple verifications.
+pub enum PublicKey {
+    G1(min_pk::PublicKey),
+    G2(min_sig::PublicKey),
+}
+
+impl PublicKey {
+    /// Parse a compressed public key. The group is determined by its length.
+    pub fn from_compressed(public_key: &[u8]) -> Result<Self> {
+        // 48 is bytes of G1
+        if public_key.len() == 48 {
+            min_pk::PublicKey::key_validate(public_key)
+                .map(Self::G1)
+                .map_err(|e| anyhow!("deserialization failed: {e:?}"))
+        } else {
+            min_sig::PublicKey::key_validate(public_key)
+                .map(Self::G2)
+                .map_err(|e| anyhow!("deserialization failed: {e:?}"))
+        }
+    }
+
+    /// Check that signature is the actual aggregate of message and public key.
+    /// The signature is expected on the group opposite to the public key.
+    pub fn verify(&self, signature: &[u8], hash: &[u8]) -> Result<bool> {
+        let result = match self {
+            Self::G1(public_key) => min_pk::Signature::sig_validate(signature, true)
+                .map_err(|e| anyhow!("verification Error: {e:?}"))?
+                .verify(false, hash, DOMAIN, &[], public_key, false),
+            Self::G2(public_key) => min_sig::Signature::sig_validate(signature, true)
+                .map_err(|e| anyhow!("verification Error: {e:?}"))?
+                .verify(false, hash, DOMAIN, &[], public_key, false),
+        };
+        Ok(result == BLST_ERROR::BLST_SUCCESS)
+    }
+
+    /// Check that every `(signature, hash)` pair verifies, with a single final exponentiation overall.
+    /// Signatures are combined with random coefficients, so that invalid signatures cannot cancel each other out.
+    /// It does not tell which signature is invalid.
+    pub fn verify_batch(&self, items: &[(Vec<u8>, Vec<u8>)]) -> Result<bool> {
+        if items.is_empty() {
+            return Ok(true);
+        }
+        let hashes: Vec<&[u8]> = items.iter().map(|(_, hash)| hash.as_slice()).collect();
+        let rands = random_scalars(items.len());
+        let result = match self {
+            Self::G1(public_key) => {
+                let signatures = items
+                    .iter()
+                    .map(|(signature, _)| min_pk::Signature::sig_validate(signature, true))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| anyhow!("verification Error: {e:?}"))?;
+                min_pk::Signature::verify_multiple_aggregate_signatures(
+                    &hashes,
+                    DOMAIN,
+                    &vec![public_key; items.len()],
+                    false,
+                    &signatures.iter().collect::<Vec<_>>(),
+                    false,
+                    &rands,
+                    RAND_BITS,
+                )
+            }
+            Self::G2(public_key) => {
+                let signatures = items
+                    .iter()
+                    .map(|(signature, _)| min_sig::Signature::sig_validate(signature, true))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| anyhow!("verification Error: {e:?}"))?;
+                min_sig::Signature::verify_multiple_aggregate_signatures(
+                    &hashes,
+                    DOMAIN,
+                    &vec![public_key; items.len()],
+                    false,
+                    &signatures.iter().collect::<Vec<_>>(),
+                    false,
+                    &rands,
+                    RAND_BITS,
+                )
+            }
+        };
+        Ok(result == BLST_ERROR::BLST_SUCCESS)
+    }
+}
+
+/// Validate a compressed or uncompressed point, and compress it. The group is determined by its length.
+/// Points must be in the prime order subgroup, and not at infinity.
+pub fn compress_point(point: &[u8]) -> Result<Vec<u8>> {
+    // public keys of one variant are on the group of signatures of the other
+    match (point.len(), crate::point::is_compressed(point)) {
+        (48, true) | (96, false) => min_pk::PublicKey::key_validate(point)
+            .map(|p| p.compress().to_vec())
+            .map_err(|e| anyhow!("invalid G1 point: {e:?}")),
+        (96, true) | (192, false) => min_sig::PublicKey::key_validate(point)
+            .map(|p| p.compress().to_vec())
+            .map_err(|e| anyhow!("invalid G2 point: {e:?}")),
+        _ => Err(anyhow!("invalid point length {}", point.len())),
+    }
+}
+
+/// Non-zero random scalars of `RAND_BITS` bits. Bytes are little-endian.
+fn random_scalars(n: usize) -> Vec<blst_scalar> {
+    let mut rng = rand::thread_rng();
+    (0..n)
+        .map(|_| {
+            let mut scalar = blst_scalar::default();
+            while scalar.b[..RAND_BITS / 8].iter().all(|&b| b == 0) {
+                rng.fill_bytes(&mut scalar.b[..RAND_BITS / 8]);
+            }
+            scalar
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{
+        tests::{chained_beacon, invalid_beacon},
+        Message,
+    };
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    #[cfg(feature = "arkworks")]
+    #[test]
+    fn blst_matches_arkworks_works() {
+        use crate::beacon::tests::{unchained_beacon, unchained_beacon_on_g1};
+        use crate::chain::tests::{unchained_chain_info, unchained_chain_on_g1_info};
+
+        let fixtures = [
+            (chained_beacon(), chained_chain_info()),
+            (unchained_beacon(), unchained_chain_info()),
+            (unchained_beacon_on_g1(), unchained_chain_on_g1_info()),
+            (invalid_beacon(), chained_chain_info()),
+            // beacon verified against another chain
+            (unchained_beacon(), chained_chain_info()),
+        ];
+        for (beacon, info) in fixtures {
+            let public_key = PublicKey::from_compressed(&info.public_key()).unwrap();
+            let reference =
+                crate::bls_signatures::PublicKey::from_compressed(&info.public_key()).unwrap();
+            let (signature, hash) = (beacon.signature(), beacon.message().unwrap());
+            assert_eq!(
+                public_key.verify(signature, &hash).unwrap(),
+                reference.verify(signature, &hash).unwrap(),
+                "round {}",
+                beacon.round()
+            );
+        }
+    }
+
+    #[test]
+    fn blst_verify_batch_works() {
+        let info = chained_chain_info();
+        let public_key = PublicKey::from_compressed(&info.public_key()).unwrap();
+        let valid = (
+            chained_beacon().signature().to_vec(),
+            chained_beacon().message().unwrap(),
+        );
+        let invalid = (
+            invalid_beacon().signature().to_vec(),
+            invalid_beacon().message().unwrap(),
+        );
+
+        assert!(public_key.verify_batch(&[]).unwrap());
+        assert!(public_key.verify_batch(&[valid.clone()]).unwrap());
+        assert!(!public_key.verify_batch(&[valid, invalid]).unwrap());
+    }
+}
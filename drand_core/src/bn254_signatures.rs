@@ -0,0 +1,235 @@
+/// Verify BN254 BLS signatures used in drand evmnet with arkworks, which are designed to be cheap to verify on the EVM.
+/// Points are uncompressed and big-endian, as with the EVM precompiles: `x || y` on G1, and `x.c1 || x.c0 || y.c1 || y.c0` on G2.
+/// Messages are hashed to G1 following RFC 9380, with Keccak-256 and the Shallue-van de Woestijne map.
+use anyhow::{anyhow, Result};
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, One, PrimeField, Zero};
+use sha3::{Digest, Keccak256};
+
+const DOMAIN: &[u8] = b"BLS_SIG_BN254G1_XMD:KECCAK-256_SVDW_RO_NUL_";
+
+/// Length of a G1 point, which is the length of signatures.
+pub const SIGNATURE_LEN: usize = 64;
+/// Length of a G2 point, which is the length of public keys.
+pub const PUBLIC_KEY_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Deserialized public key on G2, ready to be used for multiple verifications.
+pub struct PublicKey(G2Affine);
+
+impl PublicKey {
+    /// Parse an uncompressed public key. Fails if it is not on the curve, not in the prime order subgroup, or at infinity.
+    pub fn from_bytes(public_key: &[u8]) -> Result<Self> {
+        Ok(Self(g2_from_bytes(public_key)?))
+    }
+
+    /// Check that `signature` on G1 is the signature of `message` by this key.
+    /// Calculated by `e(signature, g2) == e(hash, pk)`.
+    pub fn verify(&self, signature: &[u8], message: &[u8]) -> Result<bool> {
+        let signature = g1_from_bytes(signature)?;
+        let hash_on_curve = hash_to_g1(message)?;
+        Ok(Bn254::pairing(signature, G2Affine::generator())
+            == Bn254::pairing(hash_on_curve, self.0))
+    }
+}
+
+/// Message signed for `round`, which is the Keccak-256 of the round as a big-endian `u64`.
+pub fn message(round: u64) -> Vec<u8> {
+    Keccak256::digest(round.to_be_bytes()).to_vec()
+}
+
+pub(crate) fn hash_to_g1(message: &[u8]) -> Result<G1Affine> {
+    // L = ceil((ceil(log2(p)) + k) / 8), with k = 128 bits of security
+    const L: usize = 48;
+    let uniform = expand_message_xmd(message, DOMAIN, 2 * L)?;
+    let u0 = Fq::from_be_bytes_mod_order(&uniform[..L]);
+    let u1 = Fq::from_be_bytes_mod_order(&uniform[L..]);
+    // the cofactor of G1 is 1, so the sum needs no clearing
+    Ok((map_to_curve(u0) + map_to_curve(u1)).into_affine())
+}
+
+/// `expand_message_xmd` of RFC 9380, with Keccak-256.
+fn expand_message_xmd(message: &[u8], domain: &[u8], len: usize) -> Result<Vec<u8>> {
+    // output and block sizes of Keccak-256
+    const B_IN_BYTES: usize = 32;
+    const S_IN_BYTES: usize = 136;
+    let ell = (len + B_IN_BYTES - 1) / B_IN_BYTES;
+    if ell > 255 || len > u16::MAX as usize || domain.len() > 255 {
+        return Err(anyhow!("invalid expand_message_xmd parameters"));
+    }
+    let domain_prime = [domain, &[domain.len() as u8]].concat();
+
+    let b_0 = Keccak256::new()
+        .chain_update([0; S_IN_BYTES])
+        .chain_update(message)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0])
+        .chain_update(&domain_prime)
+        .finalize();
+    let mut b_i = Keccak256::new()
+        .chain_update(b_0)
+        .chain_update([1])
+        .chain_update(&domain_prime)
+        .finalize();
+    let mut uniform = b_i.to_vec();
+    for i in 2..=ell {
+        let xored: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = Keccak256::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&domain_prime)
+            .finalize();
+        uniform.extend_from_slice(&b_i);
+    }
+    uniform.truncate(len);
+    Ok(uniform)
+}
+
+/// Shallue-van de Woestijne map of RFC 9380 (section 6.6.1) for `y^2 = x^3 + 3`, with `Z = 1`.
+fn map_to_curve(u: Fq) -> G1Affine {
+    let z = Fq::one();
+    let b = Fq::from(3u64);
+    let g = |x: Fq| x.square() * x + b;
+
+    let c1 = g(z);
+    let c2 = -z / Fq::from(2u64);
+    let mut c3 = (-g(z) * z.square() * Fq::from(3u64))
+        .sqrt()
+        .expect("-g(Z) * 3Z^2 is a square on BN254");
+    if sgn0(&c3) {
+        c3 = -c3;
+    }
+    let c4 = -g(z) * Fq::from(4u64) / (z.square() * Fq::from(3u64));
+
+    let tv1 = u.square() * c1;
+    let tv2 = Fq::one() + tv1;
+    let tv1 = Fq::one() - tv1;
+    let tv3 = (tv1 * tv2).inverse().unwrap_or_else(Fq::zero);
+    let tv4 = u * tv1 * tv3 * c3;
+    let x1 = c2 - tv4;
+    let x2 = c2 + tv4;
+    let x3 = (tv2.square() * tv3).square() * c4 + z;
+
+    let x = if is_square(&g(x1)) {
+        x1
+    } else if is_square(&g(x2)) {
+        x2
+    } else {
+        x3
+    };
+    let mut y = g(x).sqrt().expect("one of x1, x2, and x3 is on the curve");
+    if sgn0(&u) != sgn0(&y) {
+        y = -y;
+    }
+    G1Affine::new_unchecked(x, y)
+}
+
+fn is_square(x: &Fq) -> bool {
+    x.legendre().is_qr() || x.is_zero()
+}
+
+/// Sign of a field element, which is its parity.
+fn sgn0(x: &Fq) -> bool {
+    x.into_bigint().is_odd()
+}
+
+/// Field element from 32 big-endian bytes. Fails if it is not lower than the modulus.
+fn fq_from_bytes(bytes: &[u8]) -> Result<Fq> {
+    let x = Fq::from_be_bytes_mod_order(bytes);
+    if x.into_bigint().to_bytes_be() != bytes {
+        return Err(anyhow!("invalid field element"));
+    }
+    Ok(x)
+}
+
+pub(crate) fn g1_from_bytes(bytes: &[u8]) -> Result<G1Affine> {
+    if bytes.len() != SIGNATURE_LEN {
+        return Err(anyhow!(
+            "G1 points are {SIGNATURE_LEN} bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let point = G1Affine::new_unchecked(fq_from_bytes(&bytes[..32])?, fq_from_bytes(&bytes[32..])?);
+    if point.is_zero() || !point.is_on_curve() {
+        return Err(anyhow!("point is not on G1"));
+    }
+    Ok(point)
+}
+
+pub(crate) fn g2_from_bytes(bytes: &[u8]) -> Result<G2Affine> {
+    if bytes.len() != PUBLIC_KEY_LEN {
+        return Err(anyhow!(
+            "G2 points are {PUBLIC_KEY_LEN} bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let coordinate = |offset: usize| -> Result<Fq2> {
+        Ok(Fq2::new(
+            fq_from_bytes(&bytes[offset + 32..offset + 64])?,
+            fq_from_bytes(&bytes[offset..offset + 32])?,
+        ))
+    };
+    let point = G2Affine::new_unchecked(coordinate(0)?, coordinate(64)?);
+    if point.is_zero() || !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve()
+    {
+        return Err(anyhow!("point is not on G2"));
+    }
+    Ok(point)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ark_bn254::Fr;
+
+    use super::*;
+
+    fn fq_to_bytes(x: &Fq) -> Vec<u8> {
+        x.into_bigint().to_bytes_be()
+    }
+
+    pub fn g1_to_bytes(point: &G1Affine) -> Vec<u8> {
+        [fq_to_bytes(&point.x), fq_to_bytes(&point.y)].concat()
+    }
+
+    pub fn g2_to_bytes(point: &G2Affine) -> Vec<u8> {
+        [
+            fq_to_bytes(&point.x.c1),
+            fq_to_bytes(&point.x.c0),
+            fq_to_bytes(&point.y.c1),
+            fq_to_bytes(&point.y.c0),
+        ]
+        .concat()
+    }
+
+    /// Public key and signature of `round` with a fixed secret key.
+    pub fn sign(round: u64) -> (Vec<u8>, Vec<u8>) {
+        let secret_key = Fr::from(0x5eed_u64);
+        let public_key = (G2Affine::generator() * secret_key).into_affine();
+        let signature = (hash_to_g1(&message(round)).unwrap() * secret_key).into_affine();
+        (g2_to_bytes(&public_key), g1_to_bytes(&signature))
+    }
+
+    #[test]
+    fn bn254_verify_works() {
+        let (public_key, signature) = sign(1);
+        let public_key = PublicKey::from_bytes(&public_key).unwrap();
+        assert!(public_key.verify(&signature, &message(1)).unwrap());
+        assert!(!public_key.verify(&signature, &message(2)).unwrap());
+
+        let (_, other) = sign(2);
+        assert!(!public_key.verify(&other, &message(1)).unwrap());
+        assert!(public_key.verify(&[0; 64], &message(1)).is_err());
+        assert!(PublicKey::from_bytes(&[0; 128]).is_err());
+        assert!(PublicKey::from_bytes(&[0xff; 128]).is_err());
+    }
+
+    #[test]
+    fn bn254_hash_to_g1_works() {
+        let point = hash_to_g1(&message(1)).unwrap();
+        assert!(point.is_on_curve());
+        assert_eq!(point, hash_to_g1(&message(1)).unwrap());
+        assert_ne!(point, hash_to_g1(&message(2)).unwrap());
+        assert_eq!(expand_message_xmd(b"", DOMAIN, 96).unwrap().len(), 96);
+    }
+}
@@ -0,0 +1,55 @@
+//! CBOR encoding of beacons and chain info, for constrained environments and content-addressed storage such as IPLD.
+//!
+//! Byte fields are CBOR byte strings, rather than the hex strings of JSON, which halves their size. Field names are the ones of the HTTP API.
+//! Any type of the crate which serializes to JSON serializes to CBOR, such as [`ApiBeacon`](crate::beacon::ApiBeacon), [`Proof`](crate::beacon::Proof), or [`ChainInfo`](crate::chain::ChainInfo).
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::Result;
+
+/// CBOR encoding of `value`.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    ciborium::ser::into_writer(value, &mut bytes).map_err(anyhow::Error::from)?;
+    Ok(bytes)
+}
+
+/// Value decoded from its CBOR encoding.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    Ok(ciborium::de::from_reader(bytes).map_err(anyhow::Error::from)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{
+        tests::{chained_beacon, unchained_beacon_on_g1},
+        ApiBeacon,
+    };
+    use crate::chain::{tests::chained_chain_info, Chain, ChainInfo};
+
+    use super::*;
+
+    #[test]
+    fn cbor_works() {
+        for beacon in [chained_beacon(), unchained_beacon_on_g1()] {
+            let bytes = to_vec(&beacon).unwrap();
+            assert!(bytes.len() < serde_json::to_vec(&beacon).unwrap().len() * 2 / 3);
+            assert_eq!(from_slice::<ApiBeacon>(&bytes).unwrap(), beacon);
+        }
+
+        let info = chained_chain_info();
+        let decoded: ChainInfo = from_slice(&to_vec(&info).unwrap()).unwrap();
+        assert_eq!(decoded, info);
+
+        // randomness beacons are encoded as their inner beacon
+        let beacon = Chain::new(info.clone()).unwrap().beacon(chained_beacon());
+        let decoded: ApiBeacon = from_slice(&to_vec(&beacon).unwrap()).unwrap();
+        assert_eq!(decoded, chained_beacon());
+
+        let proof = beacon.to_proof(&info);
+        let decoded: crate::beacon::Proof = from_slice(&to_vec(&proof).unwrap()).unwrap();
+        assert!(decoded.verify().unwrap());
+
+        assert!(from_slice::<ChainInfo>(&[0xff]).is_err());
+    }
+}
@@ -1,10 +1,25 @@
-use anyhow::Result;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::beacon::RandomnessBeacon;
+use crate::beacon::{chained_message, ApiBeacon, Message, RandomnessBeacon, VerifiedBeacon};
+#[cfg(not(feature = "blst"))]
+use crate::bls_signatures::PublicKey;
+#[cfg(feature = "blst")]
+use crate::blst_signatures::PublicKey;
+use crate::error::{Error, Result};
+use crate::point;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Additional information about the chain.
 pub struct ChainMetadata {
     #[serde(rename(serialize = "beaconID", deserialize = "beaconID"))]
@@ -22,31 +37,57 @@ impl ChainMetadata {
     }
 }
 
-impl PartialEq for ChainMetadata {
-    fn eq(&self, other: &Self) -> bool {
-        self.beacon_id == other.beacon_id
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainInfo {
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     public_key: Vec<u8>,
+    #[serde(deserialize_with = "deserialize_period")]
     period: u64,
     genesis_time: u64,
-    #[serde(with = "hex::serde")]
+    #[serde(with = "crate::hex_bytes")]
     hash: Vec<u8>,
     #[serde(
         rename(serialize = "groupHash", deserialize = "groupHash"),
-        with = "hex::serde"
+        with = "crate::hex_bytes"
     )]
     group_hash: Vec<u8>,
     #[serde(rename(serialize = "schemeID", deserialize = "schemeID"))]
     scheme_id: String,
     metadata: ChainMetadata,
+    #[serde(skip)]
+    prepared_public_key: PreparedPublicKey,
 }
 
 impl ChainInfo {
+    /// Info of a new chain, with its hash computed as drand does. `group_hash` is the seed the first round of a chained scheme signs over.
+    /// Fails if the period is 0, or the period or genesis time cannot be encoded in the hash.
+    pub fn new(
+        public_key: Vec<u8>,
+        period: u64,
+        genesis_time: u64,
+        group_hash: Vec<u8>,
+        scheme_id: String,
+        beacon_id: String,
+    ) -> Result<Self> {
+        if period == 0 {
+            return Err(Error::InvalidChainInfo);
+        }
+        let mut info = Self {
+            public_key,
+            period,
+            genesis_time,
+            hash: vec![],
+            group_hash,
+            scheme_id,
+            metadata: ChainMetadata::new(beacon_id),
+            prepared_public_key: PreparedPublicKey::default(),
+        };
+        info.hash = info
+            .compute_hash()
+            .ok_or_else(|| Error::Other(anyhow!("chain hash cannot be computed")))?;
+        Ok(info)
+    }
+
     /// Hex encoded BLS12-381 public key.
     pub fn public_key(&self) -> Vec<u8> {
         self.public_key.clone()
@@ -57,7 +98,7 @@ impl ChainInfo {
         self.period
     }
 
-    /// Time of the round 0 of the network (in epoch seconds).
+    /// Time at which the network emits its first round (in epoch seconds).
     pub fn genesis_time(&self) -> u64 {
         self.genesis_time
     }
@@ -72,6 +113,12 @@ impl ChainInfo {
         self.group_hash.clone()
     }
 
+    /// Seed the first round of a chained network signs over, in place of a previous signature.
+    /// drand uses the hash of the genesis group, which is [`ChainInfo::group_hash`] until the group is reshared.
+    pub fn genesis_seed(&self) -> Vec<u8> {
+        self.group_hash.clone()
+    }
+
     /// The version/format of cryptography.
     pub fn scheme_id(&self) -> String {
         self.scheme_id.clone()
@@ -85,8 +132,97 @@ impl ChainInfo {
     pub fn metadata(&self) -> ChainMetadata {
         self.metadata.clone()
     }
+
+    /// The ID of the beacon chain, such as `default` or `quicknet`. See [`ChainMetadata::beacon_id`].
+    pub fn beacon_id(&self) -> String {
+        self.metadata.beacon_id()
+    }
+
+    /// See [`Chain::time_of_round`].
+    pub(crate) fn time_of_round(&self, round: u64) -> u64 {
+        round
+            .saturating_sub(1)
+            .checked_mul(self.period)
+            .and_then(|elapsed| elapsed.checked_add(self.genesis_time))
+            .unwrap_or(u64::MAX)
+    }
+
+    /// See [`Chain::round_at`].
+    pub(crate) fn round_at(&self, unix_time: u64) -> u64 {
+        match unix_time.checked_sub(self.genesis_time) {
+            Some(elapsed) => elapsed / self.period + 1,
+            None => 0,
+        }
+    }
+
+    /// Recompute the chain hash from the rest of the info, and check it matches [`ChainInfo::hash`].
+    /// As in drand, the hash covers the period, genesis time, public key, group hash, and the beacon ID unless it is `default`. The scheme is not part of it.
+    pub fn verify_hash(&self) -> bool {
+        self.compute_hash().as_ref() == Some(&self.hash)
+    }
+
+    /// Chain hash derived from the rest of the info. It is `None` if the period or genesis time cannot be encoded as drand does.
+    pub(crate) fn compute_hash(&self) -> Option<Vec<u8>> {
+        let period = u32::try_from(self.period).ok()?;
+        let genesis_time = i64::try_from(self.genesis_time).ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(period.to_be_bytes());
+        hasher.update(genesis_time.to_be_bytes());
+        hasher.update(&self.public_key);
+        hasher.update(&self.group_hash);
+        // the default beacon ID is omitted, for backward compatibility with chains created before beacon IDs
+        if !self.metadata.beacon_id.is_empty() && self.metadata.beacon_id != "default" {
+            hasher.update(self.metadata.beacon_id.as_bytes());
+        }
+        Some(hasher.finalize().to_vec())
+    }
+
+    /// Public key prepared for `scheme`. It is parsed on first use, then shared by the clones of this info.
+    fn prepared_public_key(&self, scheme: Scheme) -> Result<Arc<ChainPublicKey>> {
+        if let Some(prepared) = self.prepared_public_key.0.load().as_ref() {
+            // the cache is only valid for the key and scheme it was prepared from
+            if prepared.public_key == self.public_key && prepared.scheme == scheme {
+                return Ok(prepared.key.clone());
+            }
+        }
+        let key = Arc::new(ChainPublicKey::new(&self.public_key, scheme)?);
+        self.prepared_public_key
+            .0
+            .store(Some(Arc::new(PreparedEntry {
+                public_key: self.public_key.clone(),
+                scheme,
+                key: key.clone(),
+            })));
+        Ok(key)
+    }
+}
+
+/// Period of a chain, which rounds and times are divided by. drand chains never have a period of 0.
+fn deserialize_period<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Err(de::Error::custom("chain period cannot be 0")),
+        period => Ok(period),
+    }
 }
 
+#[derive(Clone, Default)]
+/// Cache of the chain public key, parsed and ready to verify beacons, so that verifying with the same chain info only decompresses the key once.
+/// It is not part of the chain info: it is ignored when comparing, hashing, or serializing it.
+struct PreparedPublicKey(Arc<ArcSwapOption<PreparedEntry>>);
+
+struct PreparedEntry {
+    public_key: Vec<u8>,
+    scheme: Scheme,
+    key: Arc<ChainPublicKey>,
+}
+
+impl fmt::Debug for PreparedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreparedPublicKey")
+    }
+}
+
+/// Chain info is compared and hashed by its fields, leaving out the prepared public key cache.
 impl PartialEq for ChainInfo {
     fn eq(&self, other: &Self) -> bool {
         self.public_key == other.public_key
@@ -99,6 +235,425 @@ impl PartialEq for ChainInfo {
     }
 }
 
+impl Eq for ChainInfo {}
+
+impl Hash for ChainInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.public_key.hash(state);
+        self.period.hash(state);
+        self.genesis_time.hash(state);
+        self.hash.hash(state);
+        self.group_hash.hash(state);
+        self.scheme_id.hash(state);
+        self.metadata.hash(state);
+    }
+}
+
+/// Chain hash, then its scheme, beacon ID, period, genesis time, and public key, such as `chain 8990e7a9…, scheme pedersen-bls-chained, beacon ID default, period 30s, genesis time 1595431050, public key 868f005e…`.
+impl fmt::Display for ChainInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chain {}, scheme {}, beacon ID {}, period {}s, genesis time {}, public key {}",
+            hex::encode(&self.hash),
+            self.scheme_id,
+            self.metadata.beacon_id,
+            self.period,
+            self.genesis_time,
+            hex::encode(&self.public_key)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Cryptographic scheme used by a chain to produce its beacons.
+pub enum Scheme {
+    /// Signatures on G2, chained to the previous signature.
+    PedersenBlsChained,
+    /// Signatures on G2, only depending on the round.
+    PedersenBlsUnchained,
+    /// Signatures on G1, only depending on the round.
+    BlsUnchainedOnG1,
+    /// Signatures on G1 of BN254, only depending on the round. It is the scheme of drand evmnet, whose beacons are cheap to verify on the EVM.
+    /// Verifying it requires the `bn254` feature.
+    BlsBn254UnchainedOnG1,
+}
+
+impl Scheme {
+    /// Scheme identifier, as found in `ChainInfo::scheme_id`.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::PedersenBlsChained => "pedersen-bls-chained",
+            Self::PedersenBlsUnchained => "pedersen-bls-unchained",
+            Self::BlsUnchainedOnG1 => "bls-unchained-on-g1",
+            Self::BlsBn254UnchainedOnG1 => "bls-bn254-unchained-on-g1",
+        }
+    }
+
+    pub fn is_unchained(&self) -> bool {
+        !matches!(self, Self::PedersenBlsChained)
+    }
+
+    /// Length of beacon signatures, in bytes: 96 on G2, and 48 on G1. BN254 signatures are uncompressed, and 64 bytes.
+    pub fn signature_len(&self) -> usize {
+        match self {
+            Self::PedersenBlsChained | Self::PedersenBlsUnchained => 96,
+            Self::BlsUnchainedOnG1 => 48,
+            Self::BlsBn254UnchainedOnG1 => 64,
+        }
+    }
+
+    /// Whether beacons of this scheme are signed on BLS12-381. Otherwise, they are signed on BN254.
+    pub fn is_bls12_381(&self) -> bool {
+        !matches!(self, Self::BlsBn254UnchainedOnG1)
+    }
+
+    /// Whether `beacon` has the structure of beacons produced with this scheme: chained or not, with signatures of the expected length once compressed.
+    pub(crate) fn is_conforming(&self, beacon: &ApiBeacon) -> bool {
+        let is_chained = matches!(beacon, ApiBeacon::ChainedBeacon(_));
+        let signature_len = match self.is_bls12_381() {
+            true => point::compressed_len(beacon.signature()),
+            false => beacon.signature().len(),
+        };
+        let is_previous_signature_conforming = match beacon.previous_signature() {
+            None => true,
+            // the first round signs over the 32 bytes genesis seed
+            Some(previous_signature) if beacon.round() == 1 => previous_signature.len() == 32,
+            Some(previous_signature) => {
+                point::compressed_len(previous_signature) == self.signature_len()
+            }
+        };
+        is_chained != self.is_unchained()
+            && signature_len == self.signature_len()
+            && is_previous_signature_conforming
+    }
+
+    /// Message `beacon` signs under this scheme. BN254 beacons sign the Keccak-256 of the round, which is cheaper on the EVM than SHA-256.
+    fn message(&self, beacon: &ApiBeacon) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "bn254")]
+            Self::BlsBn254UnchainedOnG1 => Ok(crate::bn254_signatures::message(beacon.round())),
+            // drand signs over the compressed previous signature, whichever form it is served in
+            _ => match compressed_previous_signature(beacon)? {
+                Some(previous_signature) => chained_message(beacon.round(), &previous_signature),
+                None => beacon.message(),
+            },
+        }
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "pedersen-bls-chained" => Ok(Self::PedersenBlsChained),
+            "pedersen-bls-unchained" => Ok(Self::PedersenBlsUnchained),
+            "bls-unchained-on-g1" => Ok(Self::BlsUnchainedOnG1),
+            "bls-bn254-unchained-on-g1" => Ok(Self::BlsBn254UnchainedOnG1),
+            _ => Err(crate::Error::UnsupportedScheme {
+                scheme: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Handle on a chain, built from its info.
+/// It holds the chain scheme and a prepared public key, and verifies beacons regardless of where they have been retrieved from.
+pub struct Chain {
+    info: ChainInfo,
+    scheme: Scheme,
+    public_key: Arc<ChainPublicKey>,
+}
+
+#[derive(Debug, Clone)]
+/// Public key of a chain, on the curve of its scheme.
+enum ChainPublicKey {
+    Bls12_381(PublicKey),
+    #[cfg(feature = "bn254")]
+    Bn254(crate::bn254_signatures::PublicKey),
+}
+
+impl ChainPublicKey {
+    /// Fails if the public key is invalid, or on the wrong group for `scheme`.
+    fn new(public_key: &[u8], scheme: Scheme) -> Result<Self> {
+        match scheme {
+            #[cfg(feature = "bn254")]
+            Scheme::BlsBn254UnchainedOnG1 => Ok(Self::Bn254(
+                crate::bn254_signatures::PublicKey::from_bytes(public_key)?,
+            )),
+            #[cfg(not(feature = "bn254"))]
+            Scheme::BlsBn254UnchainedOnG1 => Err(Error::Other(anyhow!(
+                "scheme {scheme} requires the bn254 feature"
+            ))),
+            _ => {
+                let public_key = PublicKey::from_compressed(&point::compress(public_key)?)?;
+                // signatures are on the group opposite to the public key
+                let is_signature_on_g1 = matches!(public_key, PublicKey::G2(_));
+                if is_signature_on_g1 != (scheme.signature_len() == 48) {
+                    return Err(Error::Other(anyhow!(
+                        "public key does not match scheme {scheme}"
+                    )));
+                }
+                Ok(Self::Bls12_381(public_key))
+            }
+        }
+    }
+
+    fn verify(&self, signature: &[u8], message: &[u8]) -> Result<bool> {
+        match self {
+            Self::Bls12_381(public_key) => Ok(public_key.verify(signature, message)?),
+            #[cfg(feature = "bn254")]
+            Self::Bn254(public_key) => Ok(public_key.verify(signature, message)?),
+        }
+    }
+
+    fn verify_batch(&self, items: &[(Vec<u8>, Vec<u8>)]) -> Result<bool> {
+        match self {
+            Self::Bls12_381(public_key) => Ok(public_key.verify_batch(items)?),
+            // evmnet chains are verified one beacon at a time
+            #[cfg(feature = "bn254")]
+            Self::Bn254(public_key) => {
+                for (signature, message) in items {
+                    if !public_key.verify(signature, message)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+impl Chain {
+    /// Fails if the chain scheme is not supported, its period is 0, or its public key is invalid or on the wrong group for the scheme.
+    /// The public key is only parsed once for `info` and its clones, so that building handles from the same info is cheap.
+    pub fn new(info: ChainInfo) -> Result<Self> {
+        if info.period == 0 {
+            return Err(Error::InvalidChainInfo);
+        }
+        let scheme = Scheme::from_str(&info.scheme_id)?;
+        let public_key = info.prepared_public_key(scheme)?;
+        Ok(Self {
+            info,
+            scheme,
+            public_key,
+        })
+    }
+
+    pub fn info(&self) -> ChainInfo {
+        self.info.clone()
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Verify a beacon signature and randomness against this chain.
+    /// The scheme is the chain one. Beacons which do not have the structure of this scheme do not verify.
+    pub fn verify(&self, beacon: &RandomnessBeacon) -> Result<bool> {
+        self.verify_api_beacon(&beacon.beacon)
+    }
+
+    /// Verify a beacon as [`Chain::verify`] does, telling why it does not verify.
+    /// Fails with [`Error::InvalidBeacon`] if the beacon does not verify. Its [`VerificationError`] tells a chain misconfiguration from a forged beacon.
+    pub fn verify_detailed(&self, beacon: &RandomnessBeacon) -> Result<()> {
+        self.verify_api_beacon_detailed(&beacon.beacon)
+    }
+
+    pub(crate) fn verify_api_beacon(&self, beacon: &ApiBeacon) -> Result<bool> {
+        match self.verify_api_beacon_detailed(beacon) {
+            Ok(()) => Ok(true),
+            Err(Error::InvalidBeacon { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn verify_api_beacon_detailed(&self, beacon: &ApiBeacon) -> Result<()> {
+        let invalid = |reason| Error::InvalidBeacon {
+            round: beacon.round(),
+            reason,
+        };
+        if !self.scheme.is_conforming(beacon) {
+            return Err(invalid(VerificationError::SchemeMismatch {
+                scheme: self.scheme,
+            }));
+        }
+
+        let signature = compressed_signature(self.scheme, beacon)?;
+        if !self
+            .public_key
+            .verify(&signature, &self.scheme.message(beacon)?)?
+        {
+            return Err(invalid(VerificationError::InvalidSignature));
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(&signature);
+        if hasher.finalize().as_slice() != beacon.randomness() {
+            return Err(invalid(VerificationError::RandomnessMismatch));
+        }
+        Ok(())
+    }
+
+    /// Verify `beacon`, and keep the proof it verified. Fails with [`Error::InvalidBeacon`] if it does not verify.
+    pub fn verified(&self, beacon: RandomnessBeacon) -> Result<VerifiedBeacon> {
+        self.verify_detailed(&beacon)?;
+        Ok(VerifiedBeacon::new_unchecked(beacon))
+    }
+
+    /// Verify many beacons at once, which is far faster than verifying them one by one. It is `false` if any beacon does not verify, without telling which.
+    /// Their signatures are checked together with a random linear combination, so that invalid signatures cannot cancel each other out.
+    pub fn verify_batch(&self, beacons: &[RandomnessBeacon]) -> Result<bool> {
+        let mut items = Vec::with_capacity(beacons.len());
+        for beacon in beacons {
+            let beacon = &beacon.beacon;
+            if !self.scheme.is_conforming(beacon) {
+                return Ok(false);
+            }
+            let signature = compressed_signature(self.scheme, beacon)?;
+            if Sha256::digest(&signature).as_slice() != beacon.randomness() {
+                return Ok(false);
+            }
+            items.push((signature, self.scheme.message(beacon)?));
+        }
+        self.public_key.verify_batch(&items)
+    }
+
+    /// Verify consecutive beacons, ordered by round, and the link between them: each beacon previous signature must be the signature of the beacon before it.
+    /// The first round is linked to the chain genesis seed, so that chains starting at round 1 are validated from genesis.
+    /// Beacons are verified together, see [`Chain::verify_batch`]. Fails if the chain scheme is unchained, as its beacons are not linked.
+    pub fn verify_chain(&self, beacons: &[RandomnessBeacon]) -> Result<bool> {
+        if self.scheme.is_unchained() {
+            return Err(Error::Other(anyhow!(
+                "scheme {} does not link beacons",
+                self.scheme
+            )));
+        }
+        if let Some(first) = beacons.first().filter(|beacon| beacon.round() == 1) {
+            if first.previous_signature() != Some(self.info.genesis_seed().as_slice()) {
+                return Ok(false);
+            }
+        }
+        let is_linked = beacons
+            .windows(2)
+            .all(|pair| self.is_link(&pair[0], &pair[1]));
+        Ok(is_linked && self.verify_batch(beacons)?)
+    }
+
+    /// Whether `beacon` is the round after `previous`, and signs over its signature.
+    /// Signatures are compared compressed, as consecutive beacons may be served in different forms.
+    pub(crate) fn is_link(&self, previous: &RandomnessBeacon, beacon: &RandomnessBeacon) -> bool {
+        if previous.round().checked_add(1) != Some(beacon.round()) {
+            return false;
+        }
+        let Ok(signature) = compressed_signature(self.scheme, &previous.beacon) else {
+            return false;
+        };
+        let Ok(Some(previous_signature)) = compressed_previous_signature(&beacon.beacon) else {
+            return false;
+        };
+        previous_signature == signature
+    }
+
+    /// Time at which `round` is emitted (in epoch seconds).
+    /// As in drand, round 1 is emitted at genesis, and each following round one period after the previous one.
+    /// Times past `u64::MAX` saturate to it.
+    pub fn time_of_round(&self, round: u64) -> u64 {
+        self.info.time_of_round(round)
+    }
+
+    /// Latest round emitted at `unix_time` (in epoch seconds). It is 0 before the chain genesis.
+    pub fn round_at(&self, unix_time: u64) -> u64 {
+        self.info.round_at(unix_time)
+    }
+
+    /// Round at `unix_time` (in epoch seconds), `rounding` to the round before or after it.
+    /// Fails with [`Error::BeforeGenesis`] if `unix_time` is before the chain genesis.
+    pub fn round_at_rounded(&self, unix_time: u64, rounding: RoundingMode) -> Result<u64> {
+        let Some(elapsed) = unix_time.checked_sub(self.info.genesis_time) else {
+            return Err(BeforeGenesisError {
+                unix_time,
+                genesis_time: self.info.genesis_time,
+            }
+            .into());
+        };
+        let round = self.round_at(unix_time);
+        match rounding {
+            RoundingMode::RoundUp if elapsed % self.info.period != 0 => Ok(round + 1),
+            _ => Ok(round),
+        }
+    }
+
+    /// Attach its emission time to a beacon retrieved from this chain.
+    pub(crate) fn beacon(&self, beacon: ApiBeacon) -> RandomnessBeacon {
+        let time = self.time_of_round(beacon.round());
+        RandomnessBeacon::new(beacon, time)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How a time falling between two rounds is mapped to a round.
+pub enum RoundingMode {
+    /// Last round at or before the time.
+    #[default]
+    RoundDown,
+    /// First round at or after the time. Timelock encryption needs this one, as the round must not be emitted before the time.
+    RoundUp,
+}
+
+#[derive(Debug)]
+/// Time requested is before the chain genesis, when no round has been emitted yet.
+pub struct BeforeGenesisError {
+    pub unix_time: u64,
+    pub genesis_time: u64,
+}
+
+impl fmt::Display for BeforeGenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time {} is before chain genesis at {}",
+            self.unix_time, self.genesis_time
+        )
+    }
+}
+
+impl std::error::Error for BeforeGenesisError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Reason a beacon does not verify against a chain.
+pub enum VerificationError {
+    /// Beacon does not have the structure of beacons of the chain `scheme`, chained or not, and with a signature on the expected group.
+    /// The beacon is most likely from another chain, which points to a misconfigured chain rather than to a forged beacon.
+    SchemeMismatch { scheme: Scheme },
+    /// Signature does not verify against the chain public key.
+    InvalidSignature,
+    /// Randomness is not the hash of the signature.
+    RandomnessMismatch,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SchemeMismatch { scheme } => {
+                write!(f, "beacon does not match chain scheme {scheme}")
+            }
+            Self::InvalidSignature => write!(f, "signature does not verify"),
+            Self::RandomnessMismatch => write!(f, "randomness is not the hash of the signature"),
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
 #[derive(Debug, Clone)]
 /// Retrieval and validation options when interacting with a chain.
 /// This controls beacons validation, chain validation, and cache on retrieval.
@@ -140,18 +695,63 @@ impl Default for ChainOptions {
     }
 }
 
+/// Signature of `beacon` in compressed form, which is the one drand signs over and hashes into the randomness.
+/// BN254 signatures are kept uncompressed, as the EVM verifies them.
+fn compressed_signature(scheme: Scheme, beacon: &ApiBeacon) -> Result<Vec<u8>> {
+    let signature = beacon.signature();
+    if !scheme.is_bls12_381() || point::is_compressed(signature) {
+        Ok(signature.to_vec())
+    } else {
+        point::compress(signature)
+    }
+}
+
+/// Previous signature of `beacon` in compressed form, which is the one drand chains beacons with. It is `None` for unchained beacons.
+/// The genesis seed the first round signs over is not a point, and is kept as is.
+fn compressed_previous_signature(beacon: &ApiBeacon) -> Result<Option<Vec<u8>>> {
+    match beacon.previous_signature() {
+        Some(previous_signature)
+            if beacon.round() != 1 && !point::is_compressed(previous_signature) =>
+        {
+            Ok(Some(point::compress(previous_signature)?))
+        }
+        previous_signature => Ok(previous_signature.map(<[u8]>::to_vec)),
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Parameters that can be used to validate a chain is the expected one.
 pub struct ChainVerification {
     hash: Option<Vec<u8>>,
     public_key: Option<Vec<u8>>,
+    beacon_id: Option<String>,
+    is_hash_checked: bool,
 }
 
 impl ChainVerification {
     pub fn new(hash: Option<Vec<u8>>, public_key: Option<Vec<u8>>) -> Self {
-        Self { hash, public_key }
+        Self {
+            hash,
+            public_key,
+            beacon_id: None,
+            is_hash_checked: false,
+        }
+    }
+
+    /// Only accept a chain whose beacon ID is `beacon_id`, such as `default` or `quicknet`.
+    pub fn with_beacon_id(mut self, beacon_id: &str) -> Self {
+        self.beacon_id = Some(beacon_id.to_string());
+        self
+    }
+
+    /// Also recompute the chain hash from the rest of the info when no hash is pinned, rejecting internally inconsistent chain info. See [`ChainInfo::verify_hash`].
+    /// A pinned hash is always recomputed, otherwise a relay could serve it along another public key.
+    pub fn with_hash_check(mut self) -> Self {
+        self.is_hash_checked = true;
+        self
     }
 
+    /// Whether `info` matches the expected hash, public key, and beacon ID if any, and is consistent with its own hash if a hash is pinned or checked.
     pub fn verify(&self, info: &ChainInfo) -> bool {
         let ok_hash = match &self.hash {
             Some(h) => info.hash == *h,
@@ -161,7 +761,13 @@ impl ChainVerification {
             Some(pk) => info.public_key == *pk,
             None => true,
         };
-        ok_hash && ok_public_key
+        let ok_beacon_id = match &self.beacon_id {
+            Some(beacon_id) => info.metadata.beacon_id == *beacon_id,
+            None => true,
+        };
+        let is_hash_checked = self.is_hash_checked || self.hash.is_some();
+        let ok_self_hash = !is_hash_checked || info.verify_hash();
+        ok_self_hash && ok_hash && ok_public_key && ok_beacon_id
     }
 }
 
@@ -174,6 +780,7 @@ impl Default for ChainVerification {
 impl From<ChainInfo> for ChainVerification {
     fn from(info: ChainInfo) -> Self {
         Self::new(Some(info.hash()), Some(info.public_key()))
+            .with_beacon_id(&info.metadata.beacon_id)
     }
 }
 
@@ -194,8 +801,18 @@ trait ChainClient {
 
 #[cfg(test)]
 pub mod tests {
+    use crate::beacon::tests::{
+        chained_beacon, chained_beacon_1, invalid_beacon, unchained_beacon, unchained_beacon_on_g1,
+    };
+
     use super::*;
 
+    /// Update the hash of chain `info` modified by a test, so that it stays consistent.
+    pub fn rehash(info: &mut serde_json::Value) {
+        let parsed: ChainInfo = serde_json::from_value(info.clone()).unwrap();
+        info["hash"] = hex::encode(parsed.compute_hash().unwrap()).into();
+    }
+
     /// drand mainnet (curl -sS https://drand.cloudflare.com/info)
     pub fn chained_chain_info() -> ChainInfo {
         serde_json::from_str(r#"{
@@ -253,10 +870,8 @@ pub mod tests {
         // Validate only the hash
         let hash_verification = ChainVerification::new(Some(chained_chain_info().hash()), None);
         assert!(hash_verification.verify(&chained_chain_info()));
-        let hash_verification = ChainVerification::new(Some(chained_chain_info().hash()), None);
-        let mut chain_info = chained_chain_info();
-        chain_info.public_key = unchained_chain_info().public_key();
-        assert!(hash_verification.verify(&chain_info));
+        let hash_verification = ChainVerification::new(Some(unchained_chain_info().hash()), None);
+        assert!(hash_verification.verify(&unchained_chain_info()));
 
         // Validate only the public key
         let public_key_verification =
@@ -271,6 +886,299 @@ pub mod tests {
         assert!(no_verification.verify(&chained_chain_info()));
     }
 
+    #[test]
+    fn chain_handle_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        assert_eq!(chain.scheme(), Scheme::PedersenBlsChained);
+        assert_eq!(chain.time_of_round(1000000), 1625431020);
+        assert_eq!(chain.round_at(1625431020), 1000000);
+        assert_eq!(chain.round_at(1625431049), 1000000);
+        // round 1 is emitted at genesis
+        assert_eq!(chain.time_of_round(1), 1595431050);
+        assert_eq!(chain.round_at(1595431050), 1);
+        assert_eq!(chain.round_at(1595431049), 0);
+        assert_eq!(chain.round_at(1), 0);
+        assert_eq!(
+            chain
+                .round_at_rounded(1625431020, RoundingMode::RoundUp)
+                .unwrap(),
+            1000000
+        );
+        assert_eq!(
+            chain
+                .round_at_rounded(1625431021, RoundingMode::RoundDown)
+                .unwrap(),
+            1000000
+        );
+        assert_eq!(
+            chain
+                .round_at_rounded(1625431021, RoundingMode::RoundUp)
+                .unwrap(),
+            1000001
+        );
+        let err = chain
+            .round_at_rounded(1, RoundingMode::RoundDown)
+            .unwrap_err();
+        assert!(matches!(err, Error::BeforeGenesis(_)));
+        assert_eq!(
+            Chain::new(unchained_chain_on_g1_info()).unwrap().scheme(),
+            Scheme::BlsUnchainedOnG1
+        );
+
+        let mut chain_info = chained_chain_info();
+        chain_info.scheme_id = "unknown-scheme".to_string();
+        assert!(Chain::new(chain_info).is_err());
+
+        // far rounds do not overflow
+        assert_eq!(chain.time_of_round(u64::MAX), u64::MAX);
+
+        // rounds cannot be computed without a period
+        let mut chain_info = chained_chain_info();
+        chain_info.period = 0;
+        assert!(matches!(
+            Chain::new(chain_info.clone()),
+            Err(Error::InvalidChainInfo)
+        ));
+        let json = serde_json::to_string(&chain_info).unwrap();
+        assert!(serde_json::from_str::<ChainInfo>(&json).is_err());
+        assert!(matches!(
+            ChainInfo::new(
+                chain_info.public_key(),
+                0,
+                chain_info.genesis_time(),
+                chain_info.group_hash(),
+                chain_info.scheme_id(),
+                chain_info.beacon_id(),
+            ),
+            Err(Error::InvalidChainInfo)
+        ));
+    }
+
+    #[test]
+    fn chain_prepared_public_key_works() {
+        let info = chained_chain_info();
+        assert!(info.prepared_public_key.0.load().is_none());
+        let chain = Chain::new(info.clone()).unwrap();
+        // clones share the key prepared for the first one
+        let prepared = info.prepared_public_key.0.load_full().unwrap();
+        assert!(Arc::ptr_eq(&prepared.key, &chain.public_key));
+        let other = Chain::new(info.clone()).unwrap();
+        assert!(Arc::ptr_eq(&other.public_key, &chain.public_key));
+        assert!(chain.verify(&chain.beacon(chained_beacon())).unwrap());
+        assert_eq!(info, chained_chain_info());
+
+        // a modified clone does not use the key prepared for the original
+        let mut modified = info.clone();
+        modified.public_key = unchained_chain_on_g1_info().public_key();
+        assert!(Chain::new(modified.clone()).is_err());
+        modified.scheme_id = Scheme::BlsUnchainedOnG1.id().to_string();
+        let modified = Chain::new(modified).unwrap();
+        assert!(!Arc::ptr_eq(&modified.public_key, &chain.public_key));
+        assert!(!modified.verify(&chain.beacon(chained_beacon())).unwrap());
+    }
+
+    #[test]
+    fn chain_scheme_conformance_works() {
+        let chained = Chain::new(chained_chain_info()).unwrap();
+        assert!(chained.verify_api_beacon(&chained_beacon()).unwrap());
+        assert!(!chained.verify_api_beacon(&unchained_beacon()).unwrap());
+
+        // an unchained beacon is not accepted on the G1 chain, nor a chained one on an unchained chain
+        let unchained = Chain::new(unchained_chain_info()).unwrap();
+        assert!(unchained.verify_api_beacon(&unchained_beacon()).unwrap());
+        assert!(!unchained.verify_api_beacon(&chained_beacon()).unwrap());
+        let on_g1 = Chain::new(unchained_chain_on_g1_info()).unwrap();
+        assert!(on_g1.verify_api_beacon(&unchained_beacon_on_g1()).unwrap());
+        assert!(!on_g1.verify_api_beacon(&unchained_beacon()).unwrap());
+
+        // the previous signature has to be a signature of the scheme, or the genesis seed for the first round
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["previous_signature"] = hex::encode([0x80; 48]).into();
+        let beacon = serde_json::from_value(beacon).unwrap();
+        assert!(!Scheme::PedersenBlsChained.is_conforming(&beacon));
+        assert!(!chained.verify_api_beacon(&beacon).unwrap());
+        let mut beacon = serde_json::to_value(chained_beacon_1()).unwrap();
+        beacon["previous_signature"] = chained_beacon()
+            .previous_signature()
+            .map(hex::encode)
+            .into();
+        assert!(!chained
+            .verify_api_beacon(&serde_json::from_value(beacon).unwrap())
+            .unwrap());
+
+        // the public key has to be on the group the scheme expects
+        let mut chain_info = unchained_chain_on_g1_info();
+        chain_info.public_key = unchained_chain_info().public_key();
+        assert!(Chain::new(chain_info).is_err());
+    }
+
+    #[cfg(feature = "bn254")]
+    #[test]
+    fn chain_bn254_works() {
+        use crate::bn254_signatures::tests::sign;
+
+        let (public_key, signature) = sign(1000);
+        let info = ChainInfo::new(
+            public_key,
+            3,
+            1727521075,
+            vec![0; 32],
+            "bls-bn254-unchained-on-g1".to_string(),
+            "evmnet".to_string(),
+        )
+        .unwrap();
+        let chain = Chain::new(info).unwrap();
+        assert_eq!(chain.scheme(), Scheme::BlsBn254UnchainedOnG1);
+
+        let beacon = chain.beacon(ApiBeacon::from_signature(1000, signature.clone(), None));
+        assert!(chain.verify(&beacon).unwrap());
+        assert!(chain.verify_batch(&[beacon]).unwrap());
+        let other = chain.beacon(ApiBeacon::from_signature(1001, signature, None));
+        assert!(!chain.verify(&other).unwrap());
+
+        // BLS12-381 beacons do not conform to the scheme
+        assert!(!chain.verify_api_beacon(&unchained_beacon_on_g1()).unwrap());
+        let mut chain_info = unchained_chain_info();
+        chain_info.scheme_id = "bls-bn254-unchained-on-g1".to_string();
+        assert!(Chain::new(chain_info).is_err());
+    }
+
+    #[test]
+    fn chain_verify_batch_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        let beacons: Vec<RandomnessBeacon> = [chained_beacon(), chained_beacon_1()]
+            .into_iter()
+            .map(|beacon| chain.beacon(beacon))
+            .collect();
+        assert!(chain.verify_batch(&beacons).unwrap());
+        assert!(chain.verify_batch(&[]).unwrap());
+
+        // a single invalid beacon fails the whole batch
+        let mut beacons = beacons;
+        beacons.push(chain.beacon(invalid_beacon()));
+        assert!(!chain.verify_batch(&beacons).unwrap());
+
+        let on_g1 = Chain::new(unchained_chain_on_g1_info()).unwrap();
+        let beacon = on_g1.beacon(unchained_beacon_on_g1());
+        assert!(on_g1.verify_batch(&[beacon]).unwrap());
+    }
+
+    #[test]
+    fn chain_verify_chain_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        assert!(chain.verify_chain(&[]).unwrap());
+        assert!(chain
+            .verify_chain(&[chain.beacon(chained_beacon())])
+            .unwrap());
+
+        // both beacons verify, but they are not consecutive
+        let beacons = [
+            chain.beacon(chained_beacon_1()),
+            chain.beacon(chained_beacon()),
+        ];
+        assert!(!chain.verify_chain(&beacons).unwrap());
+
+        let unchained = Chain::new(unchained_chain_info()).unwrap();
+        assert!(unchained
+            .verify_chain(&[unchained.beacon(unchained_beacon())])
+            .is_err());
+    }
+
+    #[test]
+    fn chain_genesis_seed_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        assert_eq!(
+            chained_beacon_1().previous_signature(),
+            Some(chained_chain_info().genesis_seed().as_slice())
+        );
+        assert!(chain
+            .verify_chain(&[chain.beacon(chained_beacon_1())])
+            .unwrap());
+
+        // the first round does not link to another genesis
+        let mut info = chained_chain_info();
+        info.group_hash = unchained_chain_info().group_hash();
+        let chain = Chain::new(info).unwrap();
+        assert!(!chain
+            .verify_chain(&[chain.beacon(chained_beacon_1())])
+            .unwrap());
+    }
+
+    #[test]
+    fn chain_info_verify_hash_works() {
+        for info in [
+            chained_chain_info(),
+            unchained_chain_info(),
+            unchained_chain_on_g1_info(),
+        ] {
+            assert!(info.verify_hash(), "{}", info.metadata().beacon_id());
+        }
+
+        // a relay serving another public key under the chain hash
+        let mut info = chained_chain_info();
+        info.public_key = unchained_chain_info().public_key();
+        assert!(!info.verify_hash());
+        assert!(ChainVerification::default().verify(&info));
+        assert!(!ChainVerification::default().with_hash_check().verify(&info));
+        assert!(ChainVerification::default()
+            .with_hash_check()
+            .verify(&chained_chain_info()));
+
+        let mut info = unchained_chain_info();
+        info.period = 30;
+        assert!(!info.verify_hash());
+    }
+
+    #[test]
+    fn chain_info_display_works() {
+        assert_eq!(
+            chained_chain_info().to_string(),
+            "chain 8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce, scheme pedersen-bls-chained, beacon ID default, period 30s, genesis time 1595431050, public key 868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31"
+        );
+    }
+
+    #[test]
+    fn chain_verification_beacon_id_works() {
+        assert_eq!(chained_chain_info().beacon_id(), "default");
+        assert_eq!(unchained_chain_on_g1_info().beacon_id(), "fastnet");
+
+        let verification = ChainVerification::default().with_beacon_id("default");
+        assert!(verification.verify(&chained_chain_info()));
+        assert!(!verification.verify(&unchained_chain_info()));
+
+        let verification = ChainVerification::from(unchained_chain_info());
+        assert!(verification.verify(&unchained_chain_info()));
+    }
+
+    #[test]
+    fn chain_verify_detailed_works() {
+        let chain = Chain::new(chained_chain_info()).unwrap();
+        assert!(chain
+            .verify_detailed(&chain.beacon(chained_beacon()))
+            .is_ok());
+
+        let reason = |beacon| match chain.verify_detailed(&chain.beacon(beacon)) {
+            Err(Error::InvalidBeacon { reason, .. }) => reason,
+            result => panic!("Beacon should not verify, got {result:?}"),
+        };
+        assert_eq!(
+            reason(unchained_beacon()),
+            VerificationError::SchemeMismatch {
+                scheme: Scheme::PedersenBlsChained
+            }
+        );
+        assert_eq!(
+            reason(invalid_beacon()),
+            VerificationError::InvalidSignature
+        );
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["randomness"] = hex::encode([0u8; 32]).into();
+        assert_eq!(
+            reason(serde_json::from_value(beacon).unwrap()),
+            VerificationError::RandomnessMismatch
+        );
+    }
+
     #[test]
     fn chain_verification_failure_works() {
         // Full validation should fail when public key is invalid
@@ -294,5 +1202,11 @@ pub mod tests {
         let public_key_verification =
             ChainVerification::new(None, Some(unchained_chain_info().public_key()));
         assert!(!public_key_verification.verify(&chained_chain_info()));
+
+        // A pinned hash is recomputed, so that the relay cannot swap the public key
+        let hash_verification = ChainVerification::new(Some(chained_chain_info().hash()), None);
+        let mut chain_info = chained_chain_info();
+        chain_info.public_key = unchained_chain_info().public_key();
+        assert!(!hash_verification.verify(&chain_info));
     }
 }
@@ -0,0 +1,107 @@
+//! Circuit breaker isolating failing relays.
+
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::error::Result;
+
+enum State {
+    Closed { failures: u32 },
+    // a single probe is let through every `cooldown`, until one succeeds
+    Open { probe_at: Instant },
+}
+
+/// Stops sending requests to a relay after `failure_threshold` consecutive failures.
+/// Once `cooldown` has elapsed, a single probe request is let through. The relay is used again if it succeeds, and kept aside for another `cooldown` otherwise.
+pub(crate) struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether a request can be sent to the relay now.
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { probe_at } if Instant::now() >= probe_at => {
+                *state = State::Open {
+                    probe_at: Instant::now() + self.cooldown,
+                };
+                true
+            }
+            State::Open { .. } => false,
+        }
+    }
+
+    /// Run `request`, recording its outcome.
+    pub async fn guard<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        let result = request.await;
+        self.record(&result);
+        result
+    }
+
+    pub fn record<T>(&self, result: &Result<T>) {
+        let mut state = self.state.lock().unwrap();
+        match (&*state, result) {
+            (_, Ok(_)) => *state = State::Closed { failures: 0 },
+            (State::Closed { failures }, Err(_)) if failures + 1 < self.failure_threshold => {
+                *state = State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (State::Closed { .. }, Err(_)) => {
+                *state = State::Open {
+                    probe_at: Instant::now() + self.cooldown,
+                }
+            }
+            // the failed probe already pushed the next one back
+            (State::Open { .. }, Err(_)) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::error::Error;
+
+    use super::*;
+
+    #[test]
+    fn circuit_breaker_works() {
+        let failure: Result<()> = Err(Error::Other(anyhow!("relay is down")));
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        breaker.record(&failure);
+        assert!(breaker.allow());
+        breaker.record(&failure);
+        assert!(!breaker.allow());
+
+        // half-open: a single probe is let through, and fails
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+        breaker.record(&failure);
+        assert!(!breaker.allow());
+
+        // the next probe succeeds, and the relay is used again
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.allow());
+        breaker.record(&Ok(()));
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,124 @@
+//! Weighted selection of a committee, such as validators selected proportionally to their stake.
+//!
+//! Members are drawn one by one without replacement: each seat goes to a remaining candidate with probability proportional to its weight.
+//! Weights are integers, so that every party computes the same committee. Fractional weights, such as stakes, are expressed in their smallest unit.
+
+use anyhow::anyhow;
+
+use crate::{
+    beacon::RandomnessBeacon,
+    draw::DrawRng,
+    error::{Error, Result},
+};
+
+/// Indices of the `size` candidates selected from `weights`, in the order their seat was drawn.
+/// Candidates with a weight of 0 are never selected. Fails if fewer than `size` candidates have a positive weight, or if the total weight exceeds [`u64::MAX`].
+pub fn select_committee(
+    beacon: &RandomnessBeacon,
+    weights: &[u64],
+    size: usize,
+) -> Result<Vec<usize>> {
+    select_committee_with(&mut DrawRng::new(beacon), weights, size)
+}
+
+/// Whether `committee` is the one [`select_committee`] selects from `weights` with `beacon`, so that third parties can check a published committee.
+pub fn verify_committee(
+    beacon: &RandomnessBeacon,
+    weights: &[u64],
+    committee: &[usize],
+) -> Result<bool> {
+    Ok(select_committee(beacon, weights, committee.len())? == committee)
+}
+
+pub(crate) fn select_committee_with(
+    rng: &mut DrawRng,
+    weights: &[u64],
+    size: usize,
+) -> Result<Vec<usize>> {
+    let candidates = weights.iter().filter(|&&weight| weight > 0).count();
+    if size > candidates {
+        return Err(Error::Other(anyhow!(
+            "cannot select {size} members among {candidates} candidates with a positive weight"
+        )));
+    }
+    let mut total = weights
+        .iter()
+        .try_fold(0u64, |total, &weight| total.checked_add(weight))
+        .ok_or_else(|| Error::Other(anyhow!("total weight exceeds {}", u64::MAX)))?;
+
+    let mut remaining = weights.to_vec();
+    let mut committee = Vec::with_capacity(size);
+    for _ in 0..size {
+        // the seat goes to the candidate whose cumulative weight range contains the draw
+        let mut draw = rng.below(total);
+        let selected = remaining
+            .iter()
+            .position(|&weight| {
+                if draw < weight {
+                    return true;
+                }
+                draw -= weight;
+                false
+            })
+            .expect("draw is below the total weight");
+        total -= remaining[selected];
+        remaining[selected] = 0;
+        committee.push(selected);
+    }
+    Ok(committee)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::{tests::chained_chain_info, Chain};
+
+    use super::*;
+
+    fn beacon() -> RandomnessBeacon {
+        Chain::new(chained_chain_info())
+            .unwrap()
+            .beacon(chained_beacon())
+    }
+
+    #[test]
+    fn select_committee_works() {
+        let weights = [10, 0, 25, 5, 60, 0, 1];
+        let committee = select_committee(&beacon(), &weights, 4).unwrap();
+        assert_eq!(committee.len(), 4);
+        assert!(committee.iter().all(|&i| weights[i] > 0));
+        let mut distinct = committee.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4);
+
+        assert!(verify_committee(&beacon(), &weights, &committee).unwrap());
+        let mut tampered = committee.clone();
+        tampered.swap(0, 1);
+        assert!(!verify_committee(&beacon(), &weights, &tampered).unwrap());
+
+        // every candidate with a positive weight is selected
+        let mut everyone = select_committee(&beacon(), &weights, 5).unwrap();
+        everyone.sort();
+        assert_eq!(everyone, vec![0, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn select_committee_failure_works() {
+        assert!(select_committee(&beacon(), &[1, 0, 1], 3).is_err());
+        assert!(select_committee(&beacon(), &[u64::MAX, 1], 1).is_err());
+        assert!(select_committee(&beacon(), &[u64::MAX, 0], 1).is_ok());
+    }
+
+    #[test]
+    fn select_committee_is_proportional_works() {
+        // the first seat goes to the candidate of weight 3 three times out of four
+        let selected = (0u32..4_000)
+            .filter(|i| {
+                let mut rng = DrawRng::from_seed(&i.to_be_bytes());
+                select_committee_with(&mut rng, &[1, 3], 1).unwrap() == [1]
+            })
+            .count();
+        assert!((2_900..3_100).contains(&selected));
+    }
+}
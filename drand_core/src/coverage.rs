@@ -0,0 +1,192 @@
+//! Ordering and gap detection over collections of beacons, such as archives being audited or backfilled.
+//!
+//! Beacons are identified by their round only. They are not verified, nor compared: two beacons of the same round are duplicates even if they differ.
+//! Missing rounds are reported as ranges, as a chain emits millions of rounds.
+
+use std::ops::RangeInclusive;
+
+use crate::beacon::RandomnessBeacon;
+
+/// Sort `beacons` by round, keeping duplicates in their original order.
+pub fn sort_by_round(beacons: &mut [RandomnessBeacon]) {
+    beacons.sort_by_key(RandomnessBeacon::round);
+}
+
+/// Whether `beacons` are sorted by strictly increasing round, which excludes duplicates.
+pub fn is_sorted_by_round(beacons: &[RandomnessBeacon]) -> bool {
+    beacons
+        .windows(2)
+        .all(|pair| pair[0].round() < pair[1].round())
+}
+
+/// Rounds of `beacons` found more than once, in increasing order.
+pub fn duplicate_rounds(beacons: &[RandomnessBeacon]) -> Vec<u64> {
+    let mut duplicates: Vec<u64> = sorted_rounds(beacons)
+        .windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect();
+    duplicates.dedup();
+    duplicates
+}
+
+/// Ranges of rounds of `rounds` which have no beacon in `beacons`, in increasing order.
+pub fn missing_rounds(
+    beacons: &[RandomnessBeacon],
+    rounds: RangeInclusive<u64>,
+) -> Vec<RangeInclusive<u64>> {
+    let mut missing = vec![];
+    if rounds.is_empty() {
+        return missing;
+    }
+    let mut next = *rounds.start();
+    for round in sorted_rounds(beacons) {
+        if round < next || !rounds.contains(&round) {
+            continue;
+        }
+        if round > next {
+            missing.push(next..=round - 1);
+        }
+        match round.checked_add(1) {
+            Some(round) => next = round,
+            None => return missing,
+        }
+    }
+    if next <= *rounds.end() {
+        missing.push(next..=*rounds.end());
+    }
+    missing
+}
+
+fn sorted_rounds(beacons: &[RandomnessBeacon]) -> Vec<u64> {
+    let mut rounds: Vec<u64> = beacons.iter().map(RandomnessBeacon::round).collect();
+    rounds.sort_unstable();
+    rounds
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Summary of the rounds of a range covered by a collection of beacons.
+pub struct Coverage {
+    rounds: RangeInclusive<u64>,
+    present: u64,
+    missing: Vec<RangeInclusive<u64>>,
+    duplicates: Vec<u64>,
+    outside: usize,
+}
+
+impl Coverage {
+    /// Coverage of `rounds` by `beacons`. Beacons outside of `rounds` are counted, and otherwise ignored.
+    pub fn new(beacons: &[RandomnessBeacon], rounds: RangeInclusive<u64>) -> Self {
+        let missing = missing_rounds(beacons, rounds.clone());
+        let missing_len: u64 = missing.iter().map(range_len).sum();
+        Self {
+            present: range_len(&rounds) - missing_len,
+            missing,
+            duplicates: duplicate_rounds(beacons)
+                .into_iter()
+                .filter(|round| rounds.contains(round))
+                .collect(),
+            outside: beacons
+                .iter()
+                .filter(|beacon| !rounds.contains(&beacon.round()))
+                .count(),
+            rounds,
+        }
+    }
+
+    /// Range of rounds covered.
+    pub fn rounds(&self) -> RangeInclusive<u64> {
+        self.rounds.clone()
+    }
+
+    /// Number of rounds of the range with at least one beacon.
+    pub fn present(&self) -> u64 {
+        self.present
+    }
+
+    /// Ranges of rounds of the range without a beacon. See [`missing_rounds`].
+    pub fn missing(&self) -> &[RangeInclusive<u64>] {
+        &self.missing
+    }
+
+    /// Rounds of the range with more than one beacon. See [`duplicate_rounds`].
+    pub fn duplicates(&self) -> &[u64] {
+        &self.duplicates
+    }
+
+    /// Number of beacons whose round is outside of the range.
+    pub fn outside(&self) -> usize {
+        self.outside
+    }
+
+    /// Whether every round of the range has a beacon.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Number of rounds in `rounds`, saturating for the full range of `u64`.
+fn range_len(rounds: &RangeInclusive<u64>) -> u64 {
+    match rounds.is_empty() {
+        true => 0,
+        false => (rounds.end() - rounds.start()).saturating_add(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::ApiBeacon;
+
+    use super::*;
+
+    /// Beacons of `rounds`, which do not verify.
+    fn beacons(rounds: &[u64]) -> Vec<RandomnessBeacon> {
+        rounds
+            .iter()
+            .map(|round| {
+                RandomnessBeacon::new(ApiBeacon::from_signature(*round, vec![0; 48], None), 0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn coverage_sort_works() {
+        let mut beacons = beacons(&[3, 1, 2, 1]);
+        assert!(!is_sorted_by_round(&beacons));
+        sort_by_round(&mut beacons);
+        let rounds: Vec<u64> = beacons.iter().map(RandomnessBeacon::round).collect();
+        assert_eq!(rounds, [1, 1, 2, 3]);
+        // duplicates are not strictly increasing
+        assert!(!is_sorted_by_round(&beacons));
+        assert!(is_sorted_by_round(&beacons[1..]));
+        assert_eq!(duplicate_rounds(&beacons), [1]);
+    }
+
+    #[test]
+    fn coverage_missing_works() {
+        let beacons = beacons(&[5, 2, 3, 9, 3, 12]);
+        assert_eq!(
+            missing_rounds(&beacons, 1..=10),
+            [1..=1, 4..=4, 6..=8, 10..=10]
+        );
+        assert!(missing_rounds(&beacons, 2..=3).is_empty());
+        assert_eq!(missing_rounds(&beacons, 13..=14), [13..=14]);
+        assert!(missing_rounds(&beacons, RangeInclusive::new(3, 2)).is_empty());
+        assert_eq!(missing_rounds(&[], 1..=3), [1..=3]);
+    }
+
+    #[test]
+    fn coverage_works() {
+        let coverage = Coverage::new(&beacons(&[5, 2, 3, 9, 3, 12]), 1..=10);
+        assert_eq!(coverage.rounds(), 1..=10);
+        assert_eq!(coverage.present(), 4);
+        assert_eq!(coverage.missing(), [1..=1, 4..=4, 6..=8, 10..=10]);
+        assert_eq!(coverage.duplicates(), [3]);
+        assert_eq!(coverage.outside(), 1);
+        assert!(!coverage.is_complete());
+
+        let coverage = Coverage::new(&beacons(&[2, 1, 3]), 1..=3);
+        assert_eq!(coverage.present(), 3);
+        assert!(coverage.is_complete());
+    }
+}
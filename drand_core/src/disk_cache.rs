@@ -0,0 +1,63 @@
+//! On-disk cache of chain info and beacons.
+//!
+//! Short-lived processes, such as command line tools, lose in-memory caches on exit. Persisting chain info and beacons saves requesting them again on the next run.
+//! Cached documents are verified again when they are loaded, so the cache directory does not need to be trusted.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{beacon::ApiBeacon, chain::ChainInfo};
+
+/// Documents of a single relay, stored as JSON files in a directory of their own.
+/// Failing to read or write the cache is not an error: documents are then requested from the relay.
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Documents of the relay at `base_url`, stored under `root`.
+    pub fn new(root: &Path, base_url: &str) -> Self {
+        let relay = hex::encode(Sha256::digest(base_url.as_bytes()));
+        Self {
+            dir: root.join(relay),
+        }
+    }
+
+    pub fn chain_info(&self) -> Option<ChainInfo> {
+        self.read("info.json")
+    }
+
+    pub fn set_chain_info(&self, info: &ChainInfo) {
+        self.write("info.json", info)
+    }
+
+    pub fn beacon(&self, round: u64) -> Option<ApiBeacon> {
+        self.read(&format!("{round}.json"))
+    }
+
+    pub fn set_beacon(&self, beacon: &ApiBeacon) {
+        self.write(&format!("{}.json", beacon.round()), beacon)
+    }
+
+    fn read<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let document = fs::read(self.dir.join(name)).ok()?;
+        serde_json::from_slice(&document).ok()
+    }
+
+    fn write<T: Serialize>(&self, name: &str, document: &T) {
+        let Ok(document) = serde_json::to_vec(document) else {
+            return;
+        };
+        // write to a temporary file first, so that concurrent processes never read a partial document
+        let path = self.dir.join(name);
+        let tmp = self.dir.join(format!("{name}.{}.tmp", std::process::id()));
+        let _ = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, document))
+            .and_then(|_| fs::rename(&tmp, path));
+    }
+}
@@ -0,0 +1,701 @@
+//! Distributed key generation, with the joint-Feldman protocol drand uses to set up a group, and to reshare its key to a new group.
+//!
+//! Each dealer shares a random polynomial: it sends an evaluation to each holder, and broadcasts a commitment to the polynomial.
+//! The protocol then runs in phases. Holders respond to every deal, complaining about invalid or missing shares. Dealers answer complaints by revealing the disputed share.
+//! Dealers which do not deal, or do not justify their deal, are disqualified. The group polynomial is the sum of the polynomials of qualified dealers.
+//! When resharing, dealers are the holders of the current key, and deal their own share. Holders interpolate the deals, so that the group public key is unchanged.
+//!
+//! Messages are neither authenticated nor encrypted: the transport has to provide it, as drand does by signing packets, and encrypting each share to its holder.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::anyhow;
+use ark_bls12_381::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::RngCore;
+use zeroize::Zeroize;
+
+use crate::{
+    chain::Scheme,
+    error::{Error, Result},
+    partial::{lagrange_at_zero, PublicPolynomial},
+};
+
+#[derive(Debug, Clone)]
+/// Participants and parameters of a key generation.
+pub struct Config {
+    scheme: Scheme,
+    threshold: usize,
+    dealers: Vec<u16>,
+    holders: Vec<u16>,
+    previous: Option<PublicPolynomial>,
+}
+
+impl Config {
+    /// New group of `nodes`, which are both dealers and holders. `threshold` of them are needed to sign.
+    pub fn new(scheme: Scheme, threshold: usize, nodes: Vec<u16>) -> Result<Self> {
+        Self::validated(scheme, threshold, nodes.clone(), nodes, None)
+    }
+
+    /// Reshare the key of the current group, committed to by `previous`, from its share holders `dealers`, to `holders`.
+    /// At least the threshold of the current group must deal.
+    pub fn reshare(
+        scheme: Scheme,
+        threshold: usize,
+        dealers: Vec<u16>,
+        holders: Vec<u16>,
+        previous: PublicPolynomial,
+    ) -> Result<Self> {
+        if dealers.len() < previous.threshold() {
+            return Err(Error::Other(anyhow!(
+                "{} dealers cannot reshare a key with a threshold of {}",
+                dealers.len(),
+                previous.threshold()
+            )));
+        }
+        Self::validated(scheme, threshold, dealers, holders, Some(previous))
+    }
+
+    fn validated(
+        scheme: Scheme,
+        threshold: usize,
+        dealers: Vec<u16>,
+        holders: Vec<u16>,
+        previous: Option<PublicPolynomial>,
+    ) -> Result<Self> {
+        if !scheme.is_bls12_381() {
+            return Err(Error::Other(anyhow!(
+                "scheme {scheme} is not supported for key generation"
+            )));
+        }
+        if threshold == 0 || threshold > holders.len() {
+            return Err(Error::Other(anyhow!(
+                "threshold {threshold} is invalid for {} holders",
+                holders.len()
+            )));
+        }
+        let is_distinct =
+            |indices: &[u16]| indices.iter().collect::<BTreeSet<_>>().len() == indices.len();
+        if !is_distinct(&dealers) || !is_distinct(&holders) {
+            return Err(Error::Other(anyhow!("indices must be distinct")));
+        }
+        Ok(Self {
+            scheme,
+            threshold,
+            dealers,
+            holders,
+            previous,
+        })
+    }
+
+    /// Whether the public key is on G1. Signatures are on the group opposite to the public key.
+    fn is_g1(&self) -> bool {
+        self.scheme.signature_len() == 96
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Phase of the protocol. Each phase ends once the messages of every participant have been processed, or its timeout has elapsed.
+pub enum Phase {
+    /// Dealers broadcast their deal.
+    Deal,
+    /// Holders broadcast a response to each deal.
+    Response,
+    /// Dealers answer complaints about their deal.
+    Justification,
+    /// The group polynomial, and the share of each holder, are known.
+    Finished,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Polynomial shared by a dealer.
+pub struct Deal {
+    pub dealer: u16,
+    /// Commitment to the polynomial of the dealer.
+    pub commits: PublicPolynomial,
+    /// Share of each holder, as a serialized scalar.
+    pub shares: BTreeMap<u16, Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The holder share is valid.
+    Approval,
+    /// The holder share is invalid, or missing.
+    Complaint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Response of a holder to a deal.
+pub struct Response {
+    pub dealer: u16,
+    pub holder: u16,
+    pub status: Status,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Share revealed by a dealer, in answer to a complaint.
+pub struct Justification {
+    pub dealer: u16,
+    pub holder: u16,
+    pub share: Vec<u8>,
+}
+
+impl Drop for Deal {
+    /// Shares are only to be sent encrypted to their holder.
+    fn drop(&mut self) {
+        for share in self.shares.values_mut() {
+            share.zeroize();
+        }
+    }
+}
+
+/// Share of the group key, held by a node once the key generation is finished.
+pub struct DistKeyShare {
+    index: u16,
+    share: Fr,
+    polynomial: PublicPolynomial,
+}
+
+impl DistKeyShare {
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Public polynomial of the group. Its first coefficient is the group public key.
+    pub fn public_polynomial(&self) -> PublicPolynomial {
+        self.polynomial.clone()
+    }
+
+    pub(crate) fn share(&self) -> &Fr {
+        &self.share
+    }
+}
+
+impl Drop for DistKeyShare {
+    fn drop(&mut self) {
+        self.share.zeroize();
+    }
+}
+
+/// State of a participant in a key generation.
+pub struct Dkg {
+    config: Config,
+    phase: Phase,
+    /// Index and polynomial of this participant, if it deals.
+    dealer: Option<(u16, Vec<Fr>)>,
+    /// Index of this participant, if it holds a share.
+    holder: Option<u16>,
+    deals: BTreeMap<u16, PublicPolynomial>,
+    shares: BTreeMap<u16, Fr>,
+    complaints: BTreeSet<(u16, u16)>,
+    disqualified: BTreeSet<u16>,
+}
+
+impl Dkg {
+    /// Participant `index` of a new group, which deals a random polynomial and holds a share.
+    pub fn new(config: Config, index: u16) -> Result<Self> {
+        if config.previous.is_some() {
+            return Err(Error::Other(anyhow!(
+                "resharing participants are created with Dkg::reshare"
+            )));
+        }
+        let secret = random_scalar();
+        Self::with_secret(config, Some((index, secret)), Some(index))
+    }
+
+    /// Participant of a resharing. It deals if it holds a `share` of the current key, and receives a share if it is `holder` in the new group.
+    pub fn reshare(
+        config: Config,
+        share: Option<&DistKeyShare>,
+        holder: Option<u16>,
+    ) -> Result<Self> {
+        if config.previous.is_none() {
+            return Err(Error::Other(anyhow!("configuration is not a resharing")));
+        }
+        let dealer = share.map(|share| (share.index, share.share));
+        Self::with_secret(config, dealer, holder)
+    }
+
+    fn with_secret(config: Config, dealer: Option<(u16, Fr)>, holder: Option<u16>) -> Result<Self> {
+        if let Some((index, _)) = dealer.filter(|(index, _)| !config.dealers.contains(index)) {
+            return Err(Error::Other(anyhow!("{index} is not a dealer")));
+        }
+        if let Some(index) = holder.filter(|index| !config.holders.contains(index)) {
+            return Err(Error::Other(anyhow!("{index} is not a holder")));
+        }
+        // the constant term is the secret, the others are random
+        let dealer = dealer.map(|(index, secret)| {
+            let mut coefficients = vec![secret];
+            coefficients.extend((1..config.threshold).map(|_| random_scalar()));
+            (index, coefficients)
+        });
+        Ok(Self {
+            config,
+            phase: Phase::Deal,
+            dealer,
+            holder,
+            deals: BTreeMap::new(),
+            shares: BTreeMap::new(),
+            complaints: BTreeSet::new(),
+            disqualified: BTreeSet::new(),
+        })
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Deal of this participant, to broadcast. It is `None` if it does not deal.
+    pub fn deal(&self) -> Result<Option<Deal>> {
+        self.expect_phase(Phase::Deal)?;
+        let Some((index, coefficients)) = &self.dealer else {
+            return Ok(None);
+        };
+        let shares = self
+            .config
+            .holders
+            .iter()
+            .map(|&holder| (holder, scalar_to_bytes(&evaluate(coefficients, holder))))
+            .collect();
+        Ok(Some(Deal {
+            dealer: *index,
+            commits: PublicPolynomial::commit(coefficients, self.config.is_g1()),
+            shares,
+        }))
+    }
+
+    /// Process the deals broadcast by dealers, and return the responses of this participant to broadcast.
+    /// Dealers without a well-formed deal are disqualified.
+    pub fn process_deals(&mut self, deals: &[Deal]) -> Result<Vec<Response>> {
+        self.expect_phase(Phase::Deal)?;
+        for deal in deals {
+            if !self.config.dealers.contains(&deal.dealer) || self.deals.contains_key(&deal.dealer)
+            {
+                continue;
+            }
+            if !self.is_well_formed(deal) {
+                self.disqualified.insert(deal.dealer);
+                continue;
+            }
+            self.deals.insert(deal.dealer, deal.commits.clone());
+            if let Some(holder) = self.holder {
+                let share = deal
+                    .shares
+                    .get(&holder)
+                    .and_then(|share| scalar_from_bytes(share))
+                    .filter(|share| deal.commits.verify_share(holder, share));
+                if let Some(share) = share {
+                    self.shares.insert(deal.dealer, share);
+                }
+            }
+        }
+        for dealer in &self.config.dealers {
+            if !self.deals.contains_key(dealer) {
+                self.disqualified.insert(*dealer);
+            }
+        }
+        self.phase = Phase::Response;
+
+        let Some(holder) = self.holder else {
+            return Ok(vec![]);
+        };
+        Ok(self
+            .deals
+            .keys()
+            .filter(|dealer| !self.disqualified.contains(dealer))
+            .map(|&dealer| Response {
+                dealer,
+                holder,
+                status: match self.shares.contains_key(&dealer) {
+                    true => Status::Approval,
+                    false => Status::Complaint,
+                },
+            })
+            .collect())
+    }
+
+    /// Process the responses broadcast by holders.
+    pub fn process_responses(&mut self, responses: &[Response]) -> Result<()> {
+        self.expect_phase(Phase::Response)?;
+        for response in responses {
+            if response.status == Status::Complaint
+                && self.config.holders.contains(&response.holder)
+                && self.deals.contains_key(&response.dealer)
+            {
+                self.complaints.insert((response.dealer, response.holder));
+            }
+        }
+        self.phase = Phase::Justification;
+        Ok(())
+    }
+
+    /// Justifications of this participant to broadcast, revealing the share of each holder which complained about its deal.
+    pub fn justifications(&self) -> Result<Vec<Justification>> {
+        self.expect_phase(Phase::Justification)?;
+        let Some((index, coefficients)) = &self.dealer else {
+            return Ok(vec![]);
+        };
+        Ok(self
+            .complaints
+            .iter()
+            .filter(|(dealer, _)| dealer == index)
+            .map(|&(dealer, holder)| Justification {
+                dealer,
+                holder,
+                share: scalar_to_bytes(&evaluate(coefficients, holder)),
+            })
+            .collect())
+    }
+
+    /// Process the justifications broadcast by dealers. Dealers which do not justify every complaint are disqualified.
+    pub fn process_justifications(&mut self, justifications: &[Justification]) -> Result<()> {
+        self.expect_phase(Phase::Justification)?;
+        for &(dealer, holder) in &self.complaints {
+            let share = justifications
+                .iter()
+                .filter(|justification| {
+                    justification.dealer == dealer && justification.holder == holder
+                })
+                .filter_map(|justification| scalar_from_bytes(&justification.share))
+                .find(|share| self.deals[&dealer].verify_share(holder, share));
+            match share {
+                Some(share) if Some(holder) == self.holder => {
+                    self.shares.insert(dealer, share);
+                }
+                Some(_) => {}
+                None => {
+                    self.disqualified.insert(dealer);
+                }
+            }
+        }
+        self.phase = Phase::Finished;
+        Ok(())
+    }
+
+    /// Dealers whose deal is part of the group key.
+    pub fn qualified(&self) -> Vec<u16> {
+        let qualified = self
+            .deals
+            .keys()
+            .filter(|dealer| !self.disqualified.contains(dealer))
+            .copied();
+        match &self.config.previous {
+            // any threshold of the previous shares interpolates the same key
+            Some(previous) => qualified.take(previous.threshold()).collect(),
+            None => qualified.collect(),
+        }
+    }
+
+    /// Share of the group key held by this participant. It is `None` if it does not hold a share.
+    /// Fails if fewer dealers than the threshold are qualified.
+    pub fn finish(&self) -> Result<Option<DistKeyShare>> {
+        self.expect_phase(Phase::Finished)?;
+        let qualified = self.qualified();
+        let needed = match &self.config.previous {
+            Some(previous) => previous.threshold(),
+            None => self.config.threshold,
+        };
+        if qualified.len() < needed {
+            return Err(Error::Other(anyhow!(
+                "{} dealers are qualified, {needed} are needed",
+                qualified.len()
+            )));
+        }
+        let weights: Vec<Fr> = match &self.config.previous {
+            Some(_) => (0..qualified.len())
+                .map(|i| lagrange_at_zero(&qualified, i))
+                .collect(),
+            None => vec![Fr::from(1u64); qualified.len()],
+        };
+
+        let terms: Vec<(Fr, &PublicPolynomial)> = weights
+            .iter()
+            .zip(&qualified)
+            .map(|(weight, dealer)| (*weight, &self.deals[dealer]))
+            .collect();
+        let polynomial = PublicPolynomial::linear_combination(&terms)?;
+        if let Some(previous) = &self.config.previous {
+            if polynomial.public_key() != previous.public_key() {
+                return Err(Error::Other(anyhow!(
+                    "reshared key is not the key of the previous group"
+                )));
+            }
+        }
+
+        let Some(index) = self.holder else {
+            return Ok(None);
+        };
+        let mut share = Fr::zero();
+        for (weight, dealer) in weights.iter().zip(&qualified) {
+            let Some(dealer_share) = self.shares.get(dealer) else {
+                return Err(Error::Other(anyhow!("share of dealer {dealer} is missing")));
+            };
+            share += *weight * dealer_share;
+        }
+        Ok(Some(DistKeyShare {
+            index,
+            share,
+            polynomial,
+        }))
+    }
+
+    fn is_well_formed(&self, deal: &Deal) -> bool {
+        let is_g1 = deal.commits.public_key().len() == 48;
+        let is_previous_share = match &self.config.previous {
+            Some(previous) => deal.commits.public_key() == previous.share_public_key(deal.dealer),
+            None => true,
+        };
+        deal.commits.threshold() == self.config.threshold
+            && is_g1 == self.config.is_g1()
+            && is_previous_share
+    }
+
+    fn expect_phase(&self, phase: Phase) -> Result<()> {
+        if self.phase != phase {
+            return Err(Error::Other(anyhow!(
+                "expected phase {phase:?}, the protocol is in phase {:?}",
+                self.phase
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Dkg {
+    fn drop(&mut self) {
+        if let Some((_, coefficients)) = &mut self.dealer {
+            coefficients.zeroize();
+        }
+        for share in self.shares.values_mut() {
+            share.zeroize();
+        }
+    }
+}
+
+/// Polynomial with `coefficients` evaluated for share `index`, which is at `index + 1`.
+fn evaluate(coefficients: &[Fr], index: u16) -> Fr {
+    let x = Fr::from(index as u64 + 1);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |acc, coefficient| acc * x + coefficient)
+}
+
+fn random_scalar() -> Fr {
+    let mut bytes = [0; 64];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let scalar = Fr::from_le_bytes_mod_order(&bytes);
+    bytes.zeroize();
+    scalar
+}
+
+fn scalar_to_bytes(scalar: &Fr) -> Vec<u8> {
+    let mut bytes = vec![];
+    scalar
+        .serialize_compressed(&mut bytes)
+        .expect("scalars serialize in memory");
+    bytes
+}
+
+fn scalar_from_bytes(bytes: &[u8]) -> Option<Fr> {
+    Fr::deserialize_compressed(bytes).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::chain::{tests::rehash, tests::unchained_chain_info, ChainInfo};
+    use crate::partial::{aggregate, tests::sign, PartialBeacon};
+
+    use super::*;
+
+    /// Run every phase with honest participants.
+    pub fn run(participants: &mut [Dkg]) -> Vec<Option<DistKeyShare>> {
+        let deals: Vec<Deal> = participants
+            .iter()
+            .filter_map(|p| p.deal().unwrap())
+            .collect();
+        let responses: Vec<Response> = participants
+            .iter_mut()
+            .flat_map(|p| p.process_deals(&deals).unwrap())
+            .collect();
+        participants
+            .iter_mut()
+            .for_each(|p| p.process_responses(&responses).unwrap());
+        let justifications: Vec<Justification> = participants
+            .iter()
+            .flat_map(|p| p.justifications().unwrap())
+            .collect();
+        participants
+            .iter_mut()
+            .map(|p| {
+                p.process_justifications(&justifications).unwrap();
+                p.finish().unwrap()
+            })
+            .collect()
+    }
+
+    /// Chain info with the public key of `polynomial`.
+    fn chain_info(polynomial: &PublicPolynomial) -> ChainInfo {
+        let mut info = serde_json::to_value(unchained_chain_info()).unwrap();
+        info["public_key"] = hex::encode(polynomial.public_key()).into();
+        rehash(&mut info);
+        serde_json::from_value(info).unwrap()
+    }
+
+    /// Beacon of `round` aggregated from partials signed with `shares`.
+    fn beacon(shares: &[&DistKeyShare], info: &ChainInfo, round: u64) -> Vec<u8> {
+        let partials: Vec<PartialBeacon> = shares
+            .iter()
+            .map(|share| sign(share.share(), share.index(), round, None))
+            .collect();
+        aggregate(&partials, &shares[0].public_polynomial(), info)
+            .unwrap()
+            .signature()
+            .to_vec()
+    }
+
+    pub fn new_group(threshold: usize, n: u16) -> Vec<Dkg> {
+        let config =
+            Config::new(Scheme::PedersenBlsUnchained, threshold, (0..n).collect()).unwrap();
+        (0..n)
+            .map(|i| Dkg::new(config.clone(), i).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dkg_works() {
+        let mut participants = new_group(3, 5);
+        let shares: Vec<DistKeyShare> = run(&mut participants)
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        let polynomial = shares[0].public_polynomial();
+        assert_eq!(polynomial.threshold(), 3);
+        assert!(shares
+            .iter()
+            .all(|share| share.public_polynomial() == polynomial));
+        assert_eq!(participants[0].qualified(), vec![0, 1, 2, 3, 4]);
+
+        // any threshold of holders signs the same beacon
+        let info = chain_info(&polynomial);
+        let signature = beacon(&[&shares[0], &shares[2], &shares[4]], &info, 1000000);
+        assert_eq!(
+            beacon(&[&shares[3], &shares[1], &shares[2]], &info, 1000000),
+            signature
+        );
+    }
+
+    #[test]
+    fn dkg_complaint_works() {
+        let mut participants = new_group(2, 4);
+        let mut deals: Vec<Deal> = participants
+            .iter()
+            .map(|p| p.deal().unwrap().unwrap())
+            .collect();
+        // dealer 1 sends an invalid share to holder 2, and dealer 3 does not deal
+        deals[1].shares.insert(2, scalar_to_bytes(&Fr::from(42u64)));
+        deals.pop();
+
+        let responses: Vec<Response> = participants
+            .iter_mut()
+            .flat_map(|p| p.process_deals(&deals).unwrap())
+            .collect();
+        assert!(responses.contains(&Response {
+            dealer: 1,
+            holder: 2,
+            status: Status::Complaint
+        }));
+        participants
+            .iter_mut()
+            .for_each(|p| p.process_responses(&responses).unwrap());
+        let justifications: Vec<Justification> = participants
+            .iter()
+            .flat_map(|p| p.justifications().unwrap())
+            .collect();
+        assert_eq!(justifications.len(), 1);
+
+        // dealer 1 justified its deal, holder 2 gets its share from the justification
+        for p in participants.iter_mut() {
+            p.process_justifications(&justifications).unwrap();
+        }
+        assert_eq!(participants[2].qualified(), vec![0, 1, 2]);
+        let shares: Vec<DistKeyShare> = participants
+            .iter()
+            .map(|p| p.finish().unwrap().unwrap())
+            .collect();
+        let info = chain_info(&shares[0].public_polynomial());
+        assert_eq!(
+            beacon(&[&shares[2], &shares[3]], &info, 1000000),
+            beacon(&[&shares[0], &shares[1]], &info, 1000000)
+        );
+
+        // a dealer which does not justify is disqualified
+        let mut participants = new_group(2, 3);
+        let mut deals: Vec<Deal> = participants
+            .iter()
+            .map(|p| p.deal().unwrap().unwrap())
+            .collect();
+        deals[0].shares.remove(&1);
+        let responses: Vec<Response> = participants
+            .iter_mut()
+            .flat_map(|p| p.process_deals(&deals).unwrap())
+            .collect();
+        participants
+            .iter_mut()
+            .for_each(|p| p.process_responses(&responses).unwrap());
+        participants
+            .iter_mut()
+            .for_each(|p| p.process_justifications(&[]).unwrap());
+        assert_eq!(participants[1].qualified(), vec![1, 2]);
+        assert!(participants[1].finish().unwrap().is_some());
+        assert!(participants[0].deal().is_err());
+    }
+
+    #[test]
+    fn dkg_reshare_works() {
+        let shares: Vec<DistKeyShare> = run(&mut new_group(3, 5))
+            .into_iter()
+            .map(Option::unwrap)
+            .collect();
+        let previous = shares[0].public_polynomial();
+
+        // nodes 0, 2, and 4 reshare to a new group of 4, in which node 2 is 0
+        let config = Config::reshare(
+            Scheme::PedersenBlsUnchained,
+            2,
+            vec![0, 2, 4],
+            (0..4).collect(),
+            previous.clone(),
+        )
+        .unwrap();
+        let mut participants = vec![
+            Dkg::reshare(config.clone(), Some(&shares[0]), None).unwrap(),
+            Dkg::reshare(config.clone(), Some(&shares[2]), Some(0)).unwrap(),
+            Dkg::reshare(config.clone(), Some(&shares[4]), None).unwrap(),
+        ];
+        participants.extend((1..4).map(|i| Dkg::reshare(config.clone(), None, Some(i)).unwrap()));
+        let reshared: Vec<DistKeyShare> = run(&mut participants).into_iter().flatten().collect();
+        assert_eq!(reshared.len(), 4);
+        let polynomial = reshared[0].public_polynomial();
+        assert_eq!(polynomial.threshold(), 2);
+        assert_eq!(polynomial.public_key(), previous.public_key());
+
+        // the new group signs the beacons of the previous one
+        let info = chain_info(&previous);
+        assert_eq!(
+            beacon(&[&reshared[1], &reshared[3]], &info, 1000000),
+            beacon(&[&shares[0], &shares[1], &shares[3]], &info, 1000000)
+        );
+
+        assert!(Config::reshare(
+            Scheme::PedersenBlsUnchained,
+            2,
+            vec![0, 1],
+            vec![0, 1],
+            previous
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Resolution of relay host names.
+//!
+//! By default, relay host names are resolved by the system. Deployments which cannot trust the local resolver can plug their own, or resolve over HTTPS.
+
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+// reqwest 0.11 does not re-export the name type of its resolver trait
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// DNS record types, as numbered in DNS-over-HTTPS JSON answers.
+const RECORD_A: u16 = 1;
+const RECORD_AAAA: u16 = 28;
+
+/// Resolver for relay host names, used instead of the system one.
+#[derive(Clone)]
+pub struct DnsResolver(Arc<dyn Resolve>);
+
+impl DnsResolver {
+    /// Resolve host names with a custom `resolver`.
+    pub fn new(resolver: impl Resolve + 'static) -> Self {
+        Self(Arc::new(resolver))
+    }
+
+    /// Resolve host names over HTTPS, with the JSON API of `endpoint`, such as `https://1.1.1.1/dns-query`.
+    /// The endpoint host name is itself resolved by the system. Using an IP address avoids relying on it.
+    pub fn dns_over_https(endpoint: &str) -> Result<Self> {
+        let endpoint =
+            url::Url::parse(endpoint).map_err(|e| Error::Other(anyhow!("{endpoint}: {e}")))?;
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(|e| Error::Other(anyhow!(e)))?;
+        Ok(Self::new(DohResolver { endpoint, client }))
+    }
+}
+
+/// reqwest expects a sized resolver, which the boxed one is wrapped into.
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        self.0.resolve(name)
+    }
+}
+
+impl fmt::Debug for DnsResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DnsResolver").finish()
+    }
+}
+
+#[derive(Clone)]
+struct DohResolver {
+    endpoint: url::Url,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+impl DohResolver {
+    async fn lookup(&self, name: &str) -> anyhow::Result<Vec<SocketAddr>> {
+        let mut addrs = vec![];
+        for record_type in ["A", "AAAA"] {
+            let response: DohResponse = self
+                .client
+                .get(self.endpoint.clone())
+                .query(&[("name", name), ("type", record_type)])
+                .header(reqwest::header::ACCEPT, "application/dns-json")
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            // answers also contain CNAME records, which are followed by the endpoint
+            addrs.extend(
+                response
+                    .answer
+                    .iter()
+                    .filter(|answer| matches!(answer.record_type, RECORD_A | RECORD_AAAA))
+                    .filter_map(|answer| answer.data.parse::<IpAddr>().ok())
+                    .map(|ip| SocketAddr::new(ip, 0)),
+            );
+        }
+        if addrs.is_empty() {
+            return Err(anyhow!("no address found for {name}"));
+        }
+        Ok(addrs)
+    }
+}
+
+impl Resolve for DohResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chain::tests::chained_chain_info, ApiClient, HttpOptions};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn dns_over_https_works() {
+        let mut relay = mockito::Server::new_async().await;
+        relay
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let mut doh = mockito::Server::new_async().await;
+        let a_mock = doh
+            .mock("GET", "/dns-query")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("name".to_string(), "relay.invalid".to_string()),
+                mockito::Matcher::UrlEncoded("type".to_string(), "A".to_string()),
+            ]))
+            .with_status(200)
+            .with_header("content-type", "application/dns-json")
+            .with_body(r#"{"Status":0,"Answer":[{"name":"relay.invalid","type":1,"TTL":60,"data":"127.0.0.1"}]}"#)
+            .create_async()
+            .await;
+        doh.mock("GET", "/dns-query")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "type".to_string(),
+                "AAAA".to_string(),
+            ))
+            .with_status(200)
+            .with_header("content-type", "application/dns-json")
+            .with_body(r#"{"Status":0}"#)
+            .create_async()
+            .await;
+
+        let resolver = DnsResolver::dns_over_https(&format!("{}/dns-query", doh.url())).unwrap();
+        let http_options = HttpOptions::default().with_dns_resolver(resolver);
+        let port = relay
+            .host_with_port()
+            .rsplit(':')
+            .next()
+            .unwrap()
+            .to_string();
+        let client =
+            ApiClient::with_http_options(&format!("http://relay.invalid:{port}"), http_options)
+                .unwrap();
+        assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
+        a_mock.assert_async().await;
+    }
+}
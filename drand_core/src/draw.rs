@@ -0,0 +1,265 @@
+//! Unbiased draws from beacon randomness.
+//!
+//! Draws are deterministic: anyone with the same beacon obtains the same result, which makes them publicly verifiable.
+//! Beacon randomness is expanded with SHA-256 in counter mode, and integers are drawn by rejection sampling, so that every outcome is equally likely.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::beacon::RandomnessBeacon;
+
+/// Deterministic stream of random bytes and integers, expanded from a seed such as beacon randomness.
+/// Block `i` of the stream is `SHA-256(seed || i)`, with `i` as a big-endian `u64`.
+pub struct DrawRng {
+    seed: Vec<u8>,
+    counter: u64,
+    block: [u8; 32],
+    offset: usize,
+}
+
+impl DrawRng {
+    /// Stream expanded from the randomness of `beacon`.
+    pub fn new(beacon: &RandomnessBeacon) -> Self {
+        Self::from_seed(beacon.randomness())
+    }
+
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Self {
+            seed: seed.to_vec(),
+            counter: 0,
+            block: [0; 32],
+            // the first block is computed on the first draw
+            offset: 32,
+        }
+    }
+
+    /// Next 8 bytes of the stream, as a big-endian integer.
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_be_bytes(bytes)
+    }
+
+    /// Fill `dest` with the next bytes of the stream. The stream is as long as needed, such as to draw from tens of millions of items.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.offset == self.block.len() {
+                let mut hasher = Sha256::new();
+                hasher.update(&self.seed);
+                hasher.update(self.counter.to_be_bytes());
+                self.block = hasher.finalize().into();
+                self.counter += 1;
+                self.offset = 0;
+            }
+            let len = (dest.len() - filled).min(self.block.len() - self.offset);
+            dest[filled..filled + len].copy_from_slice(&self.block[self.offset..self.offset + len]);
+            self.offset += len;
+            filled += len;
+        }
+    }
+
+    /// Integer drawn uniformly in `[0, n)`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "cannot draw below 0");
+        // values under 2^64 mod n are rejected, so that the rest splits evenly into n classes
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return value % n;
+            }
+        }
+    }
+}
+
+impl std::io::Read for DrawRng {
+    /// Read the next bytes of the stream. It never ends.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.fill_bytes(buf);
+        Ok(buf.len())
+    }
+}
+
+/// Generator for the `rand` ecosystem, such as [`rand::seq::SliceRandom`]. Its output is the stream, so that it is reproducible from the beacon alone.
+impl rand::RngCore for DrawRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_be_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        DrawRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        DrawRng::fill_bytes(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        DrawRng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+/// Index drawn uniformly in `[0, n)`, such as the winner among `n` participants.
+///
+/// # Panics
+///
+/// If `n` is 0.
+pub fn pick_index(beacon: &RandomnessBeacon, n: usize) -> usize {
+    DrawRng::new(beacon).below(n as u64) as usize
+}
+
+/// Shuffle `items` in place, with a Fisher–Yates shuffle. Every permutation is equally likely.
+pub fn shuffle<T>(beacon: &RandomnessBeacon, items: &mut [T]) {
+    let mut rng = DrawRng::new(beacon);
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// `k` distinct indices drawn uniformly in `[0, n)`, in the order they are drawn.
+/// It is a partial Fisher–Yates shuffle of `0..n`, which only keeps track of the `k` first positions.
+///
+/// # Panics
+///
+/// If `k` is greater than `n`.
+pub fn sample_k(beacon: &RandomnessBeacon, n: usize, k: usize) -> Vec<usize> {
+    assert!(k <= n, "cannot sample {k} indices out of {n}");
+    sample_k_with(&mut DrawRng::new(beacon), n, k)
+}
+
+pub(crate) fn sample_k_with(rng: &mut DrawRng, n: usize, k: usize) -> Vec<usize> {
+    // positions which have been swapped, the others hold their own index
+    let mut swapped: HashMap<usize, usize> = HashMap::new();
+    (0..k)
+        .map(|i| {
+            let j = i + rng.below((n - i) as u64) as usize;
+            let picked = *swapped.get(&j).unwrap_or(&j);
+            swapped.insert(j, *swapped.get(&i).unwrap_or(&i));
+            picked
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, unchained_beacon};
+    use crate::chain::{tests::chained_chain_info, Chain};
+
+    use super::*;
+
+    fn beacon() -> RandomnessBeacon {
+        Chain::new(chained_chain_info())
+            .unwrap()
+            .beacon(chained_beacon())
+    }
+
+    #[test]
+    fn draw_rng_works() {
+        let mut rng = DrawRng::from_seed(b"drand");
+        let mut same = DrawRng::from_seed(b"drand");
+        for _ in 0..10 {
+            assert_eq!(rng.next_u64(), same.next_u64());
+        }
+        // the stream starts with SHA-256("drand" || 0u64)
+        let expected = Sha256::new()
+            .chain_update(b"drand")
+            .chain_update(0u64.to_be_bytes())
+            .finalize();
+        let mut rng = DrawRng::from_seed(b"drand");
+        assert_eq!(rng.next_u64().to_be_bytes(), expected[..8]);
+
+        assert!((0..100).all(|_| rng.below(7) < 7));
+        assert_eq!(rng.below(1), 0);
+    }
+
+    #[test]
+    fn draw_rng_keystream_works() {
+        use std::io::Read;
+
+        // the stream is the same, however it is read
+        let mut whole = vec![0; 100];
+        DrawRng::new(&beacon()).fill_bytes(&mut whole);
+        let mut rng = DrawRng::new(&beacon());
+        let mut parts = vec![0; 100];
+        rng.fill_bytes(&mut parts[..5]);
+        rng.read_exact(&mut parts[5..70]).unwrap();
+        rng.fill_bytes(&mut parts[70..]);
+        assert_eq!(parts, whole);
+
+        let mut rng = DrawRng::new(&beacon());
+        assert_eq!(rng.next_u64().to_be_bytes(), whole[..8]);
+        assert_eq!(
+            rand::RngCore::next_u32(&mut rng).to_be_bytes(),
+            whole[8..12]
+        );
+    }
+
+    #[test]
+    fn pick_index_works() {
+        let index = pick_index(&beacon(), 10);
+        assert!(index < 10);
+        assert_eq!(pick_index(&beacon(), 10), index);
+        assert_eq!(pick_index(&beacon(), 1), 0);
+    }
+
+    #[test]
+    fn shuffle_works() {
+        let mut items: Vec<u32> = (0..50).collect();
+        shuffle(&beacon(), &mut items);
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..50).collect::<Vec<u32>>());
+        assert_ne!(items, sorted);
+
+        let mut again: Vec<u32> = (0..50).collect();
+        shuffle(&beacon(), &mut again);
+        assert_eq!(again, items);
+
+        // another beacon gives another permutation
+        let other = Chain::new(crate::chain::tests::unchained_chain_info())
+            .unwrap()
+            .beacon(unchained_beacon());
+        let mut other_items: Vec<u32> = (0..50).collect();
+        shuffle(&other, &mut other_items);
+        assert_ne!(other_items, items);
+    }
+
+    #[test]
+    fn sample_k_works() {
+        let sample = sample_k(&beacon(), 1000, 10);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.iter().all(|&i| i < 1000));
+        let mut distinct = sample.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 10);
+        assert_eq!(sample_k(&beacon(), 1000, 10), sample);
+
+        // sampling every index is a permutation
+        let mut all = sample_k(&beacon(), 20, 20);
+        all.sort();
+        assert_eq!(all, (0..20).collect::<Vec<usize>>());
+        assert!(sample_k(&beacon(), 5, 0).is_empty());
+    }
+
+    #[test]
+    fn below_is_unbiased_works() {
+        let mut rng = DrawRng::from_seed(b"drand");
+        let mut counts = [0u32; 6];
+        for _ in 0..60_000 {
+            counts[rng.below(6) as usize] += 1;
+        }
+        // each face is expected 10,000 times
+        assert!(counts.iter().all(|&count| (9_500..10_500).contains(&count)));
+    }
+}
@@ -0,0 +1,367 @@
+//! Errors raised while querying a drand relay.
+//!
+//! Client methods return an [`Error`], which can be matched on to tell relay failures from verification failures.
+//! So do lower level [`crate::ApiClient`], transports, and chain verification. Relay failures can be told apart with [`Error::relay`].
+
+use std::{fmt, time::Duration};
+
+use crate::chain::{BeforeGenesisError, VerificationError};
+
+/// Result of client methods, and of the lower level APIs they build on.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+/// Failure of a client method.
+pub enum Error {
+    /// Relay could not serve the request.
+    Relay(RelayError),
+    /// Chain info does not match the expected chain hash or public key, or is malformed, such as with a period of 0.
+    InvalidChainInfo,
+    /// Chain uses a scheme this library does not support.
+    UnsupportedScheme { scheme: String },
+    /// Beacon `round` does not verify against the chain, for `reason`.
+    InvalidBeacon {
+        round: u64,
+        reason: VerificationError,
+    },
+    /// Beacon `round` is after `max_round`, the latest round which can have been emitted given the allowed clock skew.
+    FutureRound { round: u64, max_round: u64 },
+    /// Time is before the chain genesis.
+    BeforeGenesis(BeforeGenesisError),
+    /// Any other failure, such as an invalid base URL, or chain info which has not been retrieved yet.
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// Relay failure, if the error is one.
+    pub fn relay(&self) -> Option<&RelayError> {
+        match self {
+            Self::Relay(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request might succeed later. Only relay failures can be.
+    pub fn is_retryable(&self) -> bool {
+        self.relay().map_or(false, RelayError::is_retryable)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Relay(err) => write!(f, "{err}"),
+            Self::InvalidChainInfo => write!(f, "Chain info is invalid"),
+            Self::UnsupportedScheme { scheme } => write!(f, "unsupported scheme {scheme}"),
+            Self::InvalidBeacon { round, reason } => {
+                write!(f, "Beacon {round} does not validate: {reason}")
+            }
+            Self::FutureRound { round, max_round } => write!(
+                f,
+                "Beacon is from the future: round {round} is after current round {max_round}"
+            ),
+            Self::BeforeGenesis(err) => write!(f, "{err}"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Relay(err) => Some(err),
+            Self::InvalidBeacon { reason, .. } => Some(reason),
+            Self::BeforeGenesis(err) => Some(err),
+            Self::Other(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<RelayError> for Error {
+    fn from(err: RelayError) -> Self {
+        Self::Relay(err)
+    }
+}
+
+impl From<BeforeGenesisError> for Error {
+    fn from(err: BeforeGenesisError) -> Self {
+        Self::BeforeGenesis(err)
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Recover the typed error raised by lower layers, such as signature backends.
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<Error>() {
+            Ok(err) => return err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<RelayError>() {
+            Ok(err) => return Self::Relay(err),
+            Err(err) => err,
+        };
+        match err.downcast::<BeforeGenesisError>() {
+            Ok(err) => Self::BeforeGenesis(err),
+            Err(err) => Self::Other(err),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Failure to retrieve a document from a relay.
+pub enum RelayError {
+    /// Document does not exist (404). For beacons, the round has most likely not been produced yet.
+    NotFound { body: String },
+    /// Relay is rate limiting the client (429). `retry_after` is how long the relay asks to wait, when it says so.
+    RateLimited {
+        body: String,
+        retry_after: Option<Duration>,
+    },
+    /// Relay failed to serve the request (5xx).
+    Server { status: u16, body: String },
+    /// Relay answered with any other unexpected status.
+    Status { status: u16, body: String },
+    /// Response is not JSON, such as an HTML error page or a captive portal. `body` starts the response, for diagnostics.
+    UnexpectedContentType { content_type: String, body: String },
+    /// Response exceeds the `limit` size, in bytes. It has not been read further.
+    TooLarge { limit: usize },
+    /// Latest beacon served by the relay is `round`, while the local clock expects `expected`. The relay is most likely stuck.
+    Stale { round: u64, expected: u64 },
+    /// Operation did not complete within its `budget`, including chain info retrieval, retries, and verification.
+    Timeout { budget: Duration },
+    /// Relay could not be reached, or the connection failed before a response was received.
+    Network(Box<dyn std::error::Error + Send + Sync>),
+    /// Response is not the expected document.
+    Deserialization(serde_json::Error),
+}
+
+impl RelayError {
+    /// Classify an unsuccessful response by its `status` code.
+    pub fn from_status(status: u16, body: String) -> Self {
+        match status {
+            404 => Self::NotFound { body },
+            429 => Self::RateLimited {
+                body,
+                retry_after: None,
+            },
+            500..=599 => Self::Server { status, body },
+            _ => Self::Status { status, body },
+        }
+    }
+
+    /// Attach the `Retry-After` header value of the response to a rate limiting error. Only delays in seconds are supported, not HTTP dates.
+    pub(crate) fn with_retry_after(self, retry_after: Option<&str>) -> Self {
+        match self {
+            Self::RateLimited { body, .. } => Self::RateLimited {
+                body,
+                retry_after: retry_after
+                    .and_then(|retry_after| retry_after.trim().parse().ok())
+                    .map(Duration::from_secs),
+            },
+            err => err,
+        }
+    }
+
+    pub fn network(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self::Network(Box::new(err))
+    }
+
+    /// HTTP status code returned by the relay. `None` when no response was received, or it was successful.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::NotFound { .. } => Some(404),
+            Self::RateLimited { .. } => Some(429),
+            Self::Server { status, .. } | Self::Status { status, .. } => Some(*status),
+            Self::UnexpectedContentType { .. }
+            | Self::TooLarge { .. }
+            | Self::Stale { .. }
+            | Self::Timeout { .. }
+            | Self::Network(_)
+            | Self::Deserialization(_) => None,
+        }
+    }
+
+    /// Response body sent along an unsuccessful response. Only its start is kept for unexpected content types.
+    pub fn body(&self) -> Option<&str> {
+        match self {
+            Self::NotFound { body }
+            | Self::RateLimited { body, .. }
+            | Self::Server { body, .. }
+            | Self::Status { body, .. }
+            | Self::UnexpectedContentType { body, .. } => Some(body),
+            Self::TooLarge { .. }
+            | Self::Stale { .. }
+            | Self::Timeout { .. }
+            | Self::Network(_)
+            | Self::Deserialization(_) => None,
+        }
+    }
+
+    /// Delay the relay asked to wait before sending another request.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the same request might succeed later, such as a beacon which has not been produced yet, or an overloaded relay.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::NotFound { .. }
+                | Self::RateLimited { .. }
+                | Self::Server { .. }
+                | Self::Stale { .. }
+                | Self::Timeout { .. }
+                | Self::Network(_)
+        )
+    }
+}
+
+/// Number of characters of an unexpected response kept for diagnostics.
+const SNIPPET_LEN: usize = 200;
+
+/// Fail if `content_type` is set, and is not JSON. Relays are expected to answer with `application/json`.
+pub(crate) fn ensure_json(content_type: Option<&str>, body: &str) -> Result<(), RelayError> {
+    let Some(content_type) = content_type else {
+        return Ok(());
+    };
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if essence == "application/json" || essence.ends_with("+json") {
+        return Ok(());
+    }
+    Err(RelayError::UnexpectedContentType {
+        content_type: content_type.to_string(),
+        body: snippet(body),
+    })
+}
+
+/// Start of `body`, on a single line.
+fn snippet(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<&str>>().join(" ");
+    match body.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _char)) => format!("{}...", &body[..end]),
+        None => body,
+    }
+}
+
+impl fmt::Display for RelayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { body } => write!(f, "not found: {body}"),
+            Self::RateLimited { body, .. } => write!(f, "rate limited: {body}"),
+            Self::Server { status, body } => write!(f, "relay error {status}: {body}"),
+            Self::Status { status, body } => write!(f, "unexpected status {status}: {body}"),
+            Self::UnexpectedContentType { content_type, body } => write!(
+                f,
+                "expected JSON, but relay returned {content_type}. Is the URL pointing to a drand relay? Response starts with: {body}"
+            ),
+            Self::TooLarge { limit } => write!(f, "response is larger than {limit} bytes"),
+            Self::Stale { round, expected } => {
+                write!(f, "stale relay: latest round is {round}, expected {expected}")
+            }
+            Self::Timeout { budget } => write!(f, "timed out after {budget:?}"),
+            Self::Network(err) => write!(f, "network error: {err}"),
+            Self::Deserialization(err) => write!(f, "invalid response: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RelayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Network(err) => Some(err.as_ref()),
+            Self::Deserialization(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for RelayError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Deserialization(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_status_works() {
+        let err = RelayError::from_status(404, "round not found".to_string());
+        assert!(matches!(err, RelayError::NotFound { .. }));
+        assert_eq!(err.status(), Some(404));
+        assert_eq!(err.body(), Some("round not found"));
+        assert!(err.is_retryable());
+
+        let err = RelayError::from_status(429, String::new());
+        assert!(matches!(err, RelayError::RateLimited { .. }));
+        assert_eq!(err.retry_after(), None);
+        let err = err.with_retry_after(Some("120"));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(120)));
+        let err = err.with_retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(err.retry_after(), None);
+        assert!(matches!(
+            RelayError::from_status(503, String::new()),
+            RelayError::Server { status: 503, .. }
+        ));
+
+        let err = RelayError::from_status(400, String::new());
+        assert!(matches!(err, RelayError::Status { status: 400, .. }));
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn error_from_anyhow_works() {
+        let err = Error::from(anyhow::Error::from(RelayError::from_status(
+            503,
+            String::new(),
+        )));
+        assert!(matches!(
+            err.relay(),
+            Some(RelayError::Server { status: 503, .. })
+        ));
+        assert!(err.is_retryable());
+
+        let err = Error::from(anyhow::Error::from(Error::InvalidBeacon {
+            round: 1,
+            reason: VerificationError::InvalidSignature,
+        }));
+        assert!(matches!(err, Error::InvalidBeacon { round: 1, .. }));
+        assert!(!err.is_retryable());
+
+        let err = Error::from(anyhow::anyhow!("other"));
+        assert!(matches!(err, Error::Other(_)));
+        assert_eq!(err.to_string(), "other");
+    }
+
+    #[test]
+    fn ensure_json_works() {
+        assert!(ensure_json(None, "{}").is_ok());
+        assert!(ensure_json(Some("application/json"), "{}").is_ok());
+        assert!(ensure_json(Some("application/json; charset=utf-8"), "{}").is_ok());
+        assert!(ensure_json(Some("application/problem+json"), "{}").is_ok());
+
+        let page = format!(
+            "<html>\n  <body>{}</body>\n</html>",
+            "Sign in to the network. ".repeat(20)
+        );
+        match ensure_json(Some("text/html"), &page) {
+            Err(RelayError::UnexpectedContentType { content_type, body }) => {
+                assert_eq!(content_type, "text/html");
+                assert!(body.starts_with("<html> <body>Sign in to the network."));
+                assert!(body.ends_with("..."));
+                assert_eq!(body.chars().count(), SNIPPET_LEN + 3);
+            }
+            _ => panic!("HTML page should be rejected"),
+        }
+    }
+}
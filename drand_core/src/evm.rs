@@ -0,0 +1,150 @@
+//! ABI encoding of beacons, to verify them in EVM smart contracts.
+//!
+//! Verifier contracts take the round as a `uint64`, and points as static arrays of `uint256` coordinates, which are verified with the EVM precompiles.
+//! BN254 points are encoded as the `ecPairing` precompile expects: `[x, y]` on G1, and `[x.c1, x.c0, y.c1, y.c0]` on G2.
+//! BLS12-381 points are encoded as the EIP-2537 precompiles expect: each field element is padded to 64 bytes, which are two words, and G2 coordinates are `c0` first.
+//!
+//! ```rust,ignore
+//! let calldata = drand_core::evm::calldata("verify(uint64,uint256[2])", &beacon, &info)?;
+//! ```
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use ark_bls12_381::{G1Affine, G2Affine};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    beacon::RandomnessBeacon,
+    chain::{ChainInfo, Scheme},
+    error::{Error, Result},
+    point,
+};
+
+/// Size of an ABI word, in bytes.
+const WORD_LEN: usize = 32;
+
+/// Selector of `function`, which is the first 4 bytes of the Keccak-256 of its canonical signature, such as `verify(uint64,uint256[2])`.
+pub fn selector(function: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(function.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Calldata calling `function` with the round and signature of `beacon`, from the chain described by `info`. See [`encode_beacon`].
+pub fn calldata(function: &str, beacon: &RandomnessBeacon, info: &ChainInfo) -> Result<Vec<u8>> {
+    Ok([selector(function).as_slice(), &encode_beacon(beacon, info)?].concat())
+}
+
+/// ABI encoding of `(uint64 round, uint256[n] signature)`, where `n` is 2 for BN254 signatures, 4 for BLS12-381 signatures on G1, and 8 on G2.
+/// Fails if the beacon does not have the structure of the scheme of `info`, or its signature is not a valid point. The beacon is not verified.
+pub fn encode_beacon(beacon: &RandomnessBeacon, info: &ChainInfo) -> Result<Vec<u8>> {
+    let scheme = Scheme::from_str(&info.scheme_id())?;
+    if !scheme.is_conforming(&beacon.beacon) {
+        return Err(Error::Other(anyhow!(
+            "beacon is not a beacon of scheme {scheme}"
+        )));
+    }
+    let mut encoded = pad(&beacon.round().to_be_bytes(), WORD_LEN);
+    encoded.extend(encode_point(beacon.signature(), scheme)?);
+    Ok(encoded)
+}
+
+/// ABI encoding of the public key of the chain described by `info`, as a `uint256[n]`, where `n` is 4 for BN254 keys, 4 for BLS12-381 keys on G1, and 8 on G2.
+pub fn encode_public_key(info: &ChainInfo) -> Result<Vec<u8>> {
+    let scheme = Scheme::from_str(&info.scheme_id())?;
+    encode_point(&info.public_key(), scheme)
+}
+
+/// Coordinates of `point` as `uint256` words.
+fn encode_point(point: &[u8], scheme: Scheme) -> Result<Vec<u8>> {
+    if !scheme.is_bls12_381() {
+        // BN254 points are already uncompressed, with 32 bytes coordinates
+        if point.len() != 64 && point.len() != 128 {
+            return Err(Error::Other(anyhow!(
+                "invalid BN254 point length {}",
+                point.len()
+            )));
+        }
+        return Ok(point.to_vec());
+    }
+
+    let compressed = point::compress(point)?;
+    let mut uncompressed = vec![];
+    // arkworks serializes uncompressed points as zcash does: big-endian coordinates, with `c1` first on G2
+    let coordinates: Vec<&[u8]> = match compressed.len() {
+        48 => {
+            G1Affine::deserialize_compressed(compressed.as_slice())
+                .and_then(|point| point.serialize_uncompressed(&mut uncompressed))
+                .map_err(|_| anyhow!("invalid G1 point"))?;
+            uncompressed.chunks(48).collect()
+        }
+        _ => {
+            G2Affine::deserialize_compressed(compressed.as_slice())
+                .and_then(|point| point.serialize_uncompressed(&mut uncompressed))
+                .map_err(|_| anyhow!("invalid G2 point"))?;
+            let chunks: Vec<&[u8]> = uncompressed.chunks(48).collect();
+            vec![chunks[1], chunks[0], chunks[3], chunks[2]]
+        }
+    };
+    Ok(coordinates
+        .into_iter()
+        .flat_map(|coordinate| pad(coordinate, 2 * WORD_LEN))
+        .collect())
+}
+
+/// `bytes` left padded with zeros to `len` bytes.
+fn pad(bytes: &[u8], len: usize) -> Vec<u8> {
+    let mut padded = vec![0; len - bytes.len()];
+    padded.extend_from_slice(bytes);
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{unchained_beacon, unchained_beacon_on_g1};
+    use crate::chain::{
+        tests::{unchained_chain_info, unchained_chain_on_g1_info},
+        Chain,
+    };
+
+    use super::*;
+
+    #[test]
+    fn evm_selector_works() {
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+    }
+
+    #[test]
+    fn evm_encode_beacon_works() {
+        let info = unchained_chain_info();
+        let beacon = Chain::new(info.clone()).unwrap().beacon(unchained_beacon());
+        let encoded = encode_beacon(&beacon, &info).unwrap();
+        assert_eq!(encoded.len(), WORD_LEN * 9);
+        assert_eq!(
+            encoded[..WORD_LEN],
+            pad(&1000000u64.to_be_bytes(), WORD_LEN)
+        );
+        // field elements are 48 bytes, padded to two words
+        assert!(encoded[WORD_LEN..]
+            .chunks(2 * WORD_LEN)
+            .all(|coordinate| coordinate[..16] == [0; 16]));
+
+        let calldata = calldata("verify(uint64,uint256[8])", &beacon, &info).unwrap();
+        assert_eq!(calldata[..4], selector("verify(uint64,uint256[8])"));
+        assert_eq!(calldata[4..], encoded);
+
+        let info = unchained_chain_on_g1_info();
+        let beacon = Chain::new(info.clone())
+            .unwrap()
+            .beacon(unchained_beacon_on_g1());
+        assert_eq!(encode_beacon(&beacon, &info).unwrap().len(), WORD_LEN * 5);
+        assert_eq!(encode_public_key(&info).unwrap().len(), WORD_LEN * 8);
+
+        // the signature has to be on the group of the scheme
+        assert!(encode_beacon(&beacon, &unchained_chain_info()).is_err());
+    }
+}
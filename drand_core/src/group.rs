@@ -0,0 +1,338 @@
+//! drand group files, which describe the nodes of a network and the chain they produce.
+//!
+//! A group file is the TOML output of `drand show group`. It is the trust root of a network: the chain info is derived from it,
+//! rather than trusted from a relay. See [`Group::chain_info`].
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::{
+    chain::ChainInfo,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Parsed group file.
+pub struct Group {
+    threshold: usize,
+    period: u64,
+    catchup_period: u64,
+    genesis_time: u64,
+    transition_time: u64,
+    genesis_seed: Vec<u8>,
+    scheme_id: String,
+    beacon_id: String,
+    nodes: Vec<Node>,
+    coefficients: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Node of a group.
+pub struct Node {
+    address: String,
+    key: Vec<u8>,
+    tls: bool,
+    signature: Vec<u8>,
+    index: u16,
+}
+
+impl Group {
+    /// Number of nodes needed to produce a beacon.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// How often the network emits randomness (in seconds).
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// How often the network emits randomness when catching up on missed rounds (in seconds).
+    pub fn catchup_period(&self) -> u64 {
+        self.catchup_period
+    }
+
+    /// Time of the round 0 of the network (in epoch seconds).
+    pub fn genesis_time(&self) -> u64 {
+        self.genesis_time
+    }
+
+    /// Time at which this group took over from the previous one, after a resharing (in epoch seconds).
+    pub fn transition_time(&self) -> u64 {
+        self.transition_time
+    }
+
+    /// Seed the first round signs over. It is the chain group hash.
+    pub fn genesis_seed(&self) -> Vec<u8> {
+        self.genesis_seed.clone()
+    }
+
+    pub fn scheme_id(&self) -> String {
+        self.scheme_id.clone()
+    }
+
+    /// The ID of the beacon chain. It is `default` if the group file does not have one.
+    pub fn beacon_id(&self) -> String {
+        self.beacon_id.clone()
+    }
+
+    pub fn nodes(&self) -> Vec<Node> {
+        self.nodes.clone()
+    }
+
+    /// Coefficients of the public polynomial, as compressed points. The first one is the chain public key.
+    pub fn coefficients(&self) -> Vec<Vec<u8>> {
+        self.coefficients.clone()
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.coefficients[0].clone()
+    }
+
+    /// Public polynomial of the group, to verify partial beacons of its nodes.
+    #[cfg(feature = "arkworks")]
+    pub fn public_polynomial(&self) -> Result<crate::partial::PublicPolynomial> {
+        crate::partial::PublicPolynomial::new(&self.coefficients)
+    }
+
+    /// Info of the chain produced by the group, with its hash computed as drand does.
+    pub fn chain_info(&self) -> Result<ChainInfo> {
+        Ok(ChainInfo::new(
+            self.public_key(),
+            self.period,
+            self.genesis_time,
+            self.genesis_seed.clone(),
+            self.scheme_id.clone(),
+            self.beacon_id.clone(),
+        )?)
+    }
+}
+
+impl Node {
+    /// Address the node is reachable at, such as `drand.example.com:443`.
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    /// Identity public key of the node. It is not the key of its share.
+    pub fn key(&self) -> Vec<u8> {
+        self.key.clone()
+    }
+
+    pub fn tls(&self) -> bool {
+        self.tls
+    }
+
+    /// Signature of the node identity. It is empty in group files predating it.
+    pub fn signature(&self) -> Vec<u8> {
+        self.signature.clone()
+    }
+
+    /// Index of the node share.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+}
+
+impl FromStr for Group {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let file: GroupFile = toml::from_str(s).map_err(anyhow::Error::from)?;
+        if file.public_key.coefficients.is_empty() {
+            return Err(Error::Other(anyhow!("group has no public key")));
+        }
+        if file.threshold == 0 || file.threshold > file.nodes.len() {
+            return Err(Error::Other(anyhow!(
+                "threshold {} is invalid for {} nodes",
+                file.threshold,
+                file.nodes.len()
+            )));
+        }
+        let nodes = file
+            .nodes
+            .into_iter()
+            .map(|node| {
+                Ok(Node {
+                    address: node.address,
+                    key: decode(&node.key)?,
+                    tls: node.tls,
+                    signature: decode(&node.signature)?,
+                    index: node.index,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            threshold: file.threshold,
+            period: parse_duration(&file.period)?,
+            catchup_period: parse_duration(&file.catchup_period)?,
+            genesis_time: file.genesis_time,
+            transition_time: file.transition_time,
+            genesis_seed: decode(&file.genesis_seed)?,
+            scheme_id: file.scheme_id,
+            beacon_id: Some(file.id)
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| "default".to_string()),
+            nodes,
+            coefficients: file
+                .public_key
+                .coefficients
+                .iter()
+                .map(|coefficient| decode(coefficient))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GroupFile {
+    threshold: usize,
+    period: String,
+    #[serde(default)]
+    catchup_period: String,
+    genesis_time: u64,
+    #[serde(default)]
+    transition_time: u64,
+    #[serde(default)]
+    genesis_seed: String,
+    #[serde(rename = "SchemeID", default = "default_scheme_id")]
+    scheme_id: String,
+    #[serde(rename = "ID", default)]
+    id: String,
+    nodes: Vec<NodeFile>,
+    public_key: PublicKeyFile,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NodeFile {
+    address: String,
+    key: String,
+    #[serde(rename = "TLS", default)]
+    tls: bool,
+    #[serde(default)]
+    signature: String,
+    index: u16,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PublicKeyFile {
+    coefficients: Vec<String>,
+}
+
+/// Group files predating schemes are chained.
+fn default_scheme_id() -> String {
+    "pedersen-bls-chained".to_string()
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value).map_err(|e| Error::Other(anyhow!("invalid hex {value}: {e}")))
+}
+
+/// Seconds in a Go duration, such as `30s` or `1m30s`. Only whole seconds are supported. An empty duration is 0.
+fn parse_duration(duration: &str) -> Result<u64> {
+    let invalid = || Error::Other(anyhow!("invalid duration {duration}"));
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    for c in duration.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = value
+            .checked_mul(unit)
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or_else(invalid)?;
+        number.clear();
+    }
+    match number.is_empty() {
+        // a bare 0 is a valid Go duration
+        true => Ok(seconds),
+        false if number == "0" && seconds == 0 => Ok(0),
+        false => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    /// drand mainnet group, with nodes shortened to two
+    const MAINNET_GROUP: &str = r#"
+Threshold = 2
+Period = "30s"
+CatchupPeriod = "15s"
+GenesisTime = 1595431050
+TransitionTime = 1595431050
+GenesisSeed = "176f93498eac9ca337150b46d21dd58673ea4e3581185f869672e59fa4cb390a"
+SchemeID = "pedersen-bls-chained"
+ID = "default"
+
+[[Nodes]]
+  Address = "drand.example.com:443"
+  Key = "8f6e58c3dbc6d7e58e32c5e6b3f6e1e5f1f9c8d6c5a1f3e6e2a4c1d0f5b3e8d1c7a9b2f4e6d8c0a1b3c5d7e9f1a3b5c7"
+  TLS = true
+  Index = 0
+
+[[Nodes]]
+  Address = "drand.example.org:443"
+  Key = "a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3a1"
+  TLS = true
+  Signature = "00"
+  Index = 1
+
+[PublicKey]
+  Coefficients = ["868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31", "8a7e3c7fe4a8e3b45c5a8cfa4d5dca7d9b1e7a6f5d2c6c7b3c9e2a7b2a8e1d3f9d0c8c6b5a4f3e2d1c0b9a8f7e6d5ca1"]
+"#;
+
+    #[test]
+    fn group_works() {
+        let group: Group = MAINNET_GROUP.parse().unwrap();
+        assert_eq!(group.threshold(), 2);
+        assert_eq!(group.period(), 30);
+        assert_eq!(group.catchup_period(), 15);
+        assert_eq!(group.nodes().len(), 2);
+        assert_eq!(group.nodes()[1].index(), 1);
+        assert_eq!(group.nodes()[1].signature(), vec![0]);
+        assert!(group.nodes()[0].signature().is_empty());
+        assert_eq!(group.coefficients().len(), 2);
+
+        // the chain info derived from the group is the one served by relays
+        let info = group.chain_info().unwrap();
+        assert_eq!(info, chained_chain_info());
+        assert!(info.verify_hash());
+    }
+
+    #[test]
+    fn group_failure_works() {
+        assert!("".parse::<Group>().is_err());
+        let invalid_threshold = MAINNET_GROUP.replace("Threshold = 2", "Threshold = 3");
+        assert!(invalid_threshold.parse::<Group>().is_err());
+        let invalid_period = MAINNET_GROUP.replace(r#"Period = "30s""#, r#"Period = "30ms""#);
+        assert!(invalid_period.parse::<Group>().is_err());
+    }
+
+    #[test]
+    fn parse_duration_works() {
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("1m30s").unwrap(), 90);
+        assert_eq!(parse_duration("1h").unwrap(), 3600);
+        assert_eq!(parse_duration("0").unwrap(), 0);
+        assert_eq!(parse_duration("").unwrap(), 0);
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("1.5s").is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! gRPC client for the drand `Public` service.
+//!
+//! drand nodes expose the same chain info and beacons over gRPC as relays do over HTTP. Responses are translated to their HTTP API counterpart,
+//! and go through the same caching and verification as [`HttpClient`].
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use tonic::{
+    codec::{ProstCodec, Streaming},
+    codegen::http::uri::PathAndQuery,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Code,
+};
+
+use crate::{
+    beacon::{ApiBeacon, RandomnessBeacon},
+    chain::{ChainInfo, ChainOptions, RoundingMode},
+    error::{Error, RelayError, Result},
+    proto::{self, beacon_json, chain_info_json},
+    transport::Transport,
+    HttpClient,
+};
+
+const PUBLIC_RAND: &str = "/drand.Public/PublicRand";
+const PUBLIC_RAND_STREAM: &str = "/drand.Public/PublicRandStream";
+const CHAIN_INFO: &str = "/drand.Public/ChainInfo";
+
+/// gRPC transport, querying the `Public` service of a drand node.
+/// HTTP API paths are mapped to their gRPC method, and responses are returned in the HTTP API format.
+#[derive(Clone)]
+pub struct GrpcTransport {
+    base_url: String,
+    grpc: tonic::client::Grpc<Channel>,
+}
+
+impl GrpcTransport {
+    /// Connection to the node is established lazily, on the first request.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let mut endpoint = Endpoint::from_shared(base_url.to_string()).map_err(|e| {
+            Error::Other(anyhow!(
+                "{e}. The URL should look like \"https://api.drand.sh:443\"."
+            ))
+        })?;
+        if endpoint.uri().scheme_str() == Some("https") {
+            endpoint = endpoint
+                .tls_config(ClientTlsConfig::new())
+                .map_err(|e| Error::Other(anyhow!(e)))?;
+        }
+        Ok(Self::with_channel(endpoint.connect_lazy(), base_url))
+    }
+
+    /// Create a transport reusing an existing tonic channel, with its connection and TLS configuration.
+    pub fn with_channel(channel: Channel, base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            grpc: tonic::client::Grpc::new(channel),
+        }
+    }
+
+    async fn chain_info(&self) -> Result<proto::ChainInfoPacket> {
+        let mut grpc = self.grpc.clone();
+        grpc.ready().await.map_err(RelayError::network)?;
+        let response = grpc
+            .unary(
+                tonic::Request::new(proto::ChainInfoRequest::default()),
+                PathAndQuery::from_static(CHAIN_INFO),
+                ProstCodec::default(),
+            )
+            .await
+            .map_err(status_error)?;
+        Ok(response.into_inner())
+    }
+
+    /// Round 0 retrieves the latest beacon.
+    async fn public_rand(&self, round_number: u64) -> Result<proto::PublicRandResponse> {
+        let mut grpc = self.grpc.clone();
+        grpc.ready().await.map_err(RelayError::network)?;
+        let response = grpc
+            .unary(
+                tonic::Request::new(proto::PublicRandRequest {
+                    round: round_number,
+                    metadata: None,
+                }),
+                PathAndQuery::from_static(PUBLIC_RAND),
+                ProstCodec::default(),
+            )
+            .await
+            .map_err(status_error)?;
+        Ok(response.into_inner())
+    }
+
+    /// Beacons starting from `round_number`, then as they are emitted. Round 0 starts from the latest beacon.
+    async fn public_rand_stream(
+        &self,
+        round_number: u64,
+    ) -> Result<Streaming<proto::PublicRandResponse>> {
+        let mut grpc = self.grpc.clone();
+        grpc.ready().await.map_err(RelayError::network)?;
+        let response = grpc
+            .server_streaming(
+                tonic::Request::new(proto::PublicRandRequest {
+                    round: round_number,
+                    metadata: None,
+                }),
+                PathAndQuery::from_static(PUBLIC_RAND_STREAM),
+                ProstCodec::default(),
+            )
+            .await
+            .map_err(status_error)?;
+        Ok(response.into_inner())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for GrpcTransport {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        // Query parameters only bust HTTP caches, and have no gRPC equivalent.
+        let path = path.split('?').next().unwrap_or_default();
+        match path.split('/').collect::<Vec<&str>>().as_slice() {
+            ["info"] => Ok(chain_info_json(&self.chain_info().await?)),
+            ["public", "latest"] => Ok(beacon_json(&self.public_rand(0).await?)),
+            ["public", round] => {
+                let round = round.parse().map_err(anyhow::Error::from)?;
+                Ok(beacon_json(&self.public_rand(round).await?))
+            }
+            _ => Err(Error::Other(anyhow!("{path} has no gRPC equivalent"))),
+        }
+    }
+}
+
+/// Relay error matching the gRPC `status` of a failed call, as [`RelayError`] does for HTTP statuses.
+fn status_error(status: tonic::Status) -> Error {
+    let body = status.message().to_string();
+    match status.code() {
+        Code::NotFound => RelayError::NotFound { body }.into(),
+        Code::ResourceExhausted => RelayError::RateLimited {
+            body,
+            retry_after: None,
+        }
+        .into(),
+        Code::Unavailable | Code::DeadlineExceeded => RelayError::network(status).into(),
+        Code::Internal | Code::Unknown => RelayError::Server { status: 500, body }.into(),
+        _ => Error::Other(anyhow!(status)),
+    }
+}
+
+/// gRPC Client for drand
+/// Queries the `Public` service of a drand node, with the same caching and verification `options` as [`HttpClient`]
+pub struct GrpcClient {
+    client: HttpClient,
+    transport: GrpcTransport,
+}
+
+impl GrpcClient {
+    pub fn new(base_url: &str, options: Option<ChainOptions>) -> Result<Self> {
+        Ok(Self::with_transport(GrpcTransport::new(base_url)?, options))
+    }
+
+    pub fn with_transport(transport: GrpcTransport, options: Option<ChainOptions>) -> Self {
+        Self {
+            client: HttpClient::with_transport(transport.clone(), options),
+            transport,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.client.base_url()
+    }
+
+    pub fn options(&self) -> ChainOptions {
+        self.client.options()
+    }
+
+    pub async fn chain_info(&self) -> Result<ChainInfo> {
+        self.client.chain_info().await
+    }
+
+    pub async fn latest(&self) -> Result<RandomnessBeacon> {
+        self.client.latest().await
+    }
+
+    pub async fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        self.client.get(round_number).await
+    }
+
+    pub async fn get_by_unix_time(&self, round_unix_time: u64) -> Result<RandomnessBeacon> {
+        self.client.get_by_unix_time(round_unix_time).await
+    }
+
+    pub async fn get_by_unix_time_rounded(
+        &self,
+        round_unix_time: u64,
+        rounding: RoundingMode,
+    ) -> Result<RandomnessBeacon> {
+        self.client
+            .get_by_unix_time_rounded(round_unix_time, rounding)
+            .await
+    }
+
+    /// Beacons pushed by the node, starting from `round_number`. Round 0 starts from the latest beacon.
+    /// Each beacon is verified against the chain, unless beacon verification is disabled.
+    pub async fn stream(
+        &self,
+        round_number: u64,
+    ) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + Send> {
+        let chain = self.client.chain().await?;
+        let is_beacon_verification = self.options().is_beacon_verification();
+        let responses = self.transport.public_rand_stream(round_number).await?;
+
+        Ok(responses.map(move |response| {
+            let response = response.map_err(status_error)?;
+            let beacon = ApiBeacon::try_from(&response)?;
+            let beacon = chain.beacon(beacon);
+            if is_beacon_verification {
+                chain.verify_detailed(&beacon)?;
+            }
+            Ok(beacon)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_error_works() {
+        let err = status_error(tonic::Status::not_found("round not found"));
+        assert!(matches!(err.relay(), Some(RelayError::NotFound { .. })));
+        assert!(err.is_retryable());
+        let err = status_error(tonic::Status::unavailable("node is down"));
+        assert!(matches!(err.relay(), Some(RelayError::Network(_))));
+        let err = status_error(tonic::Status::invalid_argument("invalid round"));
+        assert!(matches!(err, Error::Other(_)));
+        assert!(!err.is_retryable());
+
+        assert!(matches!(
+            GrpcTransport::new("not a url"),
+            Err(Error::Other(_))
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+use crate::chain::ChainOptions;
+use crate::{
+    beacon::RandomnessBeacon,
+    circuit_breaker::CircuitBreaker,
+    error::{Error, Result},
+    HttpClient,
+};
+
+/// Client racing requests across multiple relays of the same chain
+/// Each request is sent to all relays at once. The first response that validates is returned, and the others are cancelled
+/// It reduces tail latency, at the cost of additional requests. Relays should be configured with beacon verification enabled
+pub struct HedgedClient {
+    clients: Vec<HttpClient>,
+    breakers: Option<Vec<CircuitBreaker>>,
+}
+
+impl HedgedClient {
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn new(base_urls: &[&str], options: Option<ChainOptions>) -> Result<Self> {
+        let clients = base_urls
+            .iter()
+            .map(|base_url| HttpClient::new(base_url, options.clone()))
+            .collect::<Result<Vec<HttpClient>>>()?;
+        Self::from_clients(clients)
+    }
+
+    /// Race requests across already configured clients.
+    pub fn from_clients(clients: Vec<HttpClient>) -> Result<Self> {
+        if clients.is_empty() {
+            return Err(Error::Other(anyhow!("at least one relay is required")));
+        }
+        Ok(Self {
+            clients,
+            breakers: None,
+        })
+    }
+
+    /// Stop sending requests to a relay after `failure_threshold` consecutive failures, so that it does not take part in races it cannot win.
+    /// Once `cooldown` has elapsed, a single request probes the relay, which is raced again if it succeeds.
+    /// This relies on [`std::time::Instant`], which is not available on wasm32-unknown-unknown.
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.breakers = Some(
+            self.clients
+                .iter()
+                .map(|_| CircuitBreaker::new(failure_threshold, cooldown))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn clients(&self) -> &[HttpClient] {
+        &self.clients
+    }
+
+    pub async fn latest(&self) -> Result<RandomnessBeacon> {
+        let requests = self.available()?.into_iter().map(|(client, breaker)| {
+            Box::pin(async move {
+                match breaker {
+                    Some(breaker) => breaker.guard(client.latest()).await,
+                    None => client.latest().await,
+                }
+            })
+        });
+        let (beacon, _cancelled) = futures::future::select_ok(requests).await?;
+        Ok(beacon)
+    }
+
+    pub async fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        let requests = self.available()?.into_iter().map(|(client, breaker)| {
+            Box::pin(async move {
+                match breaker {
+                    Some(breaker) => breaker.guard(client.get(round_number)).await,
+                    None => client.get(round_number).await,
+                }
+            })
+        });
+        let (beacon, _cancelled) = futures::future::select_ok(requests).await?;
+        Ok(beacon)
+    }
+
+    /// Clients whose circuit breaker lets a request through, with their breaker.
+    fn available(&self) -> Result<Vec<(&HttpClient, Option<&CircuitBreaker>)>> {
+        let available: Vec<_> = match &self.breakers {
+            Some(breakers) => self
+                .clients
+                .iter()
+                .zip(breakers)
+                .filter(|(_, breaker)| breaker.allow())
+                .map(|(client, breaker)| (client, Some(breaker)))
+                .collect(),
+            None => self.clients.iter().map(|client| (client, None)).collect(),
+        };
+        if available.is_empty() {
+            return Err(Error::Other(anyhow!("all relays are failing, retry later")));
+        }
+        Ok(available)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, invalid_beacon};
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    async fn relay(beacon: &crate::beacon::ApiBeacon) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(beacon).unwrap())
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn hedged_client_works() {
+        let invalid_server = relay(&invalid_beacon()).await;
+        let valid_server = relay(&chained_beacon()).await;
+
+        let client = HedgedClient::new(
+            &[invalid_server.url().as_str(), valid_server.url().as_str()],
+            Some(ChainOptions::new(
+                true,
+                true,
+                Some(chained_chain_info().into()),
+            )),
+        )
+        .unwrap();
+
+        // only the valid relay response is accepted
+        let latest = match client.latest().await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(latest.beacon(), chained_beacon());
+
+        // no relay provides a valid response
+        let client = HedgedClient::new(&[invalid_server.url().as_str()], None).unwrap();
+        match client.latest().await {
+            Ok(_beacon) => panic!("Beacon should not validate"),
+            Err(_err) => (),
+        }
+
+        assert!(HedgedClient::new(&[], None).is_err());
+    }
+
+    #[tokio::test]
+    async fn hedged_client_circuit_breaker_works() {
+        let mut failing_server = mockito::Server::new_async().await;
+        let failing_mock = failing_server
+            .mock("GET", "/public/latest")
+            .with_status(500)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = HedgedClient::new(&[failing_server.url().as_str()], None)
+            .unwrap()
+            .with_circuit_breaker(2, Duration::from_secs(60));
+        for _ in 0..4 {
+            assert!(client.latest().await.is_err());
+        }
+        // the failing relay is not requested again once its breaker opened
+        failing_mock.assert_async().await;
+    }
+}
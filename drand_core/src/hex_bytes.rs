@@ -0,0 +1,87 @@
+//! Serde helpers for byte fields, such as signatures and public keys. Use with `#[serde(with = "crate::hex_bytes")]`.
+//!
+//! They are hex encoded in human readable formats such as JSON, as drand serves them, and raw bytes in binary formats such as CBOR.
+//! Both encodings are accepted when deserializing. Hex strings may be uppercase, and carry the `0x` prefix of Ethereum tooling.
+
+use std::fmt;
+
+use serde::{de, Deserializer, Serializer};
+
+pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    // formats do not tell their encoding reliably through buffered content, such as untagged enums, so any encoding is accepted
+    deserializer.deserialize_any(BytesVisitor)
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        let value = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        hex::decode(value).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(value.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(value)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{tests::chained_beacon, ApiBeacon};
+    use crate::chain::{tests::chained_chain_info, ChainInfo};
+
+    #[test]
+    fn hex_bytes_prefixed_works() {
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        for field in ["randomness", "signature", "previous_signature"] {
+            beacon[field] = format!("0x{}", beacon[field].as_str().unwrap()).into();
+        }
+        let parsed: ApiBeacon = serde_json::from_value(beacon).unwrap();
+        assert_eq!(parsed, chained_beacon());
+
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        for field in ["public_key", "hash", "groupHash"] {
+            info[field] = format!("0X{}", info[field].as_str().unwrap().to_uppercase()).into();
+        }
+        let parsed: ChainInfo = serde_json::from_value(info).unwrap();
+        assert_eq!(parsed, chained_chain_info());
+        // it is normalized to lowercase without prefix
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(chained_chain_info()).unwrap()
+        );
+
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["signature"] = "0x0x00".into();
+        assert!(serde_json::from_value::<ApiBeacon>(beacon).is_err());
+    }
+}
@@ -1,430 +1,2504 @@
-use anyhow::{anyhow, Result};
-use std::{str::FromStr, sync::Mutex};
+use anyhow::anyhow;
+use arc_swap::ArcSwapOption;
+use futures::{future::Either, Stream, StreamExt};
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+use std::str::FromStr;
+use std::{
+    future::Future,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::disk_cache::DiskCache;
+#[cfg(all(feature = "hyper", not(feature = "reqwest")))]
+use crate::transport::{HyperTransport, DEFAULT_MAX_RESPONSE_SIZE};
+use crate::{
+    api_client::{ApiClient, RelayHealth},
+    beacon::{RandomnessBeacon, VerifiedBeacon},
+    beacon_cache::{BeaconCache, BeaconCacheStats},
+    chain::{Chain, ChainInfo, ChainOptions, ChainVerification, RoundingMode},
+    error::{Error, RelayError, Result},
+    metrics::MetricsSink,
+    transport::{Conditional, Transport},
+};
+#[cfg(feature = "reqwest")]
 use crate::{
-    beacon::{ApiBeacon, RandomnessBeacon},
-    chain::{ChainInfo, ChainOptions},
+    http_options::{HttpOptions, RedirectPolicy, TlsOptions},
+    transport::HttpTransport,
 };
 
+/// Delay before polling the relay again, when it has not emitted a new beacon.
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Rounds retrieved in parallel, when retrieving a range of rounds.
+const RANGE_CONCURRENCY: usize = 8;
+/// Longest delay requested by a rate limiting relay which is honoured, by default.
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+/// Attempts to retrieve the next beacon, while the relay has not received it yet.
+const NEXT_ATTEMPTS: usize = 5;
+
+struct CachedChain {
+    chain: Chain,
+    etag: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+impl CachedChain {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .map_or(false, |expires_at| Instant::now() >= expires_at)
+    }
+}
+
 /// HTTP Client for drand
 /// Queries a specified HTTP endpoint given by `chain`, with specific `options`
 /// By default, the client verifies answers, and caches retrieved chain informations
+/// The client is `Send + Sync`, and cheap to clone. Clones share their connection pool and caches, so it can be stored in a web framework shared state, or moved into tasks
+#[derive(Clone)]
 pub struct HttpClient {
-    base_url: url::Url,
+    api: ApiClient,
     options: ChainOptions,
-    cached_chain_info: Mutex<Option<ChainInfo>>,
-    http_client: reqwest::Client,
+    // read without locking, so that concurrent requests never wait on each other once chain info is cached
+    cached_chain: Arc<ArcSwapOption<CachedChain>>,
+    // held while chain info is requested, so that concurrent callers share a single request
+    chain_refresh: Arc<futures::lock::Mutex<()>>,
+    cache_ttl: Option<Duration>,
+    trusted_chain: Option<Chain>,
+    max_staleness: Option<u64>,
+    max_clock_skew: Option<Duration>,
+    max_retry_after: Duration,
+    beacon_cache: Option<Arc<Mutex<BeaconCache>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    disk_cache: Option<Arc<DiskCache>>,
 }
 
 impl HttpClient {
+    /// Configure a new client step by step.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::new()
+    }
+
+    /// Create a client querying the relay over HTTP, with reqwest, or hyper alone when reqwest is not enabled.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
     pub fn new(base_url: &str, options: Option<ChainOptions>) -> Result<Self> {
-        // The most common error is when user forget to add protocol in front of the provided URL string.
-        // The error provided by reqwest::Url is rather obscure when that happens.
-        let mut url = reqwest::Url::parse(base_url).map_err(|e| {
-            if e == url::ParseError::RelativeUrlWithoutBase {
-                anyhow!("{e}. You might need to add \"https://\" to the provided URL.")
-            } else {
-                anyhow!(e)
-            }
-        })?;
-        // Ensure base URL ends with a trailing slash.
-        // Given it's the base for API calls, it allows for easier joins in other methods.
-        if !url.path().ends_with('/') {
-            url.set_path(&format!("{}/", url.path()));
-        }
-        Ok(Self {
-            base_url: url,
-            options: options.unwrap_or_default(),
-            cached_chain_info: Mutex::new(None),
-            http_client: reqwest::Client::builder().build().unwrap(),
-        })
+        Ok(Self::with_api(ApiClient::new(base_url)?, options))
     }
 
-    async fn chain_info_no_cache(&self) -> Result<ChainInfo> {
-        let response = self
-            .http_client
-            .get(self.base_url.join("info")?)
-            .send()
-            .await?;
-        let info = match response.error_for_status_ref() {
-            Ok(_response) => response.json::<ChainInfo>().await?,
-            Err(_err) => {
-                return Err(anyhow!(
-                    "{}",
-                    response.text().await.map_err(|e| anyhow!(e))?
-                ))
+    /// Create a client for the chain identified by `chain_hash`, on a relay serving multiple chains.
+    /// Requests are sent to `{base_url}/{chain_hash}/`, and the retrieved chain info has to match `chain_hash`, which is recomputed from its contents.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn for_chain(base_url: &str, chain_hash: &[u8]) -> Result<Self> {
+        let base_url = format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            hex::encode(chain_hash)
+        );
+        Self::new(
+            &base_url,
+            Some(ChainOptions::new(
+                true,
+                true,
+                Some(ChainVerification::new(Some(chain_hash.to_vec()), None)),
+            )),
+        )
+    }
+
+    /// Create a client for the chain whose beacon ID is `beacon_id`, such as `default` or `quicknet`, on a relay serving multiple chains.
+    /// Chains listed by the relay are looked up until one has this beacon ID. Its hash is then pinned, as with [`HttpClient::for_chain`].
+    /// Chains whose info cannot be retrieved or verified are skipped.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub async fn for_beacon_id(base_url: &str, beacon_id: &str) -> Result<Self> {
+        let api = ApiClient::new(base_url)?;
+        for chain_hash in api.chains().await? {
+            let client = Self::with_chain_api(&api, &chain_hash);
+            // another chain of the relay failing does not prevent finding the requested one
+            let Ok(info) = client.chain_info().await else {
+                continue;
+            };
+            if info.beacon_id() == beacon_id {
+                return Ok(client);
             }
-        };
-        match self.options().verify(&info) {
-            true => Ok(info),
-            false => Err(anyhow!("Chain info is invalid")),
         }
+        Err(anyhow!("{base_url} does not serve a chain with beacon ID {beacon_id}").into())
     }
 
-    fn beacon_url(&self, round: String) -> Result<reqwest::Url> {
-        let mut url = self.base_url.join(&format!("public/{round}"))?;
-        if !self.options().is_cache() {
-            url.query_pairs_mut()
-                .append_key_only(format!("{}", rand::random::<u64>()).as_str());
+    /// Client for the chain identified by `chain_hash`, sharing the transport of `api`, which points to the relay root.
+    pub(crate) fn with_chain_api(api: &ApiClient, chain_hash: &[u8]) -> Self {
+        let options = ChainOptions::new(
+            true,
+            true,
+            Some(ChainVerification::new(Some(chain_hash.to_vec()), None)),
+        );
+        Self::with_api(api.for_chain(chain_hash), Some(options))
+    }
+
+    /// Create a client for a chain whose `info` is known ahead of time, such as embedded in the application.
+    /// Chain info is never requested from the relay, which saves a round-trip, and only beacons are trusted to it.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn with_chain_info(
+        base_url: &str,
+        info: ChainInfo,
+        options: Option<ChainOptions>,
+    ) -> Result<Self> {
+        Self::new(base_url, options)?.with_trusted_chain_info(info)
+    }
+
+    /// Use `info` as the chain info, instead of requesting it from the relay.
+    pub fn with_trusted_chain_info(mut self, info: ChainInfo) -> Result<Self> {
+        self.trusted_chain = Some(self.verify_chain(info)?);
+        Ok(self)
+    }
+
+    /// Create a client whose HTTP transport follows `http_options`, such as a TLS policy.
+    #[cfg(feature = "reqwest")]
+    pub fn with_http_options(
+        base_url: &str,
+        options: Option<ChainOptions>,
+        http_options: HttpOptions,
+    ) -> Result<Self> {
+        Ok(Self::with_api(
+            ApiClient::with_http_options(base_url, http_options)?,
+            options,
+        ))
+    }
+
+    /// Create a client reusing an existing reqwest client, with its connection pool, proxy, and TLS configuration.
+    #[cfg(feature = "reqwest")]
+    pub fn with_http_client(
+        http_client: reqwest::Client,
+        base_url: &str,
+        options: Option<ChainOptions>,
+    ) -> Result<Self> {
+        Ok(Self::with_api(
+            ApiClient::with_http_client(http_client, base_url)?,
+            options,
+        ))
+    }
+
+    fn with_api(api: ApiClient, options: Option<ChainOptions>) -> Self {
+        let options = options.unwrap_or_default();
+        Self {
+            api: api.with_cache(options.is_cache()),
+            options,
+            cached_chain: Arc::new(ArcSwapOption::empty()),
+            chain_refresh: Arc::new(futures::lock::Mutex::new(())),
+            cache_ttl: None,
+            trusted_chain: None,
+            max_staleness: None,
+            max_clock_skew: None,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+            beacon_cache: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            disk_cache: None,
+        }
+    }
+
+    /// Refresh cached chain info once `cache_ttl` has elapsed. By default, chain info is cached for the lifetime of the client.
+    /// Refreshes are conditional requests: chain info is only downloaded and verified again when the relay reports a change.
+    /// This relies on [`std::time::Instant`], which is not available on wasm32-unknown-unknown.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = Some(cache_ttl);
+        self
+    }
+
+    /// Fail [`HttpClient::latest`] when the beacon is more than `periods` periods older than the local clock expects.
+    /// It detects a relay stuck on an old round, which would otherwise be served as the latest one.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub fn with_max_staleness(mut self, periods: u64) -> Self {
+        self.max_staleness = Some(periods);
+        self
+    }
+
+    /// Reject beacons whose round is after the current one, given the local clock ahead by up to `max_clock_skew`.
+    /// It prevents a relay from fabricating rounds which have not been emitted yet, such as on an unverified chain.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = Some(max_clock_skew);
+        self
+    }
+
+    /// Keep up to `capacity` beacons retrieved with [`HttpClient::get`] or [`HttpClient::get_many`] in memory, so that asking for the same round again is neither requested nor verified twice.
+    /// The least recently used beacon is evicted first.
+    pub fn with_beacon_cache(mut self, capacity: usize) -> Self {
+        self.beacon_cache = Some(Arc::new(Mutex::new(BeaconCache::new(capacity))));
+        self
+    }
+
+    /// Persist chain info and beacons under the `path` directory, so that they are not requested again by the next process using the same directory.
+    /// Documents loaded from disk are verified again, as if they had been retrieved from the relay.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_disk_cache(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        self.disk_cache = Some(Arc::new(DiskCache::new(path.as_ref(), &self.base_url())));
+        self
+    }
+
+    /// Lookups served by the beacon cache. `None` when beacons are not cached.
+    pub fn beacon_cache_stats(&self) -> Option<BeaconCacheStats> {
+        self.beacon_cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+
+    /// Longest delay to wait before retrying, when a rate limiting relay asks for it with `Retry-After`. Defaults to 60 seconds.
+    pub fn with_max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// Report requests, retries, and chain info cache lookups to `metrics`.
+    /// Latencies rely on [`std::time::Instant`], which is not available on wasm32-unknown-unknown.
+    pub fn with_metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.api = self.api.with_metrics(metrics);
+        self
+    }
+
+    /// Reject relay responses which are not exactly the documents drand relays produce. See [`ApiClient::with_strict`].
+    pub fn with_strict(mut self, is_strict: bool) -> Self {
+        self.api = self.api.with_strict(is_strict);
+        self
+    }
+
+    async fn chain_no_cache(&self) -> Result<Chain> {
+        self.verify_chain(self.api.chain_info().await?)
+    }
+
+    fn verify_chain(&self, info: ChainInfo) -> Result<Chain> {
+        match self.options().verify(&info) {
+            true => Chain::new(info),
+            false => Err(Error::InvalidChainInfo),
         }
-        Ok(url)
     }
 
-    async fn verify_beacon(&self, beacon: RandomnessBeacon) -> Result<RandomnessBeacon> {
+    async fn verify_beacon(
+        &self,
+        chain: &Chain,
+        beacon: RandomnessBeacon,
+    ) -> Result<RandomnessBeacon> {
+        if let Some(max_clock_skew) = self.max_clock_skew {
+            let max_round = chain.round_at(unix_time()? + max_clock_skew.as_secs());
+            if beacon.round() > max_round {
+                return Err(Error::FutureRound {
+                    round: beacon.round(),
+                    max_round,
+                });
+            }
+        }
         if !self.options().is_beacon_verification() {
             return Ok(beacon);
         }
 
-        match beacon.verify(self.chain_info().await?)? {
-            true => Ok(beacon),
-            false => Err(anyhow!("Beacon does not validate")),
-        }
+        chain.verify_detailed(&beacon)?;
+        Ok(beacon)
+    }
+
+    /// Create a client retrieving relay responses through a custom transport.
+    pub fn with_transport(
+        transport: impl Transport + 'static,
+        options: Option<ChainOptions>,
+    ) -> Self {
+        Self::with_api(ApiClient::with_transport(transport), options)
+    }
+
+    /// Low-level client used to query the relay, without caching nor verification.
+    pub fn api(&self) -> &ApiClient {
+        &self.api
     }
 
     pub fn base_url(&self) -> String {
-        self.base_url.to_string()
+        self.api.base_url()
     }
 
     pub fn options(&self) -> ChainOptions {
         self.options.clone()
     }
 
-    pub async fn chain_info(&self) -> Result<ChainInfo> {
-        if self.options().is_cache() {
-            let cached = self.cached_chain_info.lock().unwrap().to_owned();
-            match cached {
-                Some(info) => Ok(info),
-                None => {
-                    let info = self.chain_info_no_cache().await?;
-                    *self.cached_chain_info.lock().unwrap() = Some(info.clone());
-                    Ok(info)
+    /// Chain the client is associated to, used to verify beacons and compute their time.
+    pub async fn chain(&self) -> Result<Chain> {
+        if let Some(chain) = &self.trusted_chain {
+            return Ok(chain.clone());
+        }
+        if !self.options().is_cache() {
+            return self.chain_no_cache().await;
+        }
+
+        if let Some(chain) = self.fresh_chain() {
+            return Ok(chain);
+        }
+        let _refresh = self.chain_refresh.lock().await;
+        // another caller may have retrieved chain info while this one was waiting
+        if let Some(chain) = self.fresh_chain() {
+            return Ok(chain);
+        }
+        if let Some(metrics) = self.api.metrics() {
+            metrics.cache_miss();
+        }
+        let cached = self.cached_chain.load_full();
+        #[cfg(not(target_arch = "wasm32"))]
+        if cached.is_none() {
+            if let Some(chain) = self.disk_chain() {
+                return Ok(chain);
+            }
+        }
+        self.revalidate_chain(cached).await
+    }
+
+    /// Cached chain info, if it has not expired.
+    fn fresh_chain(&self) -> Option<Chain> {
+        let cached = self
+            .cached_chain
+            .load_full()
+            .filter(|cached| !cached.is_expired())?;
+        if let Some(metrics) = self.api.metrics() {
+            metrics.cache_hit();
+        }
+        Some(cached.chain.clone())
+    }
+
+    fn cache_chain(&self, chain: Chain, etag: Option<String>) {
+        self.cached_chain.store(Some(Arc::new(CachedChain {
+            chain,
+            etag,
+            expires_at: self.cache_ttl.map(|cache_ttl| Instant::now() + cache_ttl),
+        })));
+    }
+
+    /// Chain info persisted by a previous process, if it is still valid. It is then cached in memory.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn disk_chain(&self) -> Option<Chain> {
+        let info = self.disk_cache.as_ref()?.chain_info()?;
+        let chain = self.verify_chain(info).ok()?;
+        self.cache_chain(chain.clone(), None);
+        Some(chain)
+    }
+
+    /// Retrieve chain info again, even if the cached one has not expired. When the relay reports no change, the cached chain info is kept.
+    /// Chain info provided with [`HttpClient::with_chain_info`] is never refreshed.
+    /// Long-running processes can call it to pick up relay-side changes without recreating the client.
+    pub async fn refresh_chain_info(&self) -> Result<ChainInfo> {
+        if let Some(chain) = &self.trusted_chain {
+            return Ok(chain.info());
+        }
+        if !self.options().is_cache() {
+            return Ok(self.chain_no_cache().await?.info());
+        }
+
+        let _refresh = self.chain_refresh.lock().await;
+        let cached = self.cached_chain.load_full();
+        Ok(self.revalidate_chain(cached).await?.info())
+    }
+
+    /// Conditional request for chain info, updating the cache with its outcome.
+    async fn revalidate_chain(&self, cached: Option<Arc<CachedChain>>) -> Result<Chain> {
+        let etag = cached.as_ref().and_then(|cached| cached.etag.clone());
+        let (chain, etag) = match self.api.chain_info_if_none_match(etag.as_deref()).await? {
+            Conditional::Modified(info, etag) => {
+                let chain = self.verify_chain(info)?;
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(disk_cache) = &self.disk_cache {
+                    disk_cache.set_chain_info(&chain.info());
                 }
+                (chain, etag)
             }
-        } else {
-            self.chain_info_no_cache().await
+            Conditional::NotModified => match cached {
+                Some(cached) => (cached.chain.clone(), cached.etag.clone()),
+                None => {
+                    return Err(Error::Other(anyhow!(
+                        "Chain info is not modified, but was never retrieved"
+                    )))
+                }
+            },
+        };
+        self.cache_chain(chain.clone(), etag);
+        Ok(chain)
+    }
+
+    pub async fn chain_info(&self) -> Result<ChainInfo> {
+        Ok(self.chain().await?.info())
+    }
+
+    /// Chain info already known to the client, without querying the relay.
+    fn known_chain(&self) -> Result<Chain> {
+        if let Some(chain) = &self.trusted_chain {
+            return Ok(chain.clone());
+        }
+        match self.cached_chain.load_full() {
+            Some(cached) => Ok(cached.chain.clone()),
+            None => Err(Error::Other(anyhow!(
+                "Chain info has not been retrieved yet. Call chain_info first"
+            ))),
         }
     }
 
+    /// Round at `time`, computed from the chain info known to the client. Nothing is requested from the relay.
+    /// Fails if chain info has not been retrieved and cached yet, or `time` is before the chain genesis.
+    pub fn round_at(&self, time: SystemTime) -> Result<u64> {
+        let unix_time = time
+            .duration_since(UNIX_EPOCH)
+            .map_err(anyhow::Error::from)?
+            .as_secs();
+        self.known_chain()?
+            .round_at_rounded(unix_time, RoundingMode::RoundDown)
+    }
+
+    /// Time of `round`, computed from the chain info known to the client. Nothing is requested from the relay.
+    pub fn time_of_round(&self, round: u64) -> Result<SystemTime> {
+        let unix_time = self.known_chain()?.time_of_round(round);
+        Ok(UNIX_EPOCH + Duration::from_secs(unix_time))
+    }
+
+    /// Round at the current time, computed from the chain info known to the client. Nothing is requested from the relay.
+    pub fn current_round(&self) -> Result<u64> {
+        self.round_at(SystemTime::now())
+    }
+
+    /// Hashes of the chains served by the relay. The client base URL has to be the relay root, such as `https://api.drand.sh`.
+    pub async fn chains(&self) -> Result<Vec<Vec<u8>>> {
+        self.api.chains().await
+    }
+
+    /// Create a client for one of the [`HttpClient::chains`] served by the relay, with the same verification rules as [`HttpClient::for_chain`].
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn chain_client(&self, chain_hash: &[u8]) -> Result<Self> {
+        Self::for_chain(&self.base_url(), chain_hash)
+    }
+
+    /// Progress of the relay, to detect it is lagging behind the chain before trusting [`HttpClient::latest`].
+    pub async fn health(&self) -> Result<RelayHealth> {
+        self.api.health().await
+    }
+
     pub async fn latest(&self) -> Result<RandomnessBeacon> {
         // it is possible to either use round number 0, or to infer the round number based on the current time
         // however, to match the existing endpoint API, using latest independantly seems to be the best approach
-        let beacon = self
-            .http_client
-            .get(self.beacon_url("latest".to_string())?)
-            .send()
-            .await?
-            .json::<ApiBeacon>()
-            .await?;
+        let beacon = self.api.latest().await?;
+
+        let chain = self.chain().await?;
+        let beacon = chain.beacon(beacon);
+        if let Some(max_staleness) = self.max_staleness {
+            let expected = chain.round_at(unix_time()?);
+            if beacon.round() + max_staleness < expected {
+                return Err(RelayError::Stale {
+                    round: beacon.round(),
+                    expected,
+                }
+                .into());
+            }
+        }
+
+        self.verify_beacon(&chain, beacon).await
+    }
+
+    /// Same as [`HttpClient::latest`], with the proof the beacon verified. It is verified even if the client does not verify beacons.
+    pub async fn latest_verified(&self) -> Result<VerifiedBeacon> {
+        let beacon = self.latest().await?;
+        self.verified(beacon).await
+    }
+
+    /// Same as [`HttpClient::get`], with the proof the beacon verified. It is verified even if the client does not verify beacons.
+    pub async fn get_verified(&self, round_number: u64) -> Result<VerifiedBeacon> {
+        let beacon = self.get(round_number).await?;
+        self.verified(beacon).await
+    }
+
+    async fn verified(&self, beacon: RandomnessBeacon) -> Result<VerifiedBeacon> {
+        if self.options().is_beacon_verification() {
+            return Ok(VerifiedBeacon::new_unchecked(beacon));
+        }
+        self.chain().await?.verified(beacon)
+    }
+
+    /// Same as [`HttpClient::latest`], failing with [`RelayError::Timeout`] if it takes longer than `budget` overall.
+    pub async fn latest_with_timeout(&self, budget: Duration) -> Result<RandomnessBeacon> {
+        within(budget, self.latest()).await
+    }
 
-        let info = self.chain_info().await?;
-        let unix_time = info.genesis_time() + beacon.round() * info.period();
-        let beacon = RandomnessBeacon::new(beacon, unix_time);
+    /// Same as [`HttpClient::get`], failing with [`RelayError::Timeout`] if it takes longer than `budget` overall.
+    pub async fn get_with_timeout(
+        &self,
+        round_number: u64,
+        budget: Duration,
+    ) -> Result<RandomnessBeacon> {
+        within(budget, self.get(round_number)).await
+    }
 
-        self.verify_beacon(beacon).await
+    /// Same as [`HttpClient::next`], failing with [`RelayError::Timeout`] if it takes longer than `budget` overall, waiting for the round included.
+    pub async fn next_with_timeout(&self, budget: Duration) -> Result<RandomnessBeacon> {
+        within(budget, self.next()).await
     }
 
     pub async fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
-        let beacon = self
-            .http_client
-            .get(self.beacon_url(round_number.to_string())?)
-            .send()
+        self.get_with_chain(round_number, None).await
+    }
+
+    /// Beacon for `round_number`, looked up in the caches first. `chain` is retrieved unless it is provided.
+    async fn get_with_chain(
+        &self,
+        round_number: u64,
+        chain: Option<&Chain>,
+    ) -> Result<RandomnessBeacon> {
+        if let Some(cache) = &self.beacon_cache {
+            if let Some(beacon) = cache.lock().unwrap().get(round_number) {
+                return Ok(beacon);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let persisted = self
+            .disk_cache
+            .as_ref()
+            .and_then(|disk_cache| disk_cache.beacon(round_number))
+            .filter(|beacon| beacon.round() == round_number);
+        #[cfg(target_arch = "wasm32")]
+        let persisted = None;
+        let is_persisted = persisted.is_some();
+        let beacon = match persisted {
+            Some(beacon) => beacon,
+            None => self.api.get(round_number).await?,
+        };
+
+        let chain = match chain {
+            Some(chain) => chain.clone(),
+            None => self.chain().await?,
+        };
+        let beacon = chain.beacon(beacon);
+
+        let beacon = self.verify_beacon(&chain, beacon).await?;
+        if let Some(cache) = &self.beacon_cache {
+            cache.lock().unwrap().insert(beacon.clone());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(disk_cache) = self.disk_cache.as_ref().filter(|_| !is_persisted) {
+            disk_cache.set_beacon(&beacon.beacon);
+        }
+        Ok(beacon)
+    }
+
+    /// Beacon for the last round emitted at or before `round_unix_time`.
+    pub async fn get_by_unix_time(&self, round_unix_time: u64) -> Result<RandomnessBeacon> {
+        self.get_by_unix_time_rounded(round_unix_time, RoundingMode::RoundDown)
+            .await
+    }
+
+    /// Beacon for the round at `round_unix_time`, `rounding` to the round before or after it.
+    /// Fails with [`crate::chain::BeforeGenesisError`] if `round_unix_time` is before the chain genesis.
+    pub async fn get_by_unix_time_rounded(
+        &self,
+        round_unix_time: u64,
+        rounding: RoundingMode,
+    ) -> Result<RandomnessBeacon> {
+        let round = self
+            .chain()
             .await?
-            .json::<ApiBeacon>()
-            .await?;
+            .round_at_rounded(round_unix_time, rounding)?;
+
+        self.get(round).await
+    }
+
+    /// Wait for the round following the current one to be emitted, then retrieve it.
+    /// The relay is queried a few more times if it has not received the beacon yet.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub async fn next(&self) -> Result<RandomnessBeacon> {
+        let chain = self.chain().await?;
+        let round = chain.round_at(unix_time()?) + 1;
+        self.wait_for(&chain, round).await
+    }
+
+    /// Wait for `round` to be emitted if it has not been yet, then retrieve it.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub async fn get_when_emitted(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        let chain = self.chain().await?;
+        self.wait_for(&chain, round_number).await
+    }
+
+    /// Every round from the next one on, as they are emitted.
+    /// Rounds missed while the stream was not polled are retrieved right away, so that no round is skipped.
+    /// A round which cannot be retrieved yields an error, and is attempted again on the next poll.
+    /// The stream ends after an error which is not retryable, such as a beacon which does not verify, as the round would fail again.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub async fn watch(&self) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + '_> {
+        let chain = self.chain().await?;
+        let first_round = chain.round_at(unix_time()?) + 1;
+        Ok(futures::stream::unfold(Some(first_round), move |round| {
+            let chain = chain.clone();
+            async move {
+                let round = round?;
+                match self.wait_for(&chain, round).await {
+                    Ok(beacon) => Some((Ok(beacon), Some(round + 1))),
+                    Err(err) if err.is_retryable() => Some((Err(err), Some(round))),
+                    Err(err) => Some((Err(err), None)),
+                }
+            }
+        }))
+    }
+
+    /// Verified beacons for every round in `rounds`, in order.
+    /// Up to a handful of rounds are prefetched, so memory stays bounded however long the range is.
+    pub async fn stream_range(
+        &self,
+        rounds: RangeInclusive<u64>,
+    ) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + '_> {
+        self.get_many(rounds, RANGE_CONCURRENCY).await
+    }
+
+    /// Verified beacons for rounds `start` to `end` included, in order. Rounds are retrieved in parallel.
+    /// Fails if any round cannot be retrieved or verified, reporting the error of each failed round.
+    pub async fn get_range(&self, start: u64, end: u64) -> Result<Vec<RandomnessBeacon>> {
+        let results: Vec<Result<RandomnessBeacon>> =
+            self.stream_range(start..=end).await?.collect().await;
+        let mut beacons = Vec::with_capacity(results.len());
+        let mut errors = vec![];
+        for (round, result) in (start..=end).zip(results) {
+            match result {
+                Ok(beacon) => beacons.push(beacon),
+                Err(err) => errors.push(format!("round {round}: {err}")),
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(beacons),
+            false => Err(Error::Other(anyhow!(
+                "{} rounds could not be retrieved. {}",
+                errors.len(),
+                errors.join(", ")
+            ))),
+        }
+    }
+
+    /// Sleep until `round` is emitted, then retrieve it, with a few attempts to tolerate relay propagation delay.
+    async fn wait_for(&self, chain: &Chain, round: u64) -> Result<RandomnessBeacon> {
+        let wait = chain.time_of_round(round).saturating_sub(unix_time()?);
+        futures_timer::Delay::new(Duration::from_secs(wait)).await;
+
+        let mut attempt = 1;
+        loop {
+            match self.get(round).await {
+                Err(err) if attempt < NEXT_ATTEMPTS && err.is_retryable() => {
+                    futures_timer::Delay::new(self.retry_delay(&err)).await;
+                    self.record_retry(&format!("public/{round}"));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Beacons for `rounds`, in the order requested. Up to `concurrency` rounds are retrieved in parallel, which speeds up backfilling historical rounds.
+    /// Chain info is retrieved once, before any beacon is requested. Rounds go through the same caches as [`HttpClient::get`].
+    pub async fn get_many<'a, R>(
+        &'a self,
+        rounds: R,
+        concurrency: usize,
+    ) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + 'a>
+    where
+        R: IntoIterator<Item = u64>,
+        R::IntoIter: 'a,
+    {
+        let chain = self.chain().await?;
+        Ok(futures::stream::iter(rounds)
+            .map(move |round_number| {
+                let chain = chain.clone();
+                async move { self.get_with_chain(round_number, Some(&chain)).await }
+            })
+            // at least one request has to be in flight for the stream to make progress
+            .buffered(concurrency.max(1)))
+    }
+
+    /// Beacons as they are emitted, starting with the latest one.
+    /// Beacons are pushed by the relay when it exposes a stream. Otherwise, the relay is polled once per chain period.
+    pub async fn stream(&self) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + '_> {
+        let chain = self.chain().await?;
+        match self.api.stream().await {
+            Ok(beacons) => Ok(Either::Left(beacons.then(move |beacon| {
+                let chain = chain.clone();
+                async move { self.verify_beacon(&chain, chain.beacon(beacon?)).await }
+            }))),
+            Err(_) => Ok(Either::Right(self.poll(chain))),
+        }
+    }
+
+    /// Delay before retrying a request which failed with `err`. Rate limiting relays are waited for as long as they ask, up to a cap.
+    fn retry_delay(&self, err: &Error) -> Duration {
+        err.relay()
+            .and_then(RelayError::retry_after)
+            .map_or(POLL_RETRY_DELAY, |retry_after| {
+                retry_after.min(self.max_retry_after)
+            })
+    }
+
+    fn record_retry(&self, path: &str) {
+        if let Some(metrics) = self.api.metrics() {
+            metrics.retry(path);
+        }
+    }
+
+    fn poll(&self, chain: Chain) -> impl Stream<Item = Result<RandomnessBeacon>> + '_ {
+        let period = Duration::from_secs(chain.info().period());
+        futures::stream::unfold(None, move |last_round: Option<u64>| async move {
+            if last_round.is_some() {
+                futures_timer::Delay::new(period).await;
+            }
+            loop {
+                match self.latest().await {
+                    Ok(beacon) if last_round.map_or(true, |round| beacon.round() > round) => {
+                        let round = beacon.round();
+                        return Some((Ok(beacon), Some(round)));
+                    }
+                    // the relay has not caught up with the next round yet
+                    Ok(_beacon) => {
+                        futures_timer::Delay::new(POLL_RETRY_DELAY).await;
+                        self.record_retry("public/latest");
+                    }
+                    Err(err) => {
+                        futures_timer::Delay::new(self.retry_delay(&err)).await;
+                        self.record_retry("public/latest");
+                        return Some((Err(err), last_round));
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Current time, in epoch seconds.
+fn unix_time() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(anyhow::Error::from)?
+        .as_secs())
+}
+
+/// Run `operation`, unless it does not complete within `budget`. It is then dropped, cancelling pending requests.
+async fn within<T>(budget: Duration, operation: impl Future<Output = Result<T>>) -> Result<T> {
+    futures::pin_mut!(operation);
+    match futures::future::select(operation, futures_timer::Delay::new(budget)).await {
+        Either::Left((result, _delay)) => result,
+        Either::Right(((), _operation)) => Err(RelayError::Timeout { budget }.into()),
+    }
+}
+
+/// Builder for [`HttpClient`]
+/// By default, the client verifies beacons, and caches chain info. Only `base_url` is required
+/// Transport options are the ones of reqwest. With hyper alone, only the maximum response size applies.
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+pub struct HttpClientBuilder {
+    base_url: Option<String>,
+    is_beacon_verification: bool,
+    is_cache: bool,
+    is_strict: bool,
+    chain_hash: Option<Vec<u8>>,
+    public_key: Option<Vec<u8>>,
+    beacon_id: Option<String>,
+    #[cfg(feature = "reqwest")]
+    tls: Option<TlsOptions>,
+    #[cfg(feature = "reqwest")]
+    connect_timeout: Option<Duration>,
+    #[cfg(feature = "reqwest")]
+    timeout: Option<Duration>,
+    #[cfg(feature = "reqwest")]
+    user_agent: Option<String>,
+    #[cfg(feature = "reqwest")]
+    proxy: Option<String>,
+    #[cfg(feature = "reqwest")]
+    no_proxy: Vec<String>,
+    #[cfg(feature = "reqwest")]
+    headers: Vec<(String, String)>,
+    max_response_size: Option<usize>,
+    #[cfg(feature = "reqwest")]
+    redirect_policy: Option<RedirectPolicy>,
+    #[cfg(feature = "http3")]
+    is_http3: bool,
+    #[cfg(feature = "compression")]
+    is_compression: bool,
+    #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+    resolves: Vec<(String, Vec<std::net::SocketAddr>)>,
+    #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+    dns_resolver: Option<crate::DnsResolver>,
+    cache_ttl: Option<Duration>,
+    max_staleness: Option<u64>,
+    max_clock_skew: Option<Duration>,
+    max_retry_after: Option<Duration>,
+    beacon_cache: Option<usize>,
+    #[cfg(not(target_arch = "wasm32"))]
+    disk_cache: Option<std::path::PathBuf>,
+    metrics: Option<Arc<dyn MetricsSink>>,
+    #[cfg(feature = "reqwest")]
+    http_client: Option<reqwest::Client>,
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+impl HttpClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            base_url: None,
+            is_beacon_verification: true,
+            is_cache: true,
+            is_strict: false,
+            chain_hash: None,
+            public_key: None,
+            beacon_id: None,
+            #[cfg(feature = "reqwest")]
+            tls: None,
+            #[cfg(feature = "reqwest")]
+            connect_timeout: None,
+            #[cfg(feature = "reqwest")]
+            timeout: None,
+            #[cfg(feature = "reqwest")]
+            user_agent: None,
+            #[cfg(feature = "reqwest")]
+            proxy: None,
+            #[cfg(feature = "reqwest")]
+            no_proxy: vec![],
+            #[cfg(feature = "reqwest")]
+            headers: vec![],
+            max_response_size: None,
+            #[cfg(feature = "reqwest")]
+            redirect_policy: None,
+            #[cfg(feature = "http3")]
+            is_http3: false,
+            #[cfg(feature = "compression")]
+            is_compression: true,
+            #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+            resolves: vec![],
+            #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+            dns_resolver: None,
+            cache_ttl: None,
+            max_staleness: None,
+            max_clock_skew: None,
+            max_retry_after: None,
+            beacon_cache: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            disk_cache: None,
+            metrics: None,
+            #[cfg(feature = "reqwest")]
+            http_client: None,
+        }
+    }
+
+    /// URL of the relay serving the chain.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Verify beacons against the chain info.
+    pub fn verify(mut self, is_beacon_verification: bool) -> Self {
+        self.is_beacon_verification = is_beacon_verification;
+        self
+    }
+
+    /// Cache chain info, and allow intermediate caches to serve beacons.
+    pub fn cache(mut self, is_cache: bool) -> Self {
+        self.is_cache = is_cache;
+        self
+    }
+
+    /// Reject relay responses with unknown or malformed fields, instead of ignoring them.
+    pub fn strict(mut self, is_strict: bool) -> Self {
+        self.is_strict = is_strict;
+        self
+    }
+
+    /// Refresh cached chain info once `cache_ttl` has elapsed, with a conditional request.
+    pub fn cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = Some(cache_ttl);
+        self
+    }
+
+    /// Fail on a latest beacon more than `periods` periods older than the local clock expects.
+    pub fn max_staleness(mut self, periods: u64) -> Self {
+        self.max_staleness = Some(periods);
+        self
+    }
+
+    /// Reject beacons from rounds after the current one, tolerating a local clock late by up to `max_clock_skew`.
+    pub fn max_clock_skew(mut self, max_clock_skew: Duration) -> Self {
+        self.max_clock_skew = Some(max_clock_skew);
+        self
+    }
+
+    /// Longest delay to wait before retrying, when a rate limiting relay asks for it. Defaults to 60 seconds.
+    pub fn max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.max_retry_after = Some(max_retry_after);
+        self
+    }
+
+    /// Keep up to `capacity` retrieved beacons in memory, evicting the least recently used one first.
+    pub fn beacon_cache(mut self, capacity: usize) -> Self {
+        self.beacon_cache = Some(capacity);
+        self
+    }
+
+    /// Persist chain info and beacons under the `path` directory, for the next processes to reuse.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn disk_cache(mut self, path: impl AsRef<std::path::Path>) -> Self {
+        self.disk_cache = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Only accept a chain with this hash, recomputed from the retrieved chain info.
+    pub fn chain_hash(mut self, chain_hash: &[u8]) -> Self {
+        self.chain_hash = Some(chain_hash.to_vec());
+        self
+    }
+
+    /// Only accept a chain with this public key.
+    pub fn public_key(mut self, public_key: &[u8]) -> Self {
+        self.public_key = Some(public_key.to_vec());
+        self
+    }
+
+    /// Only accept a chain with this beacon ID, such as `default` or `quicknet`.
+    pub fn beacon_id(mut self, beacon_id: &str) -> Self {
+        self.beacon_id = Some(beacon_id.to_string());
+        self
+    }
+
+    /// TLS policy for relay connections.
+    #[cfg(feature = "reqwest")]
+    pub fn tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Maximum duration to establish a connection with the relay.
+    #[cfg(feature = "reqwest")]
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Maximum duration of a request.
+    #[cfg(feature = "reqwest")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// `User-Agent` header sent with every request.
+    #[cfg(feature = "reqwest")]
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Proxy requests are routed through, including SOCKS5 ones such as `socks5h://127.0.0.1:9050` for Tor.
+    #[cfg(feature = "reqwest")]
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// Hosts reached directly, bypassing the proxy.
+    #[cfg(feature = "reqwest")]
+    pub fn no_proxy(mut self, no_proxy: &[&str]) -> Self {
+        self.no_proxy = no_proxy.iter().map(|host| host.to_string()).collect();
+        self
+    }
+
+    /// Header sent with every request, such as an API key. Can be called multiple times.
+    #[cfg(feature = "reqwest")]
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Bearer token sent in the `Authorization` header of every request.
+    #[cfg(feature = "reqwest")]
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header("authorization", &format!("Bearer {token}"))
+    }
+
+    /// Maximum size of a relay response, in bytes. Defaults to 64 KiB.
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = Some(max_response_size);
+        self
+    }
+
+    /// Which redirects sent by the relay are followed. Defaults to up to 10, including to other origins.
+    #[cfg(feature = "reqwest")]
+    pub fn redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.redirect_policy = Some(redirect_policy);
+        self
+    }
+
+    /// Query the relay over HTTP/3 (QUIC). Requires building with `RUSTFLAGS="--cfg reqwest_unstable"`.
+    #[cfg(feature = "http3")]
+    pub fn http3(mut self) -> Self {
+        self.is_http3 = true;
+        self
+    }
+
+    /// Report requests, retries, and chain info cache lookups to `metrics`.
+    pub fn metrics(mut self, metrics: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Ask the relay for uncompressed responses, such as when debugging.
+    #[cfg(feature = "compression")]
+    pub fn no_compression(mut self) -> Self {
+        self.is_compression = false;
+        self
+    }
+
+    /// Resolve `domain` to `addrs`, instead of querying DNS. Can be called multiple times.
+    #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+    pub fn resolve(mut self, domain: &str, addrs: &[std::net::SocketAddr]) -> Self {
+        self.resolves.push((domain.to_string(), addrs.to_vec()));
+        self
+    }
+
+    /// Resolve relay host names with `dns_resolver`, such as one over HTTPS.
+    #[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+    pub fn dns_resolver(mut self, dns_resolver: crate::DnsResolver) -> Self {
+        self.dns_resolver = Some(dns_resolver);
+        self
+    }
+
+    /// Reuse an existing reqwest client. Transport options set on this builder, except the maximum response size, are then ignored.
+    #[cfg(feature = "reqwest")]
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    pub fn build(self) -> Result<HttpClient> {
+        let base_url = self
+            .base_url
+            .ok_or_else(|| Error::Other(anyhow!("base_url is required to build a client")))?;
+        let mut verification = ChainVerification::new(self.chain_hash, self.public_key);
+        if let Some(beacon_id) = self.beacon_id {
+            verification = verification.with_beacon_id(&beacon_id);
+        }
+        let options = ChainOptions::new(
+            self.is_beacon_verification,
+            self.is_cache,
+            Some(verification),
+        );
+        #[cfg(feature = "reqwest")]
+        let api = {
+            let mut http_options = HttpOptions::new(
+                self.tls,
+                self.connect_timeout,
+                self.timeout,
+                self.user_agent,
+            );
+            if let Some(proxy) = &self.proxy {
+                let no_proxy: Vec<&str> = self.no_proxy.iter().map(String::as_str).collect();
+                http_options = http_options.with_proxy(proxy).with_no_proxy(&no_proxy);
+            }
+            for (name, value) in &self.headers {
+                http_options = http_options.with_header(name, value);
+            }
+            if let Some(max_response_size) = self.max_response_size {
+                http_options = http_options.with_max_response_size(max_response_size);
+            }
+            if let Some(redirect_policy) = self.redirect_policy {
+                http_options = http_options.with_redirect_policy(redirect_policy);
+            }
+            #[cfg(feature = "http3")]
+            if self.is_http3 {
+                http_options = http_options.with_http3();
+            }
+            #[cfg(feature = "compression")]
+            if !self.is_compression {
+                http_options = http_options.without_compression();
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            for (domain, addrs) in &self.resolves {
+                http_options = http_options.with_resolve(domain, addrs);
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(dns_resolver) = self.dns_resolver {
+                http_options = http_options.with_dns_resolver(dns_resolver);
+            }
+            match self.http_client {
+                Some(http_client) => ApiClient::with_transport(
+                    HttpTransport::new(http_client, &base_url)?
+                        .with_max_response_size(http_options.max_response_size()),
+                ),
+                None => ApiClient::with_http_options(&base_url, http_options)?,
+            }
+        };
+        #[cfg(all(feature = "hyper", not(feature = "reqwest")))]
+        let api =
+            ApiClient::with_transport(HyperTransport::new(&base_url)?.with_max_response_size(
+                self.max_response_size.unwrap_or(DEFAULT_MAX_RESPONSE_SIZE),
+            ));
+        let api = match self.metrics {
+            Some(metrics) => api.with_metrics(metrics),
+            None => api,
+        }
+        .with_strict(self.is_strict);
+        let mut client = HttpClient::with_api(api, Some(options));
+        if let Some(cache_ttl) = self.cache_ttl {
+            client = client.with_cache_ttl(cache_ttl);
+        }
+        if let Some(max_staleness) = self.max_staleness {
+            client = client.with_max_staleness(max_staleness);
+        }
+        if let Some(max_clock_skew) = self.max_clock_skew {
+            client = client.with_max_clock_skew(max_clock_skew);
+        }
+        if let Some(max_retry_after) = self.max_retry_after {
+            client = client.with_max_retry_after(max_retry_after);
+        }
+        if let Some(capacity) = self.beacon_cache {
+            client = client.with_beacon_cache(capacity);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = self.disk_cache {
+            client = client.with_disk_cache(path);
+        }
+        Ok(client)
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+impl Default for HttpClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+impl TryFrom<&str> for HttpClient {
+    type Error = Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Self::from_str(value)
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+impl FromStr for HttpClient {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::new(s, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{
+        tests::chained_beacon, tests::chained_beacon_1, tests::invalid_beacon,
+        tests::unchained_beacon,
+    };
+    use crate::chain::{
+        tests::chained_chain_info, tests::rehash, tests::unchained_chain_info, ChainOptions,
+        ChainVerification,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn client_no_cache_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect_at_least(2)
+            .create_async()
+            .await;
+        let latest_mock = server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect_at_least(2)
+            .create_async()
+            .await;
+
+        // test client without cache
+        let no_cache_client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(true, false, None)),
+        )
+        .unwrap();
+
+        // info endpoint
+        let info = match no_cache_client.chain_info().await {
+            Ok(info) => info,
+            Err(_err) => panic!("fetch should have succeded"),
+        };
+        assert_eq!(info, chained_chain_info());
+        // do it again to see if it's cached or not
+        let _ = no_cache_client.chain_info().await;
+        info_mock.assert_async().await;
+
+        // latest endpoint
+        let latest = match no_cache_client.latest().await {
+            Ok(beacon) => beacon,
+            Err(_err) => panic!("fetch should have succeded"),
+        };
+        assert_eq!(latest.beacon(), chained_beacon());
+        assert_eq!(latest.time(), 1625431020);
+        // do it again to see if it's cached or not
+        let _ = no_cache_client.latest().await;
+        latest_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_cache_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let latest_mock = server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        // test client with cache
+        let cache_client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(true, true, None)),
+        )
+        .unwrap();
+
+        // info endpoint
+        let info = match cache_client.chain_info().await {
+            Ok(info) => info,
+            Err(_err) => panic!("fetch should have succeded"),
+        };
+        assert_eq!(info, chained_chain_info());
+        // do it again to see if it's cached or not
+        let _ = cache_client.chain_info().await;
+        info_mock.assert_async().await;
+
+        // latest endpoint
+        let latest = match cache_client.latest().await {
+            Ok(beacon) => beacon,
+            Err(_err) => panic!("fetch should have succeded"),
+        };
+        assert_eq!(latest.beacon(), chained_beacon());
+        assert_eq!(latest.time(), 1625431020);
+        // do it again to see if it's cached or not
+        let _ = cache_client.latest().await;
+        latest_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_with_http_client_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .match_header("x-drand-test", "custom-client")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-drand-test", "custom-client".parse().unwrap());
+        let http_client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+
+        let client =
+            HttpClient::with_http_client(http_client, server.url().as_str(), None).unwrap();
+        let info = match client.chain_info().await {
+            Ok(info) => info,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(info, chained_chain_info());
+        info_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_builder_works() {
+        let mut server = mockito::Server::new_async().await;
+        let _info_mock = server
+            .mock("GET", "/info")
+            .match_header("user-agent", "drand-test")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        let client = HttpClient::builder()
+            .base_url(server.url().as_str())
+            .chain_hash(&chained_chain_info().hash())
+            .timeout(Duration::from_secs(10))
+            .user_agent("drand-test")
+            .build()
+            .unwrap();
+        let info = match client.chain_info().await {
+            Ok(info) => info,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(info, chained_chain_info());
+        assert!(client.options().is_beacon_verification());
+
+        // test with not the correct hash
+        let client = HttpClient::builder()
+            .base_url(server.url().as_str())
+            .chain_hash(&unchained_chain_info().hash())
+            .user_agent("drand-test")
+            .build()
+            .unwrap();
+        match client.chain_info().await {
+            Ok(_info) => panic!("Chain info should not validate"),
+            Err(_err) => (),
+        };
+
+        // test with not the correct beacon ID
+        let client = HttpClient::builder()
+            .base_url(server.url().as_str())
+            .beacon_id("quicknet")
+            .build()
+            .unwrap();
+        assert!(client.chain_info().await.is_err());
+
+        assert!(HttpClient::builder().build().is_err());
+    }
+
+    #[tokio::test]
+    async fn client_beacon_verification_works() {
+        // unchained beacon
+        let mut valid_server = mockito::Server::new_async().await;
+        let _info_mock = valid_server
+            .mock("GET", "/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let _latest_mock = valid_server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        // test client without cache
+        let client = HttpClient::new(
+            valid_server.url().as_str(),
+            Some(ChainOptions::new(true, false, None)),
+        )
+        .unwrap();
+
+        // latest endpoint
+        let latest = match client.latest().await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(latest.beacon(), unchained_beacon());
+
+        let mut invalid_server = mockito::Server::new_async().await;
+        let _info_mock = invalid_server
+            .mock("GET", "/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let _latest_mock = invalid_server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&invalid_beacon()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        // test client without cache
+        let client = HttpClient::new(
+            invalid_server.url().as_str(),
+            Some(ChainOptions::new(true, false, None)),
+        )
+        .unwrap();
+
+        // latest endpoint
+        match client.latest().await {
+            Ok(_beacon) => panic!("Beacon should not validate"),
+            Err(_err) => (),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_chain_verification_works() {
+        // unchained beacon
+        let mut valid_server = mockito::Server::new_async().await;
+        let _info_mock = valid_server
+            .mock("GET", "/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let _latest_mock = valid_server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        // test client without cache
+        let unchained_info = unchained_chain_info();
+        let unchained_client = HttpClient::new(
+            valid_server.url().as_str(),
+            Some(ChainOptions::new(
+                true,
+                false,
+                Some(ChainVerification::new(
+                    Some(unchained_info.hash()),
+                    Some(unchained_info.public_key()),
+                )),
+            )),
+        )
+        .unwrap();
+
+        // latest endpoint
+        let latest = match unchained_client.latest().await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(latest.beacon(), unchained_beacon());
+        assert_eq!(latest.time(), 1654677096);
+
+        // test with not the correct hash
+        let chained_info = chained_chain_info();
+        let invalid_client = HttpClient::new(
+            valid_server.url().as_str(),
+            Some(ChainOptions::new(
+                true,
+                false,
+                Some(ChainVerification::new(Some(chained_info.hash()), None)),
+            )),
+        )
+        .unwrap();
+
+        match invalid_client.latest().await {
+            Ok(_beacon) => panic!("Beacon should not validate"),
+            Err(_err) => (),
+        };
+        // test with not the correct public_key
+        let chained_info = chained_chain_info();
+        let invalid_client = HttpClient::new(
+            valid_server.url().as_str(),
+            Some(ChainOptions::new(
+                true,
+                false,
+                Some(ChainVerification::new(
+                    None,
+                    Some(chained_info.public_key()),
+                )),
+            )),
+        )
+        .unwrap();
+
+        match invalid_client.latest().await {
+            Ok(_beacon) => panic!("Beacon should not validate"),
+            Err(_err) => (),
+        };
+    }
+
+    #[tokio::test]
+    async fn client_for_chain_works() {
+        let mut server = mockito::Server::new_async().await;
+        let chain_hash = unchained_chain_info().hash();
+        let path = format!("/{}", hex::encode(&chain_hash));
+        server
+            .mock("GET", format!("{path}/info").as_str())
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let latest_mock = server
+            .mock("GET", format!("{path}/public/latest").as_str())
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        let client = HttpClient::for_chain(&format!("{}/", server.url()), &chain_hash).unwrap();
+        assert_eq!(client.base_url(), format!("{}{path}/", server.url()));
+        assert_eq!(client.latest().await.unwrap().beacon(), unchained_beacon());
+        latest_mock.assert_async().await;
+
+        // chain info served under another chain hash is rejected
+        server
+            .mock(
+                "GET",
+                format!("/{}/info", hex::encode(chained_chain_info().hash())).as_str(),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let client =
+            HttpClient::for_chain(server.url().as_str(), &chained_chain_info().hash()).unwrap();
+        assert!(client.chain_info().await.is_err());
+
+        // chain info keeping the pinned hash along another public key is rejected
+        let mut forged = serde_json::to_value(chained_chain_info()).unwrap();
+        forged["public_key"] = hex::encode(unchained_chain_info().public_key()).into();
+        let mut forging_server = mockito::Server::new_async().await;
+        forging_server
+            .mock(
+                "GET",
+                format!("/{}/info", hex::encode(chained_chain_info().hash())).as_str(),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(forged.to_string())
+            .create_async()
+            .await;
+        let client =
+            HttpClient::for_chain(&forging_server.url(), &chained_chain_info().hash()).unwrap();
+        assert!(matches!(
+            client.chain_info().await,
+            Err(Error::InvalidChainInfo)
+        ));
+        let client = HttpClient::builder()
+            .base_url(&format!(
+                "{}/{}",
+                forging_server.url(),
+                hex::encode(chained_chain_info().hash())
+            ))
+            .chain_hash(&chained_chain_info().hash())
+            .build()
+            .unwrap();
+        assert!(client.chain_info().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn client_chains_works() {
+        let mut server = mockito::Server::new_async().await;
+        let chain_hash = unchained_chain_info().hash();
+        server
+            .mock("GET", "/chains")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::to_string(&[
+                    hex::encode(chained_chain_info().hash()),
+                    hex::encode(&chain_hash),
+                ])
+                .unwrap(),
+            )
+            .create_async()
+            .await;
+        let info_mock = server
+            .mock(
+                "GET",
+                format!("/{}/info", hex::encode(&chain_hash)).as_str(),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let chains = client.chains().await.unwrap();
+        assert_eq!(
+            chains,
+            vec![chained_chain_info().hash(), chain_hash.clone()]
+        );
+
+        let chain_client = client.chain_client(&chains[1]).unwrap();
+        assert_eq!(
+            chain_client.chain_info().await.unwrap(),
+            unchained_chain_info()
+        );
+        info_mock.assert_async().await;
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl MetricsSink for RecordingSink {
+        fn request(&self, path: &str, _latency: Duration, is_success: bool) {
+            let event = format!("request {path} {is_success}");
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn cache_hit(&self) {
+            self.events.lock().unwrap().push("hit".to_string());
+        }
+
+        fn cache_miss(&self) {
+            self.events.lock().unwrap().push("miss".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn client_metrics_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1234")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let metrics = Arc::new(RecordingSink::default());
+        let client = HttpClient::builder()
+            .base_url(server.url().as_str())
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+        client.latest().await.unwrap();
+        assert!(client.get(1234).await.is_err());
+
+        assert_eq!(
+            *metrics.events.lock().unwrap(),
+            vec![
+                "request public/latest true",
+                "miss",
+                "request info true",
+                "request public/1234 false",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn client_get_many_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        for round in [1, 1000000] {
+            let beacon = match round {
+                1 => chained_beacon_1(),
+                _ => chained_beacon(),
+            };
+            server
+                .mock("GET", format!("/public/{round}").as_str())
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(serde_json::to_string(&beacon).unwrap())
+                .create_async()
+                .await;
+        }
+        server
+            .mock("GET", "/public/2")
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+
+        // beacons are returned in the requested order, failed rounds included
+        let beacons: Vec<Result<RandomnessBeacon>> = client
+            .get_many([1000000, 2, 1], 2)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(beacons.len(), 3);
+        assert_eq!(beacons[0].as_ref().unwrap().beacon(), chained_beacon());
+        assert!(beacons[1].is_err());
+        assert_eq!(beacons[2].as_ref().unwrap().beacon(), chained_beacon_1());
+        info_mock.assert_async().await;
+
+        // cached rounds are not requested again
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let round_mock = server
+            .mock("GET", "/public/1000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        let client = HttpClient::new(server.url().as_str(), None)
+            .unwrap()
+            .with_beacon_cache(8);
+        for _ in 0..2 {
+            let beacons: Vec<Result<RandomnessBeacon>> =
+                client.get_many([1000000], 1).await.unwrap().collect().await;
+            assert_eq!(beacons[0].as_ref().unwrap().beacon(), chained_beacon());
+        }
+        assert_eq!(
+            client.get(1000000).await.unwrap().beacon(),
+            chained_beacon()
+        );
+        round_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_stream_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let stream_mock = server
+            .mock("GET", "/public/stream")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(format!(
+                "data: {}\n\ndata: {}\n\n",
+                serde_json::to_string(&chained_beacon()).unwrap(),
+                serde_json::to_string(&invalid_beacon()).unwrap()
+            ))
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+
+        // beacons pushed by the relay go through verification
+        let beacons: Vec<Result<RandomnessBeacon>> = client.stream().await.unwrap().collect().await;
+        assert_eq!(beacons.len(), 2);
+        assert_eq!(beacons[0].as_ref().unwrap().beacon(), chained_beacon());
+        assert!(beacons[1].is_err());
+        stream_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_stream_falls_back_to_polling_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/stream")
+            .with_status(404)
+            .create_async()
+            .await;
+        let latest_mock = server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+
+        let stream = client.stream().await.unwrap();
+        let beacons: Vec<Result<RandomnessBeacon>> = stream.take(1).collect().await;
+        assert_eq!(beacons[0].as_ref().unwrap().beacon(), chained_beacon());
+        latest_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_cache_ttl_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .match_header("if-none-match", mockito::Matcher::Missing)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_header("etag", "\"v1\"")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        let not_modified_mock = server
+            .mock("GET", "/info")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(2)
+            .create_async()
+            .await;
+
+        // chain info expires immediately, and is refreshed on every call
+        let client = HttpClient::new(server.url().as_str(), None)
+            .unwrap()
+            .with_cache_ttl(Duration::ZERO);
+        for _ in 0..3 {
+            assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
+        }
+
+        info_mock.assert_async().await;
+        not_modified_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_with_chain_info_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
+            .expect(0)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        // the relay is never asked for chain info
+        let client =
+            HttpClient::with_chain_info(server.url().as_str(), chained_chain_info(), None).unwrap();
+        assert_eq!(client.latest().await.unwrap().beacon(), chained_beacon());
+        assert_eq!(
+            client.refresh_chain_info().await.unwrap(),
+            chained_chain_info()
+        );
+        info_mock.assert_async().await;
+
+        // provided chain info still goes through chain verification
+        assert!(HttpClient::with_chain_info(
+            server.url().as_str(),
+            chained_chain_info(),
+            Some(ChainOptions::new(
+                true,
+                true,
+                Some(ChainVerification::new(
+                    Some(unchained_chain_info().hash()),
+                    None
+                ))
+            ))
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn client_max_staleness_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        // round 1000000 was emitted in 2021
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        assert!(client.latest().await.is_ok());
+        let client = client.with_max_staleness(10);
+        let current_round = client.current_round().unwrap();
+        let err = client.latest().await.unwrap_err();
+        match err.relay() {
+            Some(RelayError::Stale { round, expected }) => {
+                assert_eq!(*round, 1000000);
+                // the expected round is the one emitted now, as for the offline round computation
+                assert!((current_round..=client.current_round().unwrap()).contains(expected));
+            }
+            _ => panic!("Error should be stale, got {err}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_max_clock_skew_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+        let mut future_beacon = serde_json::to_value(chained_beacon()).unwrap();
+        future_beacon["round"] = serde_json::json!(1_000_000_000_000u64);
+        server
+            .mock("GET", "/public/1000000000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(future_beacon.to_string())
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None)
+            .unwrap()
+            .with_max_clock_skew(Duration::from_secs(60));
+        assert!(client.get(1000000).await.is_ok());
+        let err = client.get(1_000_000_000_000).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FutureRound {
+                round: 1_000_000_000_000,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_next_works() {
+        // chain with a 1 second period, which started 10 seconds ago
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        info["period"] = serde_json::json!(1);
+        info["genesis_time"] = serde_json::json!(unix_time().unwrap() - 10);
+        rehash(&mut info);
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(info.to_string())
+            .create_async()
+            .await;
+        let round_mock = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/public/1[0-9]$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(false, true, None)),
+        )
+        .unwrap();
+        let start = Instant::now();
+        assert_eq!(client.next().await.unwrap().beacon(), chained_beacon());
+        assert!(start.elapsed() <= Duration::from_secs(2));
+        round_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_latest_verified_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&invalid_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        // beacons are verified, even if the client does not verify them
+        let client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(false, true, None)),
+        )
+        .unwrap();
+        assert!(client.latest().await.is_ok());
+        assert!(matches!(
+            client.latest_verified().await.unwrap_err(),
+            Error::InvalidBeacon { round: 1234, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_watch_works() {
+        // chain with a 1 second period, which started 10 seconds ago
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        info["period"] = serde_json::json!(1);
+        info["genesis_time"] = serde_json::json!(unix_time().unwrap() - 10);
+        rehash(&mut info);
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(info.to_string())
+            .create_async()
+            .await;
+        let round_mock = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/public/1[0-9]$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(3)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(false, true, None)),
+        )
+        .unwrap();
+        let start = Instant::now();
+        let beacons: Vec<Result<RandomnessBeacon>> =
+            client.watch().await.unwrap().take(3).collect().await;
+        assert!(beacons.iter().all(|beacon| beacon.is_ok()));
+        // one beacon per period
+        assert!(start.elapsed() <= Duration::from_secs(4));
+        round_mock.assert_async().await;
+
+        // a round which does not verify ends the stream, instead of being requested again
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(info.to_string())
+            .create_async()
+            .await;
+        let invalid_mock = server
+            .mock("GET", mockito::Matcher::Regex(r"^/public/\d+$".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&invalid_beacon()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+        let client = HttpClient::new(
+            server.url().as_str(),
+            Some(ChainOptions::new(true, true, None)),
+        )
+        .unwrap();
+        let beacons: Vec<Result<RandomnessBeacon>> = tokio::time::timeout(
+            Duration::from_secs(3),
+            client.watch().await.unwrap().collect(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(beacons.len(), 1);
+        assert!(!beacons[0].as_ref().unwrap_err().is_retryable());
+        invalid_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_stream_range_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let round_mock = server
+            .mock("GET", mockito::Matcher::Regex(r"^/public/\d+$".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(20)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let beacons = client.stream_range(1..=20).await.unwrap();
+        let count = beacons
+            .fold(0, |count, beacon| async move {
+                assert_eq!(beacon.unwrap().beacon(), chained_beacon());
+                count + 1
+            })
+            .await;
+        assert_eq!(count, 20);
+        round_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_get_range_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon_1()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/2")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/3")
+            .with_status(404)
+            .create_async()
+            .await;
 
-        let info = self.chain_info().await?;
-        let unix_time = info.genesis_time() + beacon.round() * info.period();
-        let beacon = RandomnessBeacon::new(beacon, unix_time);
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let beacons = client.get_range(1, 2).await.unwrap();
+        assert_eq!(beacons.len(), 2);
+        assert_eq!(beacons[0].beacon(), chained_beacon_1());
+        assert_eq!(beacons[1].beacon(), chained_beacon());
 
-        self.verify_beacon(beacon).await
+        // failed rounds are reported
+        let err = client.get_range(1, 3).await.unwrap_err();
+        assert!(err.to_string().contains("round 3: not found"));
     }
 
-    pub async fn get_by_unix_time(&self, round_unix_time: u64) -> Result<RandomnessBeacon> {
-        let info = self.chain_info().await?;
-        let round = (round_unix_time - info.genesis_time()) / info.period();
-
-        self.get(round).await
-    }
-}
+    #[tokio::test]
+    async fn client_offline_round_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
 
-impl TryFrom<&str> for HttpClient {
-    type Error = anyhow::Error;
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        // chain info has to be known
+        assert!(client.current_round().is_err());
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        Self::from_str(value)
+        client.chain_info().await.unwrap();
+        let time = UNIX_EPOCH + Duration::from_secs(1625431049);
+        assert_eq!(client.round_at(time).unwrap(), 1000000);
+        assert_eq!(
+            client.time_of_round(1000000).unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1625431020)
+        );
+        assert!(client.current_round().unwrap() > 1000000);
+        assert!(client.round_at(UNIX_EPOCH).is_err());
     }
-}
 
-impl FromStr for HttpClient {
-    type Err = anyhow::Error;
+    #[tokio::test]
+    async fn client_retry_after_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/public/1234")
+            .with_status(429)
+            .with_header("retry-after", "120")
+            .create_async()
+            .await;
 
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        Self::new(s, None)
+        let client = HttpClient::new(server.url().as_str(), None)
+            .unwrap()
+            .with_max_retry_after(Duration::from_secs(30));
+        let err = client.get(1234).await.unwrap_err();
+        assert_eq!(
+            err.relay().unwrap().retry_after(),
+            Some(Duration::from_secs(120))
+        );
+        // the delay requested by the relay is capped
+        assert_eq!(client.retry_delay(&err), Duration::from_secs(30));
+        assert_eq!(
+            client.retry_delay(&Error::Other(anyhow!("other"))),
+            POLL_RETRY_DELAY
+        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::beacon::{tests::chained_beacon, tests::invalid_beacon, tests::unchained_beacon};
-    use crate::chain::{
-        tests::chained_chain_info, tests::unchained_chain_info, ChainOptions, ChainVerification,
-    };
-
-    use super::*;
 
     #[tokio::test]
-    async fn client_no_cache_works() {
+    async fn client_timeout_works() {
         let mut server = mockito::Server::new_async().await;
-        let info_mock = server
+        server
             .mock("GET", "/info")
-            .match_query(mockito::Matcher::Any)
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
-            .expect_at_least(2)
             .create_async()
             .await;
-        let latest_mock = server
+        server
             .mock("GET", "/public/latest")
             .match_query(mockito::Matcher::Any)
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(serde_json::to_string(&chained_beacon()).unwrap())
-            .expect_at_least(2)
             .create_async()
             .await;
 
-        // test client without cache
-        let no_cache_client = HttpClient::new(
-            server.url().as_str(),
-            Some(ChainOptions::new(true, false, None)),
-        )
-        .unwrap();
-
-        // info endpoint
-        let info = match no_cache_client.chain_info().await {
-            Ok(info) => info,
-            Err(_err) => panic!("fetch should have succeded"),
-        };
-        assert_eq!(info, chained_chain_info());
-        // do it again to see if it's cached or not
-        let _ = no_cache_client.chain_info().await;
-        info_mock.assert_async().await;
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let beacon = client
+            .latest_with_timeout(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert_eq!(beacon.beacon(), chained_beacon());
 
-        // latest endpoint
-        let latest = match no_cache_client.latest().await {
-            Ok(beacon) => beacon,
-            Err(_err) => panic!("fetch should have succeded"),
-        };
-        assert_eq!(latest.beacon(), chained_beacon());
-        assert_eq!(latest.time(), 1625431050);
-        // do it again to see if it's cached or not
-        let _ = no_cache_client.latest().await;
-        latest_mock.assert_async().await;
+        let err = within(
+            Duration::from_millis(10),
+            futures::future::pending::<Result<()>>(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err.relay(), Some(RelayError::Timeout { .. })));
     }
 
     #[tokio::test]
-    async fn client_cache_works() {
+    async fn client_beacon_cache_works() {
         let mut server = mockito::Server::new_async().await;
-        let info_mock = server
+        server
             .mock("GET", "/info")
-            .match_query(mockito::Matcher::Any)
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
-            .expect_at_least(1)
             .create_async()
             .await;
-        let latest_mock = server
-            .mock("GET", "/public/latest")
-            .match_query(mockito::Matcher::Any)
+        let round_mock = server
+            .mock("GET", "/public/1000000")
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(serde_json::to_string(&chained_beacon()).unwrap())
-            .expect_at_least(1)
+            .expect(1)
             .create_async()
             .await;
 
-        // test client with cache
-        let cache_client = HttpClient::new(
-            server.url().as_str(),
-            Some(ChainOptions::new(true, true, None)),
-        )
-        .unwrap();
-
-        // info endpoint
-        let info = match cache_client.chain_info().await {
-            Ok(info) => info,
-            Err(_err) => panic!("fetch should have succeded"),
-        };
-        assert_eq!(info, chained_chain_info());
-        // do it again to see if it's cached or not
-        let _ = cache_client.chain_info().await;
-        info_mock.assert_async().await;
-
-        // latest endpoint
-        let latest = match cache_client.latest().await {
-            Ok(beacon) => beacon,
-            Err(_err) => panic!("fetch should have succeded"),
-        };
-        assert_eq!(latest.beacon(), chained_beacon());
-        assert_eq!(latest.time(), 1625431050);
-        // do it again to see if it's cached or not
-        let _ = cache_client.latest().await;
-        latest_mock.assert_async().await;
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        assert_eq!(client.beacon_cache_stats(), None);
+        let client = client.with_beacon_cache(10);
+        for _ in 0..3 {
+            assert_eq!(
+                client.get(1000000).await.unwrap().beacon(),
+                chained_beacon()
+            );
+        }
+        round_mock.assert_async().await;
+        assert_eq!(
+            client.beacon_cache_stats(),
+            Some(BeaconCacheStats { hits: 2, misses: 1 })
+        );
     }
 
     #[tokio::test]
-    async fn client_beacon_verification_works() {
-        // unchained beacon
-        let mut valid_server = mockito::Server::new_async().await;
-        let _info_mock = valid_server
+    async fn client_disk_cache_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
             .mock("GET", "/info")
-            .match_query(mockito::Matcher::Any)
             .with_status(200)
             .with_header("content-type", "application/json")
-            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
-            .expect_at_least(1)
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
             .create_async()
             .await;
-        let _latest_mock = valid_server
-            .mock("GET", "/public/latest")
-            .match_query(mockito::Matcher::Any)
+        let round_mock = server
+            .mock("GET", "/public/1000000")
             .with_status(200)
             .with_header("content-type", "application/json")
-            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
-            .expect_at_least(1)
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(1)
             .create_async()
             .await;
 
-        // test client without cache
-        let client = HttpClient::new(
-            valid_server.url().as_str(),
-            Some(ChainOptions::new(true, false, None)),
-        )
-        .unwrap();
-
-        // latest endpoint
-        let latest = match client.latest().await {
-            Ok(beacon) => beacon,
-            Err(err) => panic!("fetch should have succeded {}", err),
-        };
-        assert_eq!(latest.beacon(), unchained_beacon());
+        let path = std::env::temp_dir().join(format!("drand-disk-cache-{}", std::process::id()));
+        // each client stands for a new process, starting with an empty memory cache
+        for _ in 0..2 {
+            let client = HttpClient::new(server.url().as_str(), None)
+                .unwrap()
+                .with_disk_cache(&path);
+            assert_eq!(
+                client.get(1000000).await.unwrap().beacon(),
+                chained_beacon()
+            );
+            assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
+        }
+        info_mock.assert_async().await;
+        round_mock.assert_async().await;
+        std::fs::remove_dir_all(&path).unwrap();
+    }
 
-        let mut invalid_server = mockito::Server::new_async().await;
-        let _info_mock = invalid_server
+    #[tokio::test]
+    async fn client_concurrent_chain_info_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
             .mock("GET", "/info")
-            .match_query(mockito::Matcher::Any)
             .with_status(200)
             .with_header("content-type", "application/json")
             .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
-            .expect_at_least(1)
+            .expect(1)
             .create_async()
             .await;
-        let _latest_mock = invalid_server
-            .mock("GET", "/public/latest")
-            .match_query(mockito::Matcher::Any)
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        // concurrent callers share the initial request
+        let infos = futures::future::join_all((0..4).map(|_| client.chain_info())).await;
+        for info in infos {
+            assert_eq!(info.unwrap(), chained_chain_info());
+        }
+        info_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn client_clone_works() {
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<HttpClient>();
+
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
             .with_status(200)
             .with_header("content-type", "application/json")
-            .with_body(serde_json::to_string(&invalid_beacon()).unwrap())
-            .expect_at_least(1)
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
             .create_async()
             .await;
 
-        // test client without cache
-        let client = HttpClient::new(
-            invalid_server.url().as_str(),
-            Some(ChainOptions::new(true, false, None)),
-        )
-        .unwrap();
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
+        // the clone shares the cached chain info
+        let task = tokio::spawn({
+            let client = client.clone();
+            async move { client.chain_info().await.unwrap() }
+        });
+        assert_eq!(task.await.unwrap(), chained_chain_info());
+        info_mock.assert_async().await;
+    }
 
-        // latest endpoint
-        match client.latest().await {
-            Ok(_beacon) => panic!("Beacon should not validate"),
-            Err(_err) => (),
+    #[tokio::test]
+    async fn client_for_beacon_id_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/chains")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::to_string(&[
+                    hex::encode([0; 32]),
+                    hex::encode(chained_chain_info().hash()),
+                    hex::encode(unchained_chain_info().hash()),
+                ])
+                .unwrap(),
+            )
+            .create_async()
+            .await;
+        // the first chain listed fails, and is skipped
+        server
+            .mock("GET", format!("/{}/info", hex::encode([0; 32])).as_str())
+            .with_status(500)
+            .create_async()
+            .await;
+        for info in [chained_chain_info(), unchained_chain_info()] {
+            server
+                .mock(
+                    "GET",
+                    format!("/{}/info", hex::encode(info.hash())).as_str(),
+                )
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(serde_json::to_string(&info).unwrap())
+                .create_async()
+                .await;
         }
+
+        let client = HttpClient::for_beacon_id(server.url().as_str(), "testnet-unchained-3s")
+            .await
+            .unwrap();
+        assert_eq!(client.chain_info().await.unwrap(), unchained_chain_info());
+        assert!(HttpClient::for_beacon_id(server.url().as_str(), "quicknet")
+            .await
+            .is_err());
     }
 
     #[tokio::test]
-    async fn client_chain_verification_works() {
-        // unchained beacon
-        let mut valid_server = mockito::Server::new_async().await;
-        let _info_mock = valid_server
+    async fn client_refresh_chain_info_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
             .mock("GET", "/info")
-            .match_query(mockito::Matcher::Any)
+            .match_header("if-none-match", mockito::Matcher::Missing)
             .with_status(200)
             .with_header("content-type", "application/json")
-            .with_body(serde_json::to_string(&unchained_chain_info()).unwrap())
-            .expect_at_least(1)
+            .with_header("etag", "\"v1\"")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .expect(1)
             .create_async()
             .await;
-        let _latest_mock = valid_server
-            .mock("GET", "/public/latest")
-            .match_query(mockito::Matcher::Any)
-            .with_status(200)
-            .with_header("content-type", "application/json")
-            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
-            .expect_at_least(1)
+        let not_modified_mock = server
+            .mock("GET", "/info")
+            .match_header("if-none-match", "\"v1\"")
+            .with_status(304)
+            .expect(1)
             .create_async()
             .await;
 
-        // test client without cache
-        let unchained_info = unchained_chain_info();
-        let unchained_client = HttpClient::new(
-            valid_server.url().as_str(),
-            Some(ChainOptions::new(
-                true,
-                false,
-                Some(ChainVerification::new(
-                    Some(unchained_info.hash()),
-                    Some(unchained_info.public_key()),
-                )),
-            )),
-        )
-        .unwrap();
-
-        // latest endpoint
-        let latest = match unchained_client.latest().await {
-            Ok(beacon) => beacon,
-            Err(err) => panic!("fetch should have succeded {}", err),
-        };
-        assert_eq!(latest.beacon(), unchained_beacon());
-        assert_eq!(latest.time(), 1654677099);
-
-        // test with not the correct hash
-        let chained_info = chained_chain_info();
-        let invalid_client = HttpClient::new(
-            valid_server.url().as_str(),
-            Some(ChainOptions::new(
-                true,
-                false,
-                Some(ChainVerification::new(Some(chained_info.hash()), None)),
-            )),
-        )
-        .unwrap();
-
-        match invalid_client.latest().await {
-            Ok(_beacon) => panic!("Beacon should not validate"),
-            Err(_err) => (),
-        };
-        // test with not the correct public_key
-        let chained_info = chained_chain_info();
-        let invalid_client = HttpClient::new(
-            valid_server.url().as_str(),
-            Some(ChainOptions::new(
-                true,
-                false,
-                Some(ChainVerification::new(
-                    None,
-                    Some(chained_info.public_key()),
-                )),
-            )),
-        )
-        .unwrap();
+        // cached chain info never expires, but can be refreshed on demand
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
+        assert_eq!(
+            client.refresh_chain_info().await.unwrap(),
+            chained_chain_info()
+        );
+        assert_eq!(client.chain_info().await.unwrap(), chained_chain_info());
 
-        match invalid_client.latest().await {
-            Ok(_beacon) => panic!("Beacon should not validate"),
-            Err(_err) => (),
-        };
+        info_mock.assert_async().await;
+        not_modified_mock.assert_async().await;
     }
 }
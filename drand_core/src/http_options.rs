@@ -0,0 +1,898 @@
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{net::SocketAddr, sync::Arc, time::SystemTime};
+
+use anyhow::{anyhow, Result};
+#[cfg(not(target_arch = "wasm32"))]
+use sha2::{Digest, Sha256};
+
+pub use rustls::CipherSuite;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::dns::DnsResolver;
+
+#[derive(Debug, Clone)]
+/// Transport options of the HTTP client used to query relays.
+/// These do not affect how chain and beacons are validated. See [`crate::ChainOptions`] for this.
+pub struct HttpOptions {
+    tls: TlsOptions,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    no_proxy: Vec<String>,
+    headers: Vec<(String, String)>,
+    max_response_size: Option<usize>,
+    redirect_policy: RedirectPolicy,
+    #[cfg(feature = "http3")]
+    is_http3: bool,
+    #[cfg(feature = "compression")]
+    is_compression: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    resolves: Vec<(String, Vec<SocketAddr>)>,
+    #[cfg(not(target_arch = "wasm32"))]
+    dns_resolver: Option<DnsResolver>,
+}
+
+impl HttpOptions {
+    pub fn new(
+        tls: Option<TlsOptions>,
+        connect_timeout: Option<Duration>,
+        timeout: Option<Duration>,
+        user_agent: Option<String>,
+    ) -> Self {
+        Self {
+            tls: tls.unwrap_or_default(),
+            connect_timeout,
+            timeout,
+            user_agent,
+            proxy: None,
+            no_proxy: vec![],
+            headers: vec![],
+            max_response_size: None,
+            redirect_policy: RedirectPolicy::default(),
+            #[cfg(feature = "http3")]
+            is_http3: false,
+            #[cfg(feature = "compression")]
+            is_compression: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            resolves: vec![],
+            #[cfg(not(target_arch = "wasm32"))]
+            dns_resolver: None,
+        }
+    }
+
+    /// Route requests through the proxy at `proxy`, such as `http://proxy.example:3128` or `socks5h://127.0.0.1:9050` for Tor.
+    /// With `socks5h`, host names are resolved by the proxy.
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// Hosts reached directly, bypassing the proxy. Entries are domains, IP addresses, or CIDR blocks, such as `internal.example` or `10.0.0.0/8`.
+    pub fn with_no_proxy(mut self, no_proxy: &[&str]) -> Self {
+        self.no_proxy = no_proxy.iter().map(|host| host.to_string()).collect();
+        self
+    }
+
+    /// Send the `name` header with every request, for instance an API key expected by a private relay.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Authenticate every request with `token`, sent as an `Authorization: Bearer` header.
+    pub fn with_bearer_auth(self, token: &str) -> Self {
+        self.with_header("authorization", &format!("Bearer {token}"))
+    }
+
+    /// Stop reading responses larger than `max_response_size` bytes, so that a misbehaving relay cannot exhaust memory.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = Some(max_response_size);
+        self
+    }
+
+    /// Follow redirects according to `redirect_policy`. By default, up to 10 redirects are followed, including to other origins.
+    pub fn with_redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.redirect_policy = redirect_policy;
+        self
+    }
+
+    /// Query relays over HTTP/3 (QUIC), without negotiating it over an HTTP/1.1 or HTTP/2 connection first.
+    /// reqwest HTTP/3 support is experimental, and requires building with `RUSTFLAGS="--cfg reqwest_unstable"`.
+    #[cfg(feature = "http3")]
+    pub fn with_http3(mut self) -> Self {
+        self.is_http3 = true;
+        self
+    }
+
+    /// Ask relays for uncompressed responses, which are easier to inspect when debugging.
+    /// By default, gzip and brotli responses are negotiated, and decompressed transparently.
+    #[cfg(feature = "compression")]
+    pub fn without_compression(mut self) -> Self {
+        self.is_compression = false;
+        self
+    }
+
+    /// Resolve `domain` to `addrs`, instead of querying DNS. The port of `addrs` is ignored, and the one of the relay URL is used.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_resolve(mut self, domain: &str, addrs: &[SocketAddr]) -> Self {
+        self.resolves.push((domain.to_string(), addrs.to_vec()));
+        self
+    }
+
+    /// Resolve relay host names with `dns_resolver`, such as [`DnsResolver::dns_over_https`], instead of the system resolver.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_dns_resolver(mut self, dns_resolver: DnsResolver) -> Self {
+        self.dns_resolver = Some(dns_resolver);
+        self
+    }
+
+    pub fn tls(&self) -> TlsOptions {
+        self.tls.clone()
+    }
+
+    /// Maximum duration to establish a connection with a relay. `None` when unbounded.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    /// Maximum duration of a request, from connection until the response body has been read. `None` when unbounded.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// `User-Agent` header sent with every request. `None` when no header is sent.
+    pub fn user_agent(&self) -> Option<String> {
+        self.user_agent.clone()
+    }
+
+    /// Proxy requests are routed through. `None` when reqwest environment variables apply.
+    pub fn proxy(&self) -> Option<String> {
+        self.proxy.clone()
+    }
+
+    /// Hosts bypassing the proxy.
+    pub fn no_proxy(&self) -> Vec<String> {
+        self.no_proxy.clone()
+    }
+
+    /// Headers sent with every request, in addition to `User-Agent`.
+    pub fn headers(&self) -> Vec<(String, String)> {
+        self.headers.clone()
+    }
+
+    /// Maximum size of a relay response, in bytes. Defaults to [`crate::transport::DEFAULT_MAX_RESPONSE_SIZE`].
+    pub fn max_response_size(&self) -> usize {
+        self.max_response_size
+            .unwrap_or(crate::transport::DEFAULT_MAX_RESPONSE_SIZE)
+    }
+
+    pub fn redirect_policy(&self) -> RedirectPolicy {
+        self.redirect_policy
+    }
+
+    /// Whether relays are queried over HTTP/3.
+    #[cfg(feature = "http3")]
+    pub fn is_http3(&self) -> bool {
+        self.is_http3
+    }
+
+    /// Whether gzip and brotli responses are negotiated.
+    #[cfg(feature = "compression")]
+    pub fn is_compression(&self) -> bool {
+        self.is_compression
+    }
+
+    fn header_map(&self) -> Result<reqwest::header::HeaderMap> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| anyhow!("invalid header name {name}: {e}"))?;
+            let mut value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| anyhow!("invalid value for header {name}: {e}"))?;
+            // credentials are not to be exposed in logs
+            value.set_sensitive(name == reqwest::header::AUTHORIZATION);
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+
+    /// Build a reqwest client enforcing these options.
+    /// In the browser, connections are managed by `fetch`, and only headers apply.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn build_client(&self) -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .default_headers(self.header_map()?)
+            .build()
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Build a reqwest client enforcing these options.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn build_client(&self) -> Result<reqwest::Client> {
+        let mut builder = self
+            .tls
+            .configure(reqwest::Client::builder())?
+            .default_headers(self.header_map()?);
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        builder = builder.redirect(self.redirect_policy.policy());
+        #[cfg(feature = "http3")]
+        if self.is_http3 {
+            builder = builder.http3_prior_knowledge();
+        }
+        #[cfg(feature = "compression")]
+        if !self.is_compression {
+            builder = builder.no_gzip().no_brotli();
+        }
+        for (domain, addrs) in &self.resolves {
+            builder = builder.resolve_to_addrs(domain, addrs);
+        }
+        if let Some(dns_resolver) = &self.dns_resolver {
+            builder = builder.dns_resolver(Arc::new(dns_resolver.clone()));
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy =
+                reqwest::Proxy::all(proxy).map_err(|e| anyhow!("invalid proxy {proxy}: {e}"))?;
+            let no_proxy = reqwest::NoProxy::from_string(&self.no_proxy.join(","));
+            builder = builder.proxy(proxy.no_proxy(no_proxy));
+        }
+        builder.build().map_err(|e| anyhow!(e))
+    }
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self::new(None, None, None, None)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Which redirects sent by a relay are followed.
+/// Restricting redirects to the relay origin keeps certificate pins and authentication headers from being sent elsewhere.
+pub struct RedirectPolicy {
+    max_redirects: usize,
+    is_cross_origin: bool,
+}
+
+impl RedirectPolicy {
+    /// Follow up to `max_redirects` redirects. Redirects to another origin (scheme, host, and port) are rejected unless `is_cross_origin` is set.
+    pub fn new(max_redirects: usize, is_cross_origin: bool) -> Self {
+        Self {
+            max_redirects,
+            is_cross_origin,
+        }
+    }
+
+    /// Do not follow redirects. The redirect response is returned as an error.
+    pub fn none() -> Self {
+        Self::new(0, false)
+    }
+
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects
+    }
+
+    pub fn is_cross_origin(&self) -> bool {
+        self.is_cross_origin
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn policy(&self) -> reqwest::redirect::Policy {
+        if self.max_redirects == 0 {
+            return reqwest::redirect::Policy::none();
+        }
+        let policy = *self;
+        reqwest::redirect::Policy::custom(move |attempt| {
+            let is_same_origin = attempt
+                .previous()
+                .first()
+                .map_or(true, |first| first.origin() == attempt.url().origin());
+            if attempt.previous().len() > policy.max_redirects {
+                attempt.error(format!("more than {} redirects", policy.max_redirects))
+            } else if !policy.is_cross_origin && !is_same_origin {
+                let message = format!(
+                    "redirect to another origin {} is not allowed",
+                    attempt.url()
+                );
+                attempt.error(message)
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self::new(10, true)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+/// TLS protocol version.
+pub enum TlsVersion {
+    #[default]
+    Tls12,
+    Tls13,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// SHA-256 digest a certificate of the relay chain has to match.
+pub enum CertificatePin {
+    /// Digest of the DER encoded certificate.
+    Certificate(Vec<u8>),
+    /// Digest of the DER encoded certificate public key (SubjectPublicKeyInfo). It survives certificate renewals with the same key.
+    Spki(Vec<u8>),
+}
+
+impl CertificatePin {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn matches(&self, certificate: &[u8]) -> bool {
+        match self {
+            Self::Certificate(digest) => Sha256::digest(certificate).as_slice() == digest,
+            Self::Spki(digest) => subject_public_key_info(certificate)
+                .map_or(false, |spki| Sha256::digest(spki).as_slice() == digest),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// TLS policy for relay connections.
+/// By default, TLS 1.2 and above are accepted, with the cipher suites considered safe by rustls, and certificates issued by Mozilla trusted roots.
+pub struct TlsOptions {
+    min_version: TlsVersion,
+    cipher_suites: Option<Vec<CipherSuite>>,
+    root_certificates: Vec<Vec<u8>>,
+    pins: Vec<CertificatePin>,
+}
+
+impl TlsOptions {
+    /// `cipher_suites` restricts negotiated cipher suites. Suites not supported by rustls are ignored.
+    pub fn new(min_version: TlsVersion, cipher_suites: Option<Vec<CipherSuite>>) -> Self {
+        Self {
+            min_version,
+            cipher_suites,
+            root_certificates: vec![],
+            pins: vec![],
+        }
+    }
+
+    /// Trust the PEM encoded certificates in `pem`, in addition to Mozilla trusted roots. Relays with a private certificate authority are reachable this way.
+    pub fn with_root_certificates(mut self, pem: &[u8]) -> Self {
+        self.root_certificates.push(pem.to_vec());
+        self
+    }
+
+    /// Only accept relays whose certificate chain contains a certificate matching `pin`. Can be called multiple times to allow for key rotation.
+    /// Pinning comes on top of the usual certificate validation.
+    pub fn with_pin(mut self, pin: CertificatePin) -> Self {
+        self.pins.push(pin);
+        self
+    }
+
+    /// Minimum TLS version accepted when connecting to a relay.
+    pub fn min_version(&self) -> TlsVersion {
+        self.min_version
+    }
+
+    /// Cipher suites allowed when connecting to a relay. `None` when defaults are used.
+    pub fn cipher_suites(&self) -> Option<Vec<CipherSuite>> {
+        self.cipher_suites.clone()
+    }
+
+    /// PEM encoded certificates trusted in addition to Mozilla trusted roots.
+    pub fn root_certificates(&self) -> Vec<Vec<u8>> {
+        self.root_certificates.clone()
+    }
+
+    /// Pins the relay certificate chain has to match. Empty when certificates are not pinned.
+    pub fn pins(&self) -> Vec<CertificatePin> {
+        self.pins.clone()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn configure(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if self.cipher_suites.is_none() && self.root_certificates.is_empty() && self.pins.is_empty()
+        {
+            let version = match self.min_version {
+                TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+                TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+            };
+            return Ok(builder.min_tls_version(version));
+        }
+
+        // reqwest exposes neither cipher suites nor certificate pinning. A dedicated rustls configuration is required.
+        let suites: Vec<rustls::SupportedCipherSuite> = match &self.cipher_suites {
+            Some(allowed) => rustls::ALL_CIPHER_SUITES
+                .iter()
+                .filter(|suite| allowed.contains(&suite.suite()))
+                .copied()
+                .collect(),
+            None => rustls::DEFAULT_CIPHER_SUITES.to_vec(),
+        };
+        if suites.is_empty() {
+            return Err(anyhow!("none of the provided cipher suites is supported"));
+        }
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_version {
+            TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+            TlsVersion::Tls13 => &[&rustls::version::TLS13],
+        };
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        for pem in &self.root_certificates {
+            let certificates = rustls_pemfile::certs(&mut pem.as_slice())
+                .map_err(|e| anyhow!("invalid root certificate: {e}"))?;
+            if certificates.is_empty() {
+                return Err(anyhow!("no PEM certificate found in root certificates"));
+            }
+            for certificate in certificates {
+                roots
+                    .add(&rustls::Certificate(certificate))
+                    .map_err(|e| anyhow!("invalid root certificate: {e}"))?;
+            }
+        }
+
+        let config = rustls::ClientConfig::builder()
+            .with_cipher_suites(&suites)
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(versions)
+            .map_err(|e| anyhow!("invalid TLS policy: {e}"))?;
+        let config = if self.pins.is_empty() {
+            config.with_root_certificates(roots).with_no_client_auth()
+        } else {
+            config
+                .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier {
+                    verifier: rustls::client::WebPkiVerifier::new(roots, None),
+                    pins: self.pins.clone(),
+                }))
+                .with_no_client_auth()
+        };
+        Ok(builder.use_preconfigured_tls(config))
+    }
+}
+
+/// Certificate verifier enforcing pins once the certificate chain has been validated.
+#[cfg(not(target_arch = "wasm32"))]
+struct PinnedCertificateVerifier {
+    verifier: rustls::client::WebPkiVerifier,
+    pins: Vec<CertificatePin>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl rustls::client::ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        server_name: &rustls::ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<rustls::client::ServerCertVerified, rustls::Error> {
+        let verified = self.verifier.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+        let is_pinned = std::iter::once(end_entity)
+            .chain(intermediates)
+            .any(|certificate| self.pins.iter().any(|pin| pin.matches(&certificate.0)));
+        match is_pinned {
+            true => Ok(verified),
+            false => Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            )),
+        }
+    }
+}
+
+/// DER encoded SubjectPublicKeyInfo of a DER encoded X.509 certificate.
+#[cfg(not(target_arch = "wasm32"))]
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    /// Split a DER element into its tag, the whole element, and its content.
+    fn element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+        let (&tag, rest) = der.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let size = (first & 0x7f) as usize;
+            if size == 0 || size > 4 || rest.len() < size {
+                return None;
+            }
+            let len = rest[..size]
+                .iter()
+                .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[size..])
+        };
+        let header = der.len() - rest.len();
+        let content = rest.get(..len)?;
+        Some((tag, &der[..header + len], content))
+    }
+
+    // Certificate ::= SEQUENCE { tbsCertificate, ... }
+    let (_tag, _certificate, certificate) = element(certificate)?;
+    let (_tag, _tbs, mut tbs) = element(certificate)?;
+    // tbsCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer, validity, subject, subjectPublicKeyInfo, ... }
+    let (tag, version, _content) = element(tbs)?;
+    if tag == 0xa0 {
+        tbs = &tbs[version.len()..];
+    }
+    for _field in 0..5 {
+        let (_tag, field, _content) = element(tbs)?;
+        tbs = &tbs[field.len()..];
+    }
+    let (_tag, spki, _content) = element(tbs)?;
+    Some(spki)
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+
+    /// Self-signed certificate for `relay.drand.test`.
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBjDCCATOgAwIBAgIUTPE1sNvO1AMFbW5lcFaY+icWG7EwCgYIKoZIzj0EAwIw
+GzEZMBcGA1UEAwwQcmVsYXkuZHJhbmQudGVzdDAgFw0yNjEwMTcxNzU5MTBaGA8y
+MTI2MDkyMzE3NTkxMFowGzEZMBcGA1UEAwwQcmVsYXkuZHJhbmQudGVzdDBZMBMG
+ByqGSM49AgEGCCqGSM49AwEHA0IABDVH+BbKHPps1vVYM2kLeTVP2tELEc1c3aGn
+2DTrKUleNoifdj357N0H9/7VTnkobjs919WGsKRl4GtWWqAaLrujUzBRMB0GA1Ud
+DgQWBBT3VQl2wnycyhW8Hb5D8cXUfOYcPzAfBgNVHSMEGDAWgBT3VQl2wnycyhW8
+Hb5D8cXUfOYcPzAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0cAMEQCIE3w
+wgvPCS2bTYb2OANffsQTfE+FBZsXSkYQolYWAd7MAiBpUEIGV1Q+YqSeKfGBSqQy
+Lyv++o/tZRE0ZM74/Qgo8w==
+-----END CERTIFICATE-----";
+
+    #[test]
+    fn tls_options_works() {
+        // defaults and minimum version only rely on reqwest
+        assert!(HttpOptions::default().build_client().is_ok());
+        let tls = TlsOptions::new(TlsVersion::Tls13, None);
+        assert!(HttpOptions::new(Some(tls), None, None, None)
+            .build_client()
+            .is_ok());
+
+        // restricted cipher suites
+        let tls = TlsOptions::new(
+            TlsVersion::Tls12,
+            Some(vec![
+                CipherSuite::TLS13_AES_256_GCM_SHA384,
+                CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            ]),
+        );
+        assert!(HttpOptions::new(Some(tls), None, None, None)
+            .build_client()
+            .is_ok());
+
+        // TLS 1.3 cannot be negotiated with TLS 1.2 suites only
+        let tls = TlsOptions::new(
+            TlsVersion::Tls13,
+            Some(vec![CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384]),
+        );
+        assert!(HttpOptions::new(Some(tls), None, None, None)
+            .build_client()
+            .is_err());
+    }
+
+    #[test]
+    fn certificate_pin_works() {
+        let certificate = rustls_pemfile::certs(&mut CERTIFICATE.as_bytes())
+            .unwrap()
+            .remove(0);
+
+        let spki = hex!("351a958f7f89968f3a1605cb63cd48b9d4e2f21b8edf3426675ef62ca36d5b44");
+        assert!(CertificatePin::Spki(spki.to_vec()).matches(&certificate));
+        let digest = hex!("fcb36fa0d31eb60c8089697b9b175abf20fc1a48cd48ade812585b2a2fa31fc9");
+        assert!(CertificatePin::Certificate(digest.to_vec()).matches(&certificate));
+
+        assert!(!CertificatePin::Spki(digest.to_vec()).matches(&certificate));
+        assert!(!CertificatePin::Spki(spki.to_vec()).matches(&certificate[1..]));
+    }
+
+    #[test]
+    fn root_certificates_works() {
+        let tls = TlsOptions::default()
+            .with_root_certificates(CERTIFICATE.as_bytes())
+            .with_pin(CertificatePin::Spki(vec![0; 32]));
+        assert!(HttpOptions::new(Some(tls), None, None, None)
+            .build_client()
+            .is_ok());
+
+        let tls = TlsOptions::default().with_root_certificates(b"not a certificate");
+        assert!(HttpOptions::new(Some(tls), None, None, None)
+            .build_client()
+            .is_err());
+    }
+
+    #[test]
+    fn proxy_works() {
+        for proxy in [
+            "http://127.0.0.1:3128",
+            "socks5://127.0.0.1:1080",
+            "socks5h://127.0.0.1:9050",
+        ] {
+            let http_options = HttpOptions::default().with_proxy(proxy);
+            assert_eq!(http_options.proxy(), Some(proxy.to_string()));
+            assert!(http_options.build_client().is_ok());
+        }
+
+        let http_options = HttpOptions::default()
+            .with_proxy("http://127.0.0.1:3128")
+            .with_no_proxy(&["localhost", "10.0.0.0/8"]);
+        assert_eq!(http_options.no_proxy(), vec!["localhost", "10.0.0.0/8"]);
+        assert!(http_options.build_client().is_ok());
+
+        assert!(HttpOptions::default()
+            .with_proxy("not a proxy")
+            .build_client()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn redirect_policy_works() {
+        let mut relay = mockito::Server::new_async().await;
+        let mut other = mockito::Server::new_async().await;
+        let info = serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap();
+        relay
+            .mock("GET", "/info")
+            .with_status(302)
+            .with_header("location", "/v2/info")
+            .create_async()
+            .await;
+        relay
+            .mock("GET", "/v2/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&info)
+            .create_async()
+            .await;
+        relay
+            .mock("GET", "/public/latest")
+            .with_status(302)
+            .with_header("location", &format!("{}/public/latest", other.url()))
+            .create_async()
+            .await;
+        other
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::beacon::tests::chained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        let client =
+            crate::ApiClient::with_http_options(&relay.url(), HttpOptions::default()).unwrap();
+        assert!(client.chain_info().await.is_ok());
+        assert!(client.latest().await.is_ok());
+
+        let http_options =
+            HttpOptions::default().with_redirect_policy(RedirectPolicy::new(1, false));
+        let client = crate::ApiClient::with_http_options(&relay.url(), http_options).unwrap();
+        assert!(client.chain_info().await.is_ok());
+        assert!(client.latest().await.is_err());
+
+        let http_options = HttpOptions::default().with_redirect_policy(RedirectPolicy::none());
+        let client = crate::ApiClient::with_http_options(&relay.url(), http_options).unwrap();
+        let err = client.chain_info().await.unwrap_err();
+        assert_eq!(err.relay().unwrap().status(), Some(302));
+    }
+
+    #[tokio::test]
+    async fn redirect_policy_cross_origin_works() {
+        let mut relay = mockito::Server::new_async().await;
+        let mut other = mockito::Server::new_async().await;
+        relay
+            .mock("GET", "/public/latest")
+            .with_status(302)
+            .with_header("location", &format!("{}/public/latest", other.url()))
+            .create_async()
+            .await;
+        let other_mock = other
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::beacon::tests::chained_beacon()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let http_options =
+            HttpOptions::default().with_redirect_policy(RedirectPolicy::new(10, false));
+        let client = crate::ApiClient::with_http_options(&relay.url(), http_options).unwrap();
+        let err = client.latest().await.unwrap_err();
+        let mut source: Option<&dyn std::error::Error> = Some(&err);
+        let mut messages = vec![];
+        while let Some(err) = source {
+            messages.push(err.to_string());
+            source = err.source();
+        }
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("redirect to another origin")));
+
+        let http_options =
+            HttpOptions::default().with_redirect_policy(RedirectPolicy::new(10, true));
+        let client = crate::ApiClient::with_http_options(&relay.url(), http_options).unwrap();
+        assert_eq!(
+            client.latest().await.unwrap(),
+            crate::beacon::tests::chained_beacon()
+        );
+        other_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn resolve_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        let addr: SocketAddr = server.host_with_port().parse().unwrap();
+        let http_options = HttpOptions::default().with_resolve("relay.invalid", &[addr]);
+        let client = crate::ApiClient::with_http_options(
+            &format!("http://relay.invalid:{}", addr.port()),
+            http_options,
+        )
+        .unwrap();
+        assert!(client.chain_info().await.is_ok());
+    }
+
+    #[cfg(feature = "http3")]
+    #[test]
+    fn http3_works() {
+        let http_options = HttpOptions::default().with_http3();
+        assert!(http_options.is_http3());
+        assert!(http_options.build_client().is_ok());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn compression_works() {
+        let mut server = mockito::Server::new_async().await;
+        let compressed_mock = server
+            .mock("GET", "/info")
+            .match_header(
+                "accept-encoding",
+                mockito::Matcher::Regex("gzip".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        let client =
+            crate::ApiClient::with_http_options(&server.url(), HttpOptions::default()).unwrap();
+        assert!(client.chain_info().await.is_ok());
+        compressed_mock.assert_async().await;
+
+        let http_options = HttpOptions::default().without_compression();
+        assert!(!http_options.is_compression());
+        let client = crate::ApiClient::with_http_options(&server.url(), http_options).unwrap();
+        assert!(client.chain_info().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn http_proxy_works() {
+        let mut proxy = mockito::Server::new_async().await;
+        let info_mock = proxy
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        // relay is only reachable through the proxy
+        let http_options = HttpOptions::default().with_proxy(&proxy.url());
+        let client =
+            crate::ApiClient::with_http_options("http://relay.invalid", http_options).unwrap();
+        assert!(client.chain_info().await.is_ok());
+        info_mock.assert_async().await;
+
+        // proxy is unreachable, but bypassed for the relay
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let unreachable = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let http_options = HttpOptions::default()
+            .with_proxy(&unreachable)
+            .with_no_proxy(&["127.0.0.1"]);
+        let client = crate::ApiClient::with_http_options(&proxy.url(), http_options).unwrap();
+        assert!(client.chain_info().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn headers_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info_mock = server
+            .mock("GET", "/info")
+            .match_header("authorization", "Bearer secret")
+            .match_header("x-api-key", "key")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+
+        let http_options = HttpOptions::default()
+            .with_bearer_auth("secret")
+            .with_header("x-api-key", "key");
+        let client = crate::ApiClient::with_http_options(&server.url(), http_options).unwrap();
+        assert!(client.chain_info().await.is_ok());
+        info_mock.assert_async().await;
+
+        assert!(HttpOptions::default()
+            .with_header("invalid name", "value")
+            .build_client()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn max_response_size_works() {
+        let mut server = mockito::Server::new_async().await;
+        let info = serde_json::to_string(&crate::chain::tests::chained_chain_info()).unwrap();
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(&info)
+            .create_async()
+            .await;
+
+        let http_options = HttpOptions::default().with_max_response_size(info.len());
+        let client = crate::ApiClient::with_http_options(&server.url(), http_options).unwrap();
+        assert!(client.chain_info().await.is_ok());
+
+        let http_options = HttpOptions::default().with_max_response_size(info.len() - 1);
+        let client = crate::ApiClient::with_http_options(&server.url(), http_options).unwrap();
+        let err = client.chain_info().await.unwrap_err();
+        assert!(matches!(
+            err.relay(),
+            Some(crate::RelayError::TooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn timeout_works() {
+        // relay accepting connections, but never responding
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let http_options = HttpOptions::new(None, None, Some(Duration::from_millis(100)), None);
+        let client = crate::ApiClient::with_http_options(&base_url, http_options).unwrap();
+        match client.chain_info().await {
+            Ok(_info) => panic!("Relay should not respond"),
+            Err(err) => match err.relay() {
+                Some(crate::RelayError::Network(err)) => {
+                    assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout())
+                }
+                _ => panic!("Timeout should be a network error"),
+            },
+        }
+    }
+}
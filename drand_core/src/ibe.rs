@@ -0,0 +1,284 @@
+//! Identity-based encryption against a chain public key, the primitive beneath [timelock encryption](crate::tlock).
+//!
+//! It is the Boneh–Franklin scheme, with the Fujisaki–Okamoto transform, as implemented by drand [kyber](https://github.com/drand/kyber/tree/master/encrypt/ibe).
+//! The chain public key is the master public key. The identity of a round is the message its beacon signs, see [`identity`], and the beacon signature is the private key of that identity.
+//! Encryption is on the group of the public key, and the identity is hashed to the group of signatures, as beacons are.
+//!
+//! Messages are short, such as the symmetric key of a larger ciphertext.
+
+use anyhow::anyhow;
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    AffineRepr, CurveGroup,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::{
+    bls_signatures::{hash_to_g1, hash_to_g2, PublicKey},
+    error::{Error, Result},
+    point,
+};
+
+/// Maximum length of a message, in bytes. It is the output length of SHA-256.
+pub const MAX_MESSAGE_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    /// Commitment to the encryption randomness, as a compressed point on the group of the public key.
+    pub u: Vec<u8>,
+    /// Random mask, encrypted with the pairing of the identity.
+    pub v: Vec<u8>,
+    /// Message, encrypted with the random mask.
+    pub w: Vec<u8>,
+}
+
+impl Ciphertext {
+    /// `u || v || w`, as found in a tlock stanza.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.u.as_slice(), &self.v, &self.w].concat()
+    }
+
+    /// Parse `u || v || w`. `u` is on the group of `public_key`, and `v` and `w` have the same length.
+    pub fn from_bytes(bytes: &[u8], public_key: &[u8]) -> Result<Self> {
+        let u_len = point::compressed_len(public_key);
+        let Some(rest) = bytes.get(u_len..).filter(|rest| rest.len() % 2 == 0) else {
+            return Err(Error::Other(anyhow!(
+                "invalid ciphertext length {}",
+                bytes.len()
+            )));
+        };
+        let (v, w) = rest.split_at(rest.len() / 2);
+        Ok(Self {
+            u: bytes[..u_len].to_vec(),
+            v: v.to_vec(),
+            w: w.to_vec(),
+        })
+    }
+}
+
+/// Identity of `round`, which is the message signed by unchained beacons: `SHA-256(round)`, with `round` as a big-endian `u64`.
+pub fn identity(round: u64) -> Vec<u8> {
+    Sha256::digest(round.to_be_bytes()).to_vec()
+}
+
+/// Encrypt `message` to `identity`, under the master `public_key`.
+/// Fails if `message` is longer than [`MAX_MESSAGE_LEN`], or `public_key` is not a valid point.
+pub fn encrypt(public_key: &[u8], identity: &[u8], message: &[u8]) -> Result<Ciphertext> {
+    if message.len() > MAX_MESSAGE_LEN {
+        return Err(Error::Other(anyhow!(
+            "message is {} bytes, more than {MAX_MESSAGE_LEN}",
+            message.len()
+        )));
+    }
+    let public_key = point::compress(public_key)?;
+    let mut sigma = vec![0; message.len()];
+    rand::thread_rng().fill_bytes(&mut sigma);
+    let mut r = h3(&sigma, message)?;
+
+    // e(master, Q_id)^r is computed as e(r * master, Q_id)
+    let (u, mut r_gid) = match public_key.len() {
+        48 => {
+            let master = G1Affine::deserialize_compressed(public_key.as_slice())
+                .map_err(|_| anyhow!("deserialization failed"))?;
+            let u = (G1Affine::generator() * r).into_affine();
+            let r_gid = Bls12_381::pairing((master * r).into_affine(), hash_to_g2(identity)?);
+            (serialize(&u)?, r_gid)
+        }
+        _ => {
+            let master = G2Affine::deserialize_compressed(public_key.as_slice())
+                .map_err(|_| anyhow!("deserialization failed"))?;
+            let u = (G2Affine::generator() * r).into_affine();
+            let r_gid = Bls12_381::pairing(hash_to_g1(identity)?, (master * r).into_affine());
+            (serialize(&u)?, r_gid)
+        }
+    };
+
+    // knowing the randomness or the pairing would unmask the message
+    r.zeroize();
+    let v = xor(&sigma, &h2(&r_gid, sigma.len())?);
+    r_gid.zeroize();
+    let w = xor(message, &h4(&sigma, message.len()));
+    sigma.zeroize();
+    Ok(Ciphertext { u, v, w })
+}
+
+/// Private key of `identity`, which is its signature by the master key, such as a beacon signature.
+/// `signature` is verified against `public_key` first, so that a forged key is rejected before any decryption.
+pub fn extract(public_key: &[u8], identity: &[u8], signature: &[u8]) -> Result<Vec<u8>> {
+    let public_key = PublicKey::from_compressed(&point::compress(public_key)?)?;
+    let signature = point::compress(signature)?;
+    if !public_key.verify(&signature, identity)? {
+        return Err(Error::Other(anyhow!(
+            "signature is not the private key of the identity"
+        )));
+    }
+    Ok(signature)
+}
+
+/// Decrypt `ciphertext` with the `private_key` of the identity it has been encrypted to, see [`extract`].
+/// Fails if the ciphertext has been tampered with, or has been encrypted to another identity.
+pub fn decrypt(private_key: &[u8], ciphertext: &Ciphertext) -> Result<Vec<u8>> {
+    let Ciphertext { u, v, w } = ciphertext;
+    if v.len() != w.len() || w.len() > MAX_MESSAGE_LEN {
+        return Err(Error::Other(anyhow!("invalid ciphertext length")));
+    }
+    let mut r_gid = match private_key.len() {
+        96 => {
+            let mut private_key = G2Affine::deserialize_compressed(private_key)
+                .map_err(|_| anyhow!("invalid private key"))?;
+            let u = G1Affine::deserialize_compressed(u.as_slice())
+                .map_err(|_| anyhow!("invalid ciphertext commitment"))?;
+            let r_gid = Bls12_381::pairing(u, private_key);
+            private_key.zeroize();
+            r_gid
+        }
+        48 => {
+            let mut private_key = G1Affine::deserialize_compressed(private_key)
+                .map_err(|_| anyhow!("invalid private key"))?;
+            let u = G2Affine::deserialize_compressed(u.as_slice())
+                .map_err(|_| anyhow!("invalid ciphertext commitment"))?;
+            let r_gid = Bls12_381::pairing(private_key, u);
+            private_key.zeroize();
+            r_gid
+        }
+        len => return Err(Error::Other(anyhow!("invalid private key length {len}"))),
+    };
+
+    let mut sigma = xor(v, &h2(&r_gid, v.len())?);
+    r_gid.zeroize();
+    let message = xor(w, &h4(&sigma, w.len()));
+    // the commitment is recomputed from the decrypted values, which binds them to the ciphertext
+    let r = h3(&sigma, &message)?;
+    sigma.zeroize();
+    let expected = match u.len() {
+        48 => serialize(&(G1Affine::generator() * r).into_affine())?,
+        _ => serialize(&(G2Affine::generator() * r).into_affine())?,
+    };
+    if expected != *u {
+        return Err(Error::Other(anyhow!(
+            "decryption failed, the private key is not the one of the ciphertext identity"
+        )));
+    }
+    Ok(message)
+}
+
+/// Mask derived from the pairing result.
+fn h2(r_gid: &PairingOutput<Bls12_381>, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    r_gid
+        .0
+        .serialize_compressed(&mut bytes)
+        .map_err(|_| anyhow!("serialization failed"))?;
+    // kyber serializes coefficients in the reverse order, as big-endian, which is the reverse of arkworks bytes
+    bytes.reverse();
+    let hash = Sha256::new()
+        .chain_update(b"IBE-H2")
+        .chain_update(&bytes)
+        .finalize();
+    bytes.zeroize();
+    Ok(hash[..len].to_vec())
+}
+
+/// Encryption randomness, derived from the mask and the message.
+fn h3(sigma: &[u8], message: &[u8]) -> Result<Fr> {
+    let buffer = Sha256::new()
+        .chain_update(b"IBE-H3")
+        .chain_update(sigma)
+        .chain_update(message)
+        .finalize();
+    // hashes are drawn until one is a canonical scalar once its top bit is cleared
+    for i in 1..u16::MAX {
+        let mut hash = Sha256::new()
+            .chain_update(i.to_le_bytes())
+            .chain_update(buffer)
+            .finalize();
+        hash[0] >>= 1;
+        // the hash is read as big-endian, and arkworks scalars are little-endian
+        hash.reverse();
+        if let Ok(r) = Fr::deserialize_compressed(hash.as_slice()) {
+            return Ok(r);
+        }
+    }
+    Err(Error::Other(anyhow!("cannot derive a scalar")))
+}
+
+/// Mask of the message, derived from `sigma`.
+fn h4(sigma: &[u8], len: usize) -> Vec<u8> {
+    let hash = Sha256::new()
+        .chain_update(b"IBE-H4")
+        .chain_update(sigma)
+        .finalize();
+    hash[..len].to_vec()
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+fn serialize<P: CanonicalSerialize>(point: &P) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    point
+        .serialize_compressed(&mut bytes)
+        .map_err(|_| anyhow!("serialization failed"))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{
+        tests::{unchained_beacon, unchained_beacon_on_g1},
+        Message,
+    };
+    use crate::chain::tests::{unchained_chain_info, unchained_chain_on_g1_info};
+
+    use super::*;
+
+    const MESSAGE: &[u8] = b"sixteen byte key";
+
+    #[test]
+    fn ibe_works() {
+        let fixtures = [
+            (unchained_beacon(), unchained_chain_info()),
+            (unchained_beacon_on_g1(), unchained_chain_on_g1_info()),
+        ];
+        for (beacon, info) in fixtures {
+            let id = identity(beacon.round());
+            assert_eq!(id, beacon.message().unwrap());
+
+            let ciphertext = encrypt(&info.public_key(), &id, MESSAGE).unwrap();
+            assert_eq!(
+                ciphertext.u.len(),
+                point::compressed_len(&info.public_key())
+            );
+            let private_key = extract(&info.public_key(), &id, beacon.signature()).unwrap();
+            assert_eq!(decrypt(&private_key, &ciphertext).unwrap(), MESSAGE);
+
+            let parsed =
+                Ciphertext::from_bytes(&ciphertext.to_bytes(), &info.public_key()).unwrap();
+            assert_eq!(parsed, ciphertext);
+
+            // the key of another round does not decrypt it
+            let other =
+                encrypt(&info.public_key(), &identity(beacon.round() + 1), MESSAGE).unwrap();
+            assert!(decrypt(&private_key, &other).is_err());
+            assert!(extract(&info.public_key(), &identity(1), beacon.signature()).is_err());
+        }
+    }
+
+    #[test]
+    fn ibe_tampered_works() {
+        let info = unchained_chain_info();
+        let id = identity(1000000);
+        let private_key = extract(&info.public_key(), &id, unchained_beacon().signature()).unwrap();
+        let mut ciphertext = encrypt(&info.public_key(), &id, MESSAGE).unwrap();
+        ciphertext.w[0] ^= 1;
+        assert!(decrypt(&private_key, &ciphertext).is_err());
+
+        assert!(encrypt(&info.public_key(), &id, &[0; MAX_MESSAGE_LEN + 1]).is_err());
+        assert!(Ciphertext::from_bytes(&[0; 49], &info.public_key()).is_err());
+    }
+}
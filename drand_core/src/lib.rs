@@ -24,9 +24,90 @@
 //! }
 //! ```
 
+mod adaptive_client;
+pub use adaptive_client::AdaptiveClient;
+mod api_client;
+pub use api_client::{ApiClient, RelayHealth};
 pub mod beacon;
+mod beacon_cache;
+pub use beacon_cache::BeaconCacheStats;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(not(any(feature = "arkworks", feature = "blst")))]
+compile_error!("a BLS backend is required, enable either the `arkworks` or the `blst` feature");
+// arkworks curve types back `ibe`, `partial` and `signer`, so the module is kept with `blst`, which only replaces beacon verification
+#[cfg(feature = "arkworks")]
 mod bls_signatures;
+#[cfg(feature = "blst")]
+mod blst_signatures;
+#[cfg(feature = "bn254")]
+mod bn254_signatures;
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod chain;
+mod circuit_breaker;
+pub mod committee;
+pub mod coverage;
 pub use chain::ChainOptions;
+#[cfg(not(target_arch = "wasm32"))]
+mod disk_cache;
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+mod dns;
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+pub use dns::DnsResolver;
+#[cfg(feature = "arkworks")]
+pub mod dkg;
+pub mod draw;
+mod error;
+pub use error::{Error, RelayError, Result};
+#[cfg(feature = "evm")]
+pub mod evm;
+#[cfg(feature = "group")]
+pub mod group;
+#[cfg(feature = "grpc")]
+mod grpc_client;
+#[cfg(feature = "grpc")]
+pub use grpc_client::{GrpcClient, GrpcTransport};
+mod hedged_client;
+pub use hedged_client::HedgedClient;
+mod hex_bytes;
 mod http_client;
 pub use http_client::HttpClient;
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+pub use http_client::HttpClientBuilder;
+#[cfg(feature = "reqwest")]
+mod http_options;
+#[cfg(feature = "reqwest")]
+pub use http_options::{
+    CertificatePin, CipherSuite, HttpOptions, RedirectPolicy, TlsOptions, TlsVersion,
+};
+#[cfg(feature = "arkworks")]
+pub mod ibe;
+pub mod lottery;
+pub mod metrics;
+mod multi_beacon_client;
+pub use multi_beacon_client::MultiBeaconClient;
+#[cfg(feature = "arkworks")]
+pub mod partial;
+pub mod point;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+#[cfg(feature = "pubsub")]
+mod pubsub_client;
+#[cfg(feature = "pubsub")]
+pub use pubsub_client::PubsubClient;
+mod quorum_client;
+pub use quorum_client::QuorumClient;
+#[cfg(all(feature = "refresher", not(target_arch = "wasm32")))]
+mod refresher;
+#[cfg(all(feature = "refresher", not(target_arch = "wasm32")))]
+pub use refresher::BeaconRefresher;
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+pub mod service;
+#[cfg(feature = "arkworks")]
+pub mod signer;
+pub mod stream;
+mod strict;
+#[cfg(feature = "tlock")]
+pub mod tlock;
+pub mod transport;
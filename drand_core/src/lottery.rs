@@ -0,0 +1,173 @@
+//! Public drawings of winners among participants, with a transcript anyone can verify.
+//!
+//! Winners are drawn from a beacon emitted after the participant list is closed. The transcript binds the winners to the participants, the round, and the chain.
+//! Given the same participants, a third party retrieves the beacon of that round from any relay, and recomputes the winners with [`Transcript::verify`].
+
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    beacon::{RandomnessBeacon, VerifiedBeacon},
+    chain::ChainInfo,
+    draw::{self, DrawRng},
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Audit evidence of a drawing.
+pub struct Transcript {
+    /// Hash of the chain the beacon is from.
+    #[serde(with = "hex::serde")]
+    pub chain_hash: Vec<u8>,
+    /// Round of the beacon the winners are drawn from.
+    pub round: u64,
+    #[serde(with = "hex::serde")]
+    pub randomness: Vec<u8>,
+    /// Hash of the participant list, in order. See [`inputs_hash`].
+    #[serde(with = "hex::serde")]
+    pub inputs_hash: Vec<u8>,
+    /// Winners, in the order they have been drawn.
+    pub winners: Vec<String>,
+}
+
+impl Transcript {
+    /// Whether the transcript is the drawing of `participants` with `beacon`, and `beacon` verifies against `info`.
+    /// It fails if `beacon` does not verify.
+    pub fn verify(
+        &self,
+        beacon: &RandomnessBeacon,
+        info: &ChainInfo,
+        participants: &[String],
+    ) -> Result<bool> {
+        if beacon.round() != self.round
+            || info.hash() != self.chain_hash
+            || self.winners.len() > participants.len()
+        {
+            return Ok(false);
+        }
+        let transcript = draw_winners(beacon, info, participants, self.winners.len())?;
+        Ok(transcript == *self)
+    }
+}
+
+/// Draw `winners` distinct winners among `participants`, identified by unique strings, using the randomness of `beacon`.
+/// `beacon` is verified against `info` first. Every subset of winners, in every order, is equally likely.
+pub fn draw_winners(
+    beacon: &RandomnessBeacon,
+    info: &ChainInfo,
+    participants: &[String],
+    winners: usize,
+) -> Result<Transcript> {
+    let beacon = VerifiedBeacon::new(beacon.clone(), info.clone())?;
+    if winners > participants.len() {
+        return Err(Error::Other(anyhow!(
+            "cannot draw {winners} winners among {} participants",
+            participants.len()
+        )));
+    }
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = participants.iter().find(|id| !seen.insert(*id)) {
+        return Err(Error::Other(anyhow!(
+            "participant {duplicate} is listed twice"
+        )));
+    }
+
+    let inputs_hash = inputs_hash(participants);
+    // the participant list and number of winners are part of the seed, so that drawings with the same beacon are independent
+    let mut hasher = Sha256::new();
+    hasher.update(beacon.randomness());
+    hasher.update(&inputs_hash);
+    hasher.update((winners as u64).to_be_bytes());
+    let mut rng = DrawRng::from_seed(&hasher.finalize());
+    let winners = draw::sample_k_with(&mut rng, participants.len(), winners)
+        .into_iter()
+        .map(|i| participants[i].clone())
+        .collect();
+
+    Ok(Transcript {
+        chain_hash: info.hash(),
+        round: beacon.round(),
+        randomness: beacon.randomness().to_vec(),
+        inputs_hash,
+        winners,
+    })
+}
+
+/// Hash of the participant list, in order. Each identifier is prefixed by its length in bytes, as a big-endian `u64`.
+pub fn inputs_hash(participants: &[String]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for id in participants {
+        hasher.update((id.len() as u64).to_be_bytes());
+        hasher.update(id.as_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, invalid_beacon, unchained_beacon};
+    use crate::chain::{
+        tests::{chained_chain_info, unchained_chain_info},
+        Chain,
+    };
+
+    use super::*;
+
+    fn participants() -> Vec<String> {
+        (0..100).map(|i| format!("participant-{i}")).collect()
+    }
+
+    #[test]
+    fn lottery_works() {
+        let info = chained_chain_info();
+        let beacon = Chain::new(info.clone()).unwrap().beacon(chained_beacon());
+        let transcript = draw_winners(&beacon, &info, &participants(), 3).unwrap();
+        assert_eq!(transcript.round, 1000000);
+        assert_eq!(transcript.chain_hash, info.hash());
+        assert_eq!(transcript.winners.len(), 3);
+        assert!(transcript
+            .winners
+            .iter()
+            .all(|winner| participants().contains(winner)));
+        assert!(transcript.verify(&beacon, &info, &participants()).unwrap());
+
+        // the transcript survives a round trip through JSON
+        let json = serde_json::to_string(&transcript).unwrap();
+        let parsed: Transcript = serde_json::from_str(&json).unwrap();
+        assert!(parsed.verify(&beacon, &info, &participants()).unwrap());
+
+        // tampered winners, or another participant list, do not verify
+        let mut tampered = transcript.clone();
+        tampered.winners[0] = "participant-ghost".to_string();
+        assert!(!tampered.verify(&beacon, &info, &participants()).unwrap());
+        let mut others = participants();
+        others.pop();
+        assert!(!transcript.verify(&beacon, &info, &others).unwrap());
+
+        // another beacon does not verify
+        let other_info = unchained_chain_info();
+        let other = Chain::new(other_info.clone())
+            .unwrap()
+            .beacon(unchained_beacon());
+        assert!(!transcript
+            .verify(&other, &other_info, &participants())
+            .unwrap());
+    }
+
+    #[test]
+    fn lottery_failure_works() {
+        let info = chained_chain_info();
+        let chain = Chain::new(info.clone()).unwrap();
+        let beacon = chain.beacon(chained_beacon());
+        assert!(draw_winners(&beacon, &info, &participants(), 101).is_err());
+        let duplicates = vec!["alice".to_string(), "alice".to_string()];
+        assert!(draw_winners(&beacon, &info, &duplicates, 1).is_err());
+        assert!(matches!(
+            draw_winners(&chain.beacon(invalid_beacon()), &info, &participants(), 1),
+            Err(Error::InvalidBeacon { .. })
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+//! Hooks to observe client activity.
+//!
+//! A [`MetricsSink`] attached to a client is told about each relay request, retry, and chain info cache lookup. It is the place to export them to Prometheus or StatsD.
+
+use std::time::Duration;
+
+/// Receiver of client events. Every method does nothing by default, so implementations only override the events they record.
+/// Methods are called inline with requests, and should not block.
+pub trait MetricsSink: Send + Sync {
+    /// Request to `path` completed after `latency`. `path` is relative to the relay base URL, such as `info` or `public/latest`.
+    fn request(&self, _path: &str, _latency: Duration, _is_success: bool) {}
+
+    /// Request to `path` is about to be sent again, after a failure or an outdated response.
+    fn retry(&self, _path: &str) {}
+
+    /// Chain info was served from the cache.
+    fn cache_hit(&self) {}
+
+    /// Chain info was not cached, or expired, and has been requested from the relay.
+    fn cache_miss(&self) {}
+}
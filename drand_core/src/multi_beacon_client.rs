@@ -0,0 +1,122 @@
+use anyhow::anyhow;
+
+use crate::{
+    chain::ChainInfo,
+    error::{Error, Result},
+    ApiClient, HttpClient,
+};
+
+/// Client for every chain served by a single relay, such as the default, quicknet, and fastnet chains of `https://api.drand.sh`
+/// Each chain has its own [`HttpClient`], looked up by chain hash or beacon ID. They share the relay transport and its connection pool
+/// Chain info of every chain is retrieved and verified against its hash when the client is created, and stays cached
+pub struct MultiBeaconClient {
+    chains: Vec<(ChainInfo, HttpClient)>,
+}
+
+impl MultiBeaconClient {
+    /// Discover the chains served by the relay at `base_url`, its root such as `https://api.drand.sh`.
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub async fn new(base_url: &str) -> Result<Self> {
+        Self::with_api(ApiClient::new(base_url)?).await
+    }
+
+    /// Discover the chains served by the relay `api` points to.
+    pub async fn with_api(api: ApiClient) -> Result<Self> {
+        let mut chains = vec![];
+        for chain_hash in api.chains().await? {
+            let client = HttpClient::with_chain_api(&api, &chain_hash);
+            chains.push((client.chain_info().await?, client));
+        }
+        if chains.is_empty() {
+            return Err(Error::Other(anyhow!("relay does not serve any chain")));
+        }
+        Ok(Self { chains })
+    }
+
+    /// Info of the chains served by the relay.
+    pub fn chains(&self) -> Vec<ChainInfo> {
+        self.chains.iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// Client for the chain identified by `chain_hash`.
+    pub fn chain(&self, chain_hash: &[u8]) -> Option<&HttpClient> {
+        self.chains
+            .iter()
+            .find(|(info, _)| info.hash() == chain_hash)
+            .map(|(_, client)| client)
+    }
+
+    /// Client for the chain whose beacon ID is `beacon_id`, such as `default` or `quicknet`.
+    pub fn beacon(&self, beacon_id: &str) -> Option<&HttpClient> {
+        self.chains
+            .iter()
+            .find(|(info, _)| info.metadata().beacon_id() == beacon_id)
+            .map(|(_, client)| client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::{chained_chain_info, unchained_chain_info};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn multi_beacon_client_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/chains")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                serde_json::to_string(&[
+                    hex::encode(chained_chain_info().hash()),
+                    hex::encode(unchained_chain_info().hash()),
+                ])
+                .unwrap(),
+            )
+            .create_async()
+            .await;
+        for info in [chained_chain_info(), unchained_chain_info()] {
+            server
+                .mock(
+                    "GET",
+                    format!("/{}/info", hex::encode(info.hash())).as_str(),
+                )
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(serde_json::to_string(&info).unwrap())
+                .create_async()
+                .await;
+        }
+        server
+            .mock(
+                "GET",
+                format!(
+                    "/{}/public/latest",
+                    hex::encode(chained_chain_info().hash())
+                )
+                .as_str(),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        let client = MultiBeaconClient::new(server.url().as_str()).await.unwrap();
+        assert_eq!(
+            client.chains(),
+            vec![chained_chain_info(), unchained_chain_info()]
+        );
+        let chained = client.beacon("default").unwrap();
+        assert_eq!(chained.latest().await.unwrap().beacon(), chained_beacon());
+        let unchained = client.chain(&unchained_chain_info().hash()).unwrap();
+        assert_eq!(
+            unchained.chain_info().await.unwrap(),
+            unchained_chain_info()
+        );
+        assert!(client.beacon("quicknet").is_none());
+    }
+}
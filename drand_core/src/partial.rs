@@ -0,0 +1,501 @@
+//! Partial beacons, signed by a single drand node with its share of the group key.
+//!
+//! A group of `n` nodes holds shares of the chain private key, such that any `t` of them produce a beacon.
+//! Each node signs the round with its share, and broadcasts a partial beacon. Partial signatures are prefixed by the index of the share, as a big-endian `u16`.
+//! They are verified against the share public key, which is the group public polynomial evaluated at `index + 1`. The chain public key is the polynomial evaluated at 0.
+//!
+//! Verifying partials tells which node signs a round late, or with an invalid share. Any `t` valid partials of a round are aggregated into its beacon, with Lagrange interpolation at 0.
+
+use anyhow::anyhow;
+use ark_bls12_381::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    beacon::{ApiBeacon, Message, RandomnessBeacon},
+    bls_signatures::PublicKey,
+    chain::{Chain, ChainInfo},
+    error::{Error, Result},
+    point,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Beacon signed by a single node, as a partial beacon packet of drand.
+pub struct PartialBeacon {
+    round: u64,
+    /// Signature of the previous round, signed over by chained schemes. It is empty for unchained schemes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "hex::serde")]
+    previous_signature: Vec<u8>,
+    /// Share index, as a big-endian `u16`, followed by the signature share.
+    #[serde(with = "hex::serde")]
+    partial_signature: Vec<u8>,
+}
+
+impl PartialBeacon {
+    pub fn new(
+        round: u64,
+        previous_signature: Option<Vec<u8>>,
+        partial_signature: Vec<u8>,
+    ) -> Self {
+        Self {
+            round,
+            previous_signature: previous_signature.unwrap_or_default(),
+            partial_signature,
+        }
+    }
+
+    pub fn round(&self) -> u64 {
+        self.round
+    }
+
+    pub fn previous_signature(&self) -> Option<Vec<u8>> {
+        Some(self.previous_signature.clone()).filter(|signature| !signature.is_empty())
+    }
+
+    /// Index of the share the node signed with. Fails if the partial signature is too short to hold one.
+    pub fn index(&self) -> Result<u16> {
+        match self.partial_signature.as_slice() {
+            [high, low, _, ..] => Ok(u16::from_be_bytes([*high, *low])),
+            _ => Err(Error::Other(anyhow!("partial signature is too short"))),
+        }
+    }
+
+    /// Signature share, without its index.
+    pub fn signature_share(&self) -> Vec<u8> {
+        self.partial_signature.get(2..).unwrap_or_default().to_vec()
+    }
+
+    /// Message the share signs, which is the one of the full beacon.
+    pub(crate) fn message(&self) -> Result<Vec<u8>> {
+        if let Some(previous_signature) = self.previous_signature() {
+            let len = if self.round == 1 { 32 } else { 96 };
+            if previous_signature.len() != len {
+                return Err(Error::Other(anyhow!(
+                    "previous signature of round {} must be {len} bytes",
+                    self.round
+                )));
+            }
+        }
+        self.beacon(vec![]).message()
+    }
+
+    /// Full beacon of this round, carrying `signature`.
+    pub(crate) fn beacon(&self, signature: Vec<u8>) -> ApiBeacon {
+        ApiBeacon::from_signature(self.round, signature, self.previous_signature())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Public polynomial of a drand group, as found in its group file. It commits to the polynomial sharing the chain private key.
+pub struct PublicPolynomial {
+    commits: Commits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Commits {
+    G1(Vec<G1Affine>),
+    G2(Vec<G2Affine>),
+}
+
+impl PublicPolynomial {
+    /// Parse the polynomial coefficients, as compressed points. The first one is the chain public key.
+    /// Coefficients must all be on the same group, which is the group of the chain public key.
+    pub fn new(coefficients: &[Vec<u8>]) -> Result<Self> {
+        let coefficients = coefficients
+            .iter()
+            .map(|coefficient| point::compress(coefficient))
+            .collect::<Result<Vec<_>>>()?;
+        let commits = match coefficients.first().map(Vec::len) {
+            None => {
+                return Err(Error::Other(anyhow!(
+                    "public polynomial has no coefficient"
+                )))
+            }
+            Some(48) => Commits::G1(deserialize_all(&coefficients)?),
+            Some(_) => Commits::G2(deserialize_all(&coefficients)?),
+        };
+        Ok(Self { commits })
+    }
+
+    /// Number of partial beacons needed to produce a beacon, which is the number of coefficients.
+    pub fn threshold(&self) -> usize {
+        match &self.commits {
+            Commits::G1(commits) => commits.len(),
+            Commits::G2(commits) => commits.len(),
+        }
+    }
+
+    /// Chain public key, compressed.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.eval(None)
+    }
+
+    /// Public key of share `index`, compressed.
+    pub fn share_public_key(&self, index: u16) -> Vec<u8> {
+        self.eval(Some(index))
+    }
+
+    /// Verify the signature share of `partial` against the public key of its share.
+    /// Fails if `partial` is malformed.
+    pub fn verify_partial(&self, partial: &PartialBeacon) -> Result<bool> {
+        let public_key = PublicKey::from_compressed(&self.share_public_key(partial.index()?))?;
+        let signature = point::compress(&partial.signature_share())?;
+        Ok(public_key.verify(&signature, &partial.message()?)?)
+    }
+
+    /// Commitment to the polynomial with `coefficients`, on G1 if `is_g1`, and on G2 otherwise.
+    pub(crate) fn commit(coefficients: &[Fr], is_g1: bool) -> Self {
+        let commits = match is_g1 {
+            true => Commits::G1(
+                coefficients
+                    .iter()
+                    .map(|c| (G1Affine::generator() * *c).into_affine())
+                    .collect(),
+            ),
+            false => Commits::G2(
+                coefficients
+                    .iter()
+                    .map(|c| (G2Affine::generator() * *c).into_affine())
+                    .collect(),
+            ),
+        };
+        Self { commits }
+    }
+
+    /// Whether `share` is the evaluation of the committed polynomial for share `index`.
+    pub(crate) fn verify_share(&self, index: u16, share: &Fr) -> bool {
+        let is_g1 = matches!(self.commits, Commits::G1(_));
+        let public_share = Self::commit(&[*share], is_g1);
+        public_share.public_key() == self.share_public_key(index)
+    }
+
+    /// Commitment to the sum of the committed polynomials, each multiplied by its weight.
+    /// Fails if polynomials are on different groups, or of different degrees.
+    pub(crate) fn linear_combination(terms: &[(Fr, &PublicPolynomial)]) -> Result<Self> {
+        let mismatch = || Error::Other(anyhow!("polynomials must have the same group and degree"));
+        let Some((_, first)) = terms.first() else {
+            return Err(Error::Other(anyhow!("no polynomial to combine")));
+        };
+        let commits = match &first.commits {
+            Commits::G1(commits) => {
+                let mut sum = vec![G1Projective::zero(); commits.len()];
+                for (weight, polynomial) in terms {
+                    let Commits::G1(commits) = &polynomial.commits else {
+                        return Err(mismatch());
+                    };
+                    if commits.len() != sum.len() {
+                        return Err(mismatch());
+                    }
+                    for (acc, commit) in sum.iter_mut().zip(commits) {
+                        *acc += *commit * *weight;
+                    }
+                }
+                Commits::G1(G1Projective::normalize_batch(&sum))
+            }
+            Commits::G2(commits) => {
+                let mut sum = vec![G2Projective::zero(); commits.len()];
+                for (weight, polynomial) in terms {
+                    let Commits::G2(commits) = &polynomial.commits else {
+                        return Err(mismatch());
+                    };
+                    if commits.len() != sum.len() {
+                        return Err(mismatch());
+                    }
+                    for (acc, commit) in sum.iter_mut().zip(commits) {
+                        *acc += *commit * *weight;
+                    }
+                }
+                Commits::G2(G2Projective::normalize_batch(&sum))
+            }
+        };
+        Ok(Self { commits })
+    }
+
+    /// Polynomial evaluated at `index + 1`, or at 0 without index.
+    fn eval(&self, index: Option<u16>) -> Vec<u8> {
+        let x = index.map_or(Fr::zero(), |index| Fr::from(index as u64 + 1));
+        let mut bytes = vec![];
+        // Horner's method, from the highest degree coefficient
+        match &self.commits {
+            Commits::G1(commits) => commits
+                .iter()
+                .rev()
+                .fold(G1Projective::zero(), |acc, commit| acc * x + commit)
+                .into_affine()
+                .serialize_compressed(&mut bytes),
+            Commits::G2(commits) => commits
+                .iter()
+                .rev()
+                .fold(G2Projective::zero(), |acc, commit| acc * x + commit)
+                .into_affine()
+                .serialize_compressed(&mut bytes),
+        }
+        .expect("points serialize in memory");
+        bytes
+    }
+}
+
+/// Aggregate partial beacons of a round into the beacon of the chain described by `info`, whose group has `polynomial` as public polynomial.
+/// Invalid partials, and partials of a share already counted, are skipped. Fails if fewer than [`PublicPolynomial::threshold`] valid partials remain,
+/// or if partials are of different rounds. The aggregated beacon is verified against `info`.
+pub fn aggregate(
+    partials: &[PartialBeacon],
+    polynomial: &PublicPolynomial,
+    info: &ChainInfo,
+) -> Result<RandomnessBeacon> {
+    let Some(first) = partials.first() else {
+        return Err(Error::Other(anyhow!("no partial beacon to aggregate")));
+    };
+    if partials.iter().any(|partial| {
+        partial.round != first.round || partial.previous_signature != first.previous_signature
+    }) {
+        return Err(Error::Other(anyhow!(
+            "partial beacons must all be of round {}",
+            first.round
+        )));
+    }
+    if polynomial.public_key() != point::compress(&info.public_key())? {
+        return Err(Error::Other(anyhow!(
+            "public polynomial is not the one of the chain"
+        )));
+    }
+
+    let threshold = polynomial.threshold();
+    let mut valid: Vec<(u16, Vec<u8>)> = Vec::with_capacity(threshold);
+    for partial in partials {
+        if valid.len() == threshold {
+            break;
+        }
+        let Ok(index) = partial.index() else {
+            continue;
+        };
+        if valid.iter().any(|(counted, _)| *counted == index) {
+            continue;
+        }
+        if let Ok(true) = polynomial.verify_partial(partial) {
+            valid.push((index, point::compress(&partial.signature_share())?));
+        }
+    }
+    if valid.len() < threshold {
+        return Err(Error::Other(anyhow!(
+            "{} valid partial beacons of round {}, {threshold} are needed",
+            valid.len(),
+            first.round
+        )));
+    }
+
+    let indices: Vec<u16> = valid.iter().map(|(index, _)| *index).collect();
+    let mut signature = vec![];
+    match polynomial.commits {
+        // signatures are on the group opposite to the public key
+        Commits::G1(_) => interpolate::<G2Affine>(&indices, &valid)?
+            .into_affine()
+            .serialize_compressed(&mut signature),
+        Commits::G2(_) => interpolate::<G1Affine>(&indices, &valid)?
+            .into_affine()
+            .serialize_compressed(&mut signature),
+    }
+    .expect("points serialize in memory");
+
+    let chain = Chain::new(info.clone())?;
+    let beacon = chain.beacon(first.beacon(signature));
+    chain.verify_detailed(&beacon)?;
+    Ok(beacon)
+}
+
+/// Signature shares combined with their Lagrange coefficient at 0, where share `index` is the evaluation at `index + 1`.
+fn interpolate<P: AffineRepr<ScalarField = Fr>>(
+    indices: &[u16],
+    shares: &[(u16, Vec<u8>)],
+) -> Result<P::Group> {
+    let mut sum = P::Group::zero();
+    for (i, (_, share)) in shares.iter().enumerate() {
+        let share = P::deserialize_compressed(share.as_slice())
+            .map_err(|_| Error::Other(anyhow!("invalid signature share")))?;
+        sum += share * lagrange_at_zero(indices, i);
+    }
+    Ok(sum)
+}
+
+/// Lagrange coefficient at 0 of the `i`-th of `indices`, which are distinct. Share `index` is the evaluation at `index + 1`.
+pub(crate) fn lagrange_at_zero(indices: &[u16], i: usize) -> Fr {
+    let xs: Vec<Fr> = indices
+        .iter()
+        .map(|&index| Fr::from(index as u64 + 1))
+        .collect();
+    xs.iter()
+        .enumerate()
+        .filter(|(j, _)| *j != i)
+        .fold(Fr::from(1u64), |acc, (_, x_j)| {
+            acc * x_j
+                * (*x_j - xs[i])
+                    .inverse()
+                    .expect("share indices are distinct")
+        })
+}
+
+fn deserialize_all<P: CanonicalDeserialize>(points: &[Vec<u8>]) -> Result<Vec<P>> {
+    if points.windows(2).any(|pair| pair[0].len() != pair[1].len()) {
+        return Err(Error::Other(anyhow!(
+            "coefficients must be on the same group"
+        )));
+    }
+    points
+        .iter()
+        .map(|point| {
+            P::deserialize_compressed(point.as_slice())
+                .map_err(|_| Error::Other(anyhow!("invalid coefficient")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rand::Rng;
+
+    use crate::beacon::tests::chained_beacon;
+    use crate::bls_signatures::hash_to_g2;
+    use crate::chain::tests::{rehash, unchained_chain_info};
+
+    use super::*;
+
+    /// Group of `n` nodes with a threshold of `t`, with public keys on G1. It returns the public polynomial, and the private share of each node.
+    pub fn dealer(t: usize, n: usize) -> (PublicPolynomial, Vec<Fr>) {
+        let mut rng = rand::thread_rng();
+        let coefficients: Vec<Fr> = (0..t).map(|_| Fr::from(rng.gen::<u128>())).collect();
+        let shares = (1..=n as u64)
+            .map(|x| {
+                coefficients
+                    .iter()
+                    .rev()
+                    .fold(Fr::zero(), |acc, c| acc * Fr::from(x) + c)
+            })
+            .collect();
+        let commits: Vec<Vec<u8>> = coefficients
+            .iter()
+            .map(|c| {
+                let mut bytes = vec![];
+                (G1Affine::generator() * *c)
+                    .into_affine()
+                    .serialize_compressed(&mut bytes)
+                    .unwrap();
+                bytes
+            })
+            .collect();
+        (PublicPolynomial::new(&commits).unwrap(), shares)
+    }
+
+    /// Partial beacon of `round`, signed by the node holding share `index`.
+    pub fn sign(
+        share: &Fr,
+        index: u16,
+        round: u64,
+        previous_signature: Option<Vec<u8>>,
+    ) -> PartialBeacon {
+        let unsigned = PartialBeacon::new(round, previous_signature, vec![]);
+        let mut signature = index.to_be_bytes().to_vec();
+        (hash_to_g2(&unsigned.message().unwrap()).unwrap() * *share)
+            .into_affine()
+            .serialize_compressed(&mut signature)
+            .unwrap();
+        PartialBeacon {
+            partial_signature: signature,
+            ..unsigned
+        }
+    }
+
+    #[test]
+    fn partial_beacon_works() {
+        let (polynomial, shares) = dealer(3, 5);
+        assert_eq!(polynomial.threshold(), 3);
+        assert_eq!(polynomial.public_key().len(), 48);
+
+        let previous = chained_beacon().signature().to_vec();
+        for (index, share) in shares.iter().enumerate() {
+            let partial = sign(share, index as u16, 1000001, Some(previous.clone()));
+            assert_eq!(partial.index().unwrap(), index as u16);
+            assert!(polynomial.verify_partial(&partial).unwrap());
+
+            // the same share claimed by another node does not verify
+            let mut misattributed = partial.clone();
+            misattributed.partial_signature[1] = (index as u8 + 1) % 5;
+            assert!(!polynomial.verify_partial(&misattributed).unwrap());
+        }
+
+        let unchained = sign(&shares[0], 0, 1000000, None);
+        assert!(polynomial.verify_partial(&unchained).unwrap());
+        let json = serde_json::to_string(&unchained).unwrap();
+        assert!(!json.contains("previous_signature"));
+        assert_eq!(
+            serde_json::from_str::<PartialBeacon>(&json).unwrap(),
+            unchained
+        );
+
+        // a share signing another round does not verify
+        let mut wrong_round = unchained.clone();
+        wrong_round.round += 1;
+        assert!(!polynomial.verify_partial(&wrong_round).unwrap());
+        assert!(PartialBeacon::new(1, None, vec![0, 1]).index().is_err());
+    }
+
+    #[test]
+    fn aggregate_works() {
+        let (polynomial, shares) = dealer(3, 5);
+        let mut info = serde_json::to_value(unchained_chain_info()).unwrap();
+        info["public_key"] = hex::encode(polynomial.public_key()).into();
+        rehash(&mut info);
+        let info: ChainInfo = serde_json::from_value(info).unwrap();
+        let partials: Vec<PartialBeacon> = shares
+            .iter()
+            .enumerate()
+            .map(|(index, share)| sign(share, index as u16, 1000000, None))
+            .collect();
+
+        let beacon = aggregate(&partials[1..4], &polynomial, &info).unwrap();
+        assert_eq!(beacon.round(), 1000000);
+        assert!(beacon.verify(info.clone()).unwrap());
+        // any threshold of shares produces the same beacon
+        let others = [
+            partials[4].clone(),
+            partials[0].clone(),
+            partials[2].clone(),
+        ];
+        let same = aggregate(&others, &polynomial, &info).unwrap();
+        assert_eq!(same.signature(), beacon.signature());
+        assert_eq!(same.randomness(), beacon.randomness());
+
+        // invalid and duplicate partials are skipped
+        let forged = sign(&shares[0], 0, 1000001, None);
+        let forged = PartialBeacon::new(1000000, None, forged.partial_signature);
+        let noisy = [
+            forged.clone(),
+            partials[1].clone(),
+            partials[1].clone(),
+            partials[3].clone(),
+            partials[4].clone(),
+        ];
+        let recovered = aggregate(&noisy, &polynomial, &info).unwrap();
+        assert_eq!(recovered.signature(), beacon.signature());
+        assert!(aggregate(
+            &[forged, partials[1].clone(), partials[1].clone()],
+            &polynomial,
+            &info
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn aggregate_failure_works() {
+        let (polynomial, shares) = dealer(2, 3);
+        let partials = [
+            sign(&shares[0], 0, 1000000, None),
+            sign(&shares[1], 1, 1000001, None),
+        ];
+        // partials of different rounds, or of another chain, are not aggregated
+        assert!(aggregate(&partials, &polynomial, &unchained_chain_info()).is_err());
+        assert!(aggregate(&[], &polynomial, &unchained_chain_info()).is_err());
+        assert!(aggregate(&partials[..1], &polynomial, &unchained_chain_info()).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! Encoding of BLS12-381 points, as found in chain public keys and beacon signatures.
+//!
+//! drand serves compressed points: 48 bytes on G1, and 96 bytes on G2. Some tooling emits uncompressed points instead, which are twice as long.
+//! These helpers normalize them into the compressed form used by the rest of the crate.
+
+#[cfg(not(feature = "blst"))]
+use crate::bls_signatures::compress_point;
+#[cfg(feature = "blst")]
+use crate::blst_signatures::compress_point;
+use crate::error::Result;
+
+/// Flag set on the first byte of compressed points.
+const COMPRESSION_FLAG: u8 = 0x80;
+
+/// Whether `point` is encoded in compressed form. It does not check the point is valid.
+pub fn is_compressed(point: &[u8]) -> bool {
+    point
+        .first()
+        .map_or(false, |byte| byte & COMPRESSION_FLAG != 0)
+}
+
+/// Length of `point` once compressed, in bytes.
+pub fn compressed_len(point: &[u8]) -> usize {
+    if is_compressed(point) {
+        point.len()
+    } else {
+        point.len() / 2
+    }
+}
+
+/// Validate `point`, compressed or not, and compress it. The group is determined by its length.
+/// Fails if the point is not on the curve, not in the prime order subgroup, or at infinity.
+pub fn compress(point: &[u8]) -> Result<Vec<u8>> {
+    Ok(compress_point(point)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, unchained_beacon_on_g1};
+    use crate::chain::tests::{chained_chain_info, unchained_chain_on_g1_info};
+
+    use super::*;
+
+    #[cfg(feature = "arkworks")]
+    fn uncompress(point: &[u8]) -> Vec<u8> {
+        use ark_bls12_381::{G1Affine, G2Affine};
+        use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+        let mut uncompressed = vec![];
+        if point.len() == 48 {
+            G1Affine::deserialize_compressed(point)
+                .unwrap()
+                .serialize_uncompressed(&mut uncompressed)
+                .unwrap();
+        } else {
+            G2Affine::deserialize_compressed(point)
+                .unwrap()
+                .serialize_uncompressed(&mut uncompressed)
+                .unwrap();
+        }
+        uncompressed
+    }
+
+    #[test]
+    fn point_compress_works() {
+        for point in [
+            chained_chain_info().public_key(),
+            chained_beacon().signature().to_vec(),
+            unchained_chain_on_g1_info().public_key(),
+            unchained_beacon_on_g1().signature().to_vec(),
+        ] {
+            assert!(is_compressed(&point));
+            assert_eq!(compressed_len(&point), point.len());
+            assert_eq!(compress(&point).unwrap(), point);
+        }
+
+        assert!(compress(&[]).is_err());
+        assert!(compress(&[0x80; 48]).is_err());
+        assert!(compress(&[0; 96]).is_err());
+    }
+
+    #[cfg(feature = "arkworks")]
+    #[test]
+    fn point_compress_uncompressed_works() {
+        use crate::beacon::ApiBeacon;
+        use crate::chain::Chain;
+
+        for point in [
+            chained_chain_info().public_key(),
+            chained_beacon().signature().to_vec(),
+            unchained_chain_on_g1_info().public_key(),
+            unchained_beacon_on_g1().signature().to_vec(),
+        ] {
+            let uncompressed = uncompress(&point);
+            assert!(!is_compressed(&uncompressed));
+            assert_eq!(compressed_len(&uncompressed), point.len());
+            assert_eq!(compress(&uncompressed).unwrap(), point);
+
+            // a coordinate off the curve
+            let mut invalid = uncompressed;
+            let last = invalid.len() - 1;
+            invalid[last] ^= 1;
+            assert!(compress(&invalid).is_err());
+        }
+
+        // chains and beacons using uncompressed points verify
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        info["public_key"] = hex::encode(uncompress(&chained_chain_info().public_key())).into();
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["signature"] = hex::encode(uncompress(chained_beacon().signature())).into();
+        let chain = Chain::new(serde_json::from_value(info).unwrap()).unwrap();
+        let uncompressed = chain.beacon(serde_json::from_value(beacon.clone()).unwrap());
+        assert!(chain.verify(&uncompressed).unwrap());
+
+        // so do chained beacons whose previous signature is uncompressed, which is signed over compressed
+        let previous_signature = chained_beacon().previous_signature().unwrap().to_vec();
+        beacon["previous_signature"] = hex::encode(uncompress(&previous_signature)).into();
+        let beacon = chain.beacon(serde_json::from_value(beacon).unwrap());
+        assert!(chain.verify(&beacon).unwrap());
+        assert!(chain.verify_batch(&[beacon.clone()]).unwrap());
+        assert!(chain.verify_chain(&[beacon.clone()]).unwrap());
+
+        // and link to the previous beacon, whichever form either signature is served in
+        let previous = chain.beacon(ApiBeacon::from_signature(
+            999999,
+            uncompress(&previous_signature),
+            Some(previous_signature),
+        ));
+        assert!(chain.is_link(&previous, &beacon));
+        assert!(chain.is_link(&previous, &chain.beacon(chained_beacon())));
+        let other = chain.beacon(ApiBeacon::from_signature(
+            999999,
+            chained_beacon().signature().to_vec(),
+            Some(chained_beacon().previous_signature().unwrap().to_vec()),
+        ));
+        assert!(!chain.is_link(&other, &beacon));
+    }
+}
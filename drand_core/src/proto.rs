@@ -0,0 +1,245 @@
+//! Messages of the drand protocol, as defined in `drand/protobuf/drand/api.proto` and `common.proto`.
+//!
+//! These are shared by transports which do not speak the HTTP API, and convert from and to the crate beacon and chain types, to interoperate with gRPC relays and Go tooling.
+//! Only fields used by the client are decoded.
+
+use crate::{
+    beacon::{ApiBeacon, RandomnessBeacon},
+    chain::ChainInfo,
+    error::{Error, Result},
+};
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Metadata {
+    #[prost(string, tag = "2")]
+    pub beacon_id: String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub chain_hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PublicRandRequest {
+    #[prost(uint64, tag = "1")]
+    pub round: u64,
+    #[prost(message, optional, tag = "2")]
+    pub metadata: Option<Metadata>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PublicRandResponse {
+    #[prost(uint64, tag = "1")]
+    pub round: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub previous_signature: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub randomness: Vec<u8>,
+    #[prost(message, optional, tag = "5")]
+    pub metadata: Option<Metadata>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChainInfoRequest {
+    #[prost(message, optional, tag = "1")]
+    pub metadata: Option<Metadata>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ChainInfoPacket {
+    #[prost(bytes = "vec", tag = "1")]
+    pub public_key: Vec<u8>,
+    #[prost(int64, tag = "2")]
+    pub period: i64,
+    #[prost(int64, tag = "3")]
+    pub genesis_time: i64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "5")]
+    pub group_hash: Vec<u8>,
+    #[prost(string, tag = "6")]
+    pub scheme_id: String,
+    #[prost(message, optional, tag = "7")]
+    pub metadata: Option<Metadata>,
+}
+
+/// Beacon in the HTTP API format.
+pub(crate) fn beacon_json(response: &PublicRandResponse) -> serde_json::Value {
+    // Unchained beacons have no previous signature, and are told apart by its absence.
+    if response.previous_signature.is_empty() {
+        serde_json::json!({
+            "round": response.round,
+            "randomness": hex::encode(&response.randomness),
+            "signature": hex::encode(&response.signature),
+        })
+    } else {
+        serde_json::json!({
+            "round": response.round,
+            "randomness": hex::encode(&response.randomness),
+            "signature": hex::encode(&response.signature),
+            "previous_signature": hex::encode(&response.previous_signature),
+        })
+    }
+}
+
+/// Chain info in the HTTP API format.
+pub(crate) fn chain_info_json(packet: &ChainInfoPacket) -> serde_json::Value {
+    serde_json::json!({
+        "public_key": hex::encode(&packet.public_key),
+        "period": packet.period,
+        "genesis_time": packet.genesis_time,
+        "hash": hex::encode(&packet.hash),
+        "groupHash": hex::encode(&packet.group_hash),
+        "schemeID": packet.scheme_id,
+        "metadata": {
+            "beaconID": packet.metadata.clone().unwrap_or_default().beacon_id,
+        },
+    })
+}
+
+impl TryFrom<&PublicRandResponse> for ApiBeacon {
+    type Error = Error;
+
+    fn try_from(response: &PublicRandResponse) -> Result<Self> {
+        Ok(serde_json::from_value(beacon_json(response)).map_err(anyhow::Error::from)?)
+    }
+}
+
+impl TryFrom<PublicRandResponse> for ApiBeacon {
+    type Error = Error;
+
+    fn try_from(response: PublicRandResponse) -> Result<Self> {
+        Self::try_from(&response)
+    }
+}
+
+impl From<&ApiBeacon> for PublicRandResponse {
+    fn from(beacon: &ApiBeacon) -> Self {
+        Self {
+            round: beacon.round(),
+            signature: beacon.signature().to_vec(),
+            previous_signature: beacon.previous_signature().unwrap_or_default().to_vec(),
+            randomness: beacon.randomness().to_vec(),
+            metadata: None,
+        }
+    }
+}
+
+impl From<&RandomnessBeacon> for PublicRandResponse {
+    fn from(beacon: &RandomnessBeacon) -> Self {
+        Self::from(&beacon.beacon)
+    }
+}
+
+impl TryFrom<&ChainInfoPacket> for ChainInfo {
+    type Error = Error;
+
+    /// Fails if the period or genesis time is negative.
+    fn try_from(packet: &ChainInfoPacket) -> Result<Self> {
+        Ok(serde_json::from_value(chain_info_json(packet)).map_err(anyhow::Error::from)?)
+    }
+}
+
+impl TryFrom<ChainInfoPacket> for ChainInfo {
+    type Error = Error;
+
+    fn try_from(packet: ChainInfoPacket) -> Result<Self> {
+        Self::try_from(&packet)
+    }
+}
+
+impl From<&ChainInfo> for ChainInfoPacket {
+    /// Periods and genesis times beyond `i64::MAX` are saturated. drand cannot hash them either.
+    fn from(info: &ChainInfo) -> Self {
+        Self {
+            public_key: info.public_key(),
+            period: i64::try_from(info.period()).unwrap_or(i64::MAX),
+            genesis_time: i64::try_from(info.genesis_time()).unwrap_or(i64::MAX),
+            hash: info.hash(),
+            group_hash: info.group_hash(),
+            scheme_id: info.scheme_id(),
+            metadata: Some(Metadata {
+                beacon_id: info.beacon_id(),
+                chain_hash: info.hash(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, unchained_beacon};
+    use crate::chain::tests::chained_chain_info;
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn beacon_json_works() {
+        let beacon = unchained_beacon();
+        let response = PublicRandResponse {
+            round: beacon.round(),
+            signature: beacon.signature().to_vec(),
+            previous_signature: vec![],
+            randomness: beacon.randomness().to_vec(),
+            metadata: None,
+        };
+
+        let converted: ApiBeacon = serde_json::from_value(beacon_json(&response)).unwrap();
+        assert_eq!(converted, beacon);
+
+        // chained beacons are parsed back with their previous signature
+        let beacon = chained_beacon();
+        let value = serde_json::to_value(&beacon).unwrap();
+        let response = PublicRandResponse {
+            round: beacon.round(),
+            signature: beacon.signature().to_vec(),
+            previous_signature: hex::decode(value["previous_signature"].as_str().unwrap()).unwrap(),
+            randomness: beacon.randomness().to_vec(),
+            metadata: None,
+        };
+
+        let converted: ApiBeacon = serde_json::from_value(beacon_json(&response)).unwrap();
+        assert_eq!(converted, beacon);
+    }
+
+    #[test]
+    fn chain_info_json_works() {
+        let info = chained_chain_info();
+        let packet = ChainInfoPacket {
+            public_key: info.public_key(),
+            period: info.period() as i64,
+            genesis_time: info.genesis_time() as i64,
+            hash: info.hash(),
+            group_hash: info.group_hash(),
+            scheme_id: info.scheme_id(),
+            metadata: Some(Metadata {
+                beacon_id: info.metadata().beacon_id(),
+                chain_hash: info.hash(),
+            }),
+        };
+
+        let converted: ChainInfo = serde_json::from_value(chain_info_json(&packet)).unwrap();
+        assert_eq!(converted, info);
+    }
+
+    #[test]
+    fn proto_conversion_works() {
+        for beacon in [chained_beacon(), unchained_beacon()] {
+            let response = PublicRandResponse::from(&beacon);
+            assert_eq!(ApiBeacon::try_from(&response).unwrap(), beacon);
+            // the wire encoding round trips
+            let decoded = PublicRandResponse::decode(response.encode_to_vec().as_slice()).unwrap();
+            assert_eq!(ApiBeacon::try_from(decoded).unwrap(), beacon);
+        }
+
+        let info = chained_chain_info();
+        let packet = ChainInfoPacket::from(&info);
+        assert_eq!(packet.metadata.clone().unwrap().chain_hash, info.hash());
+        assert_eq!(ChainInfo::try_from(&packet).unwrap(), info);
+
+        let mut invalid = packet;
+        invalid.period = -1;
+        assert!(ChainInfo::try_from(invalid).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! libp2p gossipsub client for drand.
+//!
+//! drand nodes and relays publish each beacon on a gossipsub topic dedicated to their chain. Subscribing to it yields beacons as soon as they are emitted, without polling.
+
+use anyhow::anyhow;
+use futures::{Stream, StreamExt};
+use libp2p::{
+    gossipsub::{self, IdentTopic, MessageAuthenticity, ValidationMode},
+    identity,
+    swarm::{SwarmBuilder, SwarmEvent},
+    Multiaddr, PeerId,
+};
+use prost::Message;
+
+use crate::{
+    beacon::{ApiBeacon, RandomnessBeacon},
+    chain::{Chain, ChainInfo, ChainOptions},
+    error::{Error, Result},
+    proto,
+};
+
+/// Gossipsub topic on which beacons of the chain identified by `chain_hash` are published.
+pub fn topic(chain_hash: &[u8]) -> String {
+    format!("/drand/pubsub/v0.0.0/{}", hex::encode(chain_hash))
+}
+
+/// Pubsub Client for drand
+/// Joins the gossipsub topic of `info` chain through `peers`, and yields beacons as they are published
+/// Chain info is not exchanged over gossipsub, and has to be provided. It is validated against `options`
+pub struct PubsubClient {
+    chain: Chain,
+    options: ChainOptions,
+    peers: Vec<Multiaddr>,
+}
+
+impl PubsubClient {
+    /// `peers` are multiaddresses of relays bootstrapping the subscription, such as `/dns4/pl-us.testnet.drand.sh/tcp/44544`.
+    pub fn new(info: ChainInfo, peers: &[&str], options: Option<ChainOptions>) -> Result<Self> {
+        let options = options.unwrap_or_default();
+        if !options.verify(&info) {
+            return Err(Error::InvalidChainInfo);
+        }
+        if peers.is_empty() {
+            return Err(Error::Other(anyhow!("at least one peer is required")));
+        }
+        let peers = peers
+            .iter()
+            .map(|peer| {
+                peer.parse::<Multiaddr>()
+                    .map_err(|e| Error::Other(anyhow!("{peer}: {e}")))
+            })
+            .collect::<Result<Vec<Multiaddr>>>()?;
+        Ok(Self {
+            chain: Chain::new(info)?,
+            options,
+            peers,
+        })
+    }
+
+    pub fn options(&self) -> ChainOptions {
+        self.options.clone()
+    }
+
+    pub fn chain_info(&self) -> ChainInfo {
+        self.chain.info()
+    }
+
+    pub fn peers(&self) -> &[Multiaddr] {
+        &self.peers
+    }
+
+    /// Beacons published on the chain topic from now on.
+    /// Each beacon is verified against the chain, unless beacon verification is disabled. Messages which are not beacons are skipped.
+    /// The subscription is driven by the returned stream, and requires a tokio runtime.
+    pub fn subscribe(&self) -> Result<impl Stream<Item = Result<RandomnessBeacon>> + Send> {
+        let keypair = identity::Keypair::generate_ed25519();
+        let peer_id = PeerId::from(keypair.public());
+        let transport =
+            libp2p::tokio_development_transport(keypair).map_err(anyhow::Error::from)?;
+
+        // Beacons are authenticated by their BLS signature. Gossipsub signatures add nothing on top of it.
+        let config = gossipsub::ConfigBuilder::default()
+            .validation_mode(ValidationMode::Permissive)
+            .build()
+            .map_err(|e| Error::Other(anyhow!(e)))?;
+        let mut behaviour = gossipsub::Behaviour::new(MessageAuthenticity::Anonymous, config)
+            .map_err(|e| Error::Other(anyhow!(e)))?;
+        behaviour
+            .subscribe(&IdentTopic::new(topic(&self.chain.info().hash())))
+            .map_err(anyhow::Error::from)?;
+
+        let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build();
+        for peer in &self.peers {
+            swarm.dial(peer.clone()).map_err(anyhow::Error::from)?;
+        }
+
+        let chain = self.chain.clone();
+        let is_beacon_verification = self.options.is_beacon_verification();
+        let messages = swarm.filter_map(|event| async move {
+            match event {
+                SwarmEvent::Behaviour(gossipsub::Event::Message { message, .. }) => {
+                    Some(message.data)
+                }
+                _ => None,
+            }
+        });
+
+        Ok(messages.map(move |data| {
+            let response =
+                proto::PublicRandResponse::decode(data.as_slice()).map_err(anyhow::Error::from)?;
+            let beacon = ApiBeacon::try_from(&response)?;
+            let beacon = chain.beacon(beacon);
+            if is_beacon_verification {
+                chain.verify_detailed(&beacon)?;
+            }
+            Ok(beacon)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chain::{tests::chained_chain_info, ChainVerification};
+
+    use super::*;
+
+    #[test]
+    fn topic_works() {
+        assert_eq!(
+            topic(&chained_chain_info().hash()),
+            "/drand/pubsub/v0.0.0/8990e7a9aaed2ffed73dbd7092123d6f289930540d7651336225dc172e51b2ce"
+        );
+    }
+
+    #[test]
+    fn client_rejects_invalid_config() {
+        assert!(PubsubClient::new(chained_chain_info(), &[], None).is_err());
+        assert!(PubsubClient::new(chained_chain_info(), &["not a multiaddr"], None).is_err());
+        assert!(PubsubClient::new(
+            chained_chain_info(),
+            &["/ip4/127.0.0.1/tcp/44544"],
+            Some(ChainOptions::new(
+                true,
+                true,
+                Some(ChainVerification::new(Some(vec![0; 32]), None))
+            ))
+        )
+        .is_err());
+        assert!(
+            PubsubClient::new(chained_chain_info(), &["/ip4/127.0.0.1/tcp/44544"], None).is_ok()
+        );
+    }
+}
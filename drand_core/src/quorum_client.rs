@@ -0,0 +1,192 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::anyhow;
+
+#[cfg(any(feature = "reqwest", feature = "hyper"))]
+use crate::chain::ChainOptions;
+use crate::{
+    beacon::RandomnessBeacon,
+    circuit_breaker::CircuitBreaker,
+    error::{Error, Result},
+    HttpClient,
+};
+
+/// Client requiring multiple relays to agree on a beacon
+/// Each request is sent to all relays. A beacon is returned only if at least `threshold` relays return the same round and signature
+/// This protects against a single compromised or stale relay, including when beacon verification is disabled
+pub struct QuorumClient {
+    clients: Vec<HttpClient>,
+    threshold: usize,
+    breakers: Option<Vec<CircuitBreaker>>,
+}
+
+impl QuorumClient {
+    #[cfg(any(feature = "reqwest", feature = "hyper"))]
+    pub fn new(
+        base_urls: &[&str],
+        threshold: usize,
+        options: Option<ChainOptions>,
+    ) -> Result<Self> {
+        let clients = base_urls
+            .iter()
+            .map(|base_url| HttpClient::new(base_url, options.clone()))
+            .collect::<Result<Vec<HttpClient>>>()?;
+        Self::from_clients(clients, threshold)
+    }
+
+    /// Require agreement between already configured clients.
+    pub fn from_clients(clients: Vec<HttpClient>, threshold: usize) -> Result<Self> {
+        if threshold == 0 || threshold > clients.len() {
+            return Err(Error::Other(anyhow!(
+                "threshold must be between 1 and the number of relays ({})",
+                clients.len()
+            )));
+        }
+        Ok(Self {
+            clients,
+            threshold,
+            breakers: None,
+        })
+    }
+
+    /// Stop sending requests to a relay after `failure_threshold` consecutive failures. It then counts as a relay which does not agree.
+    /// Once `cooldown` has elapsed, a single request probes the relay, which is requested again if it succeeds.
+    /// This relies on [`std::time::Instant`], which is not available on wasm32-unknown-unknown.
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.breakers = Some(
+            self.clients
+                .iter()
+                .map(|_| CircuitBreaker::new(failure_threshold, cooldown))
+                .collect(),
+        );
+        self
+    }
+
+    pub fn clients(&self) -> &[HttpClient] {
+        &self.clients
+    }
+
+    /// Number of relays which have to agree on a beacon.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Latest beacon agreed upon by relays.
+    /// Relays serving different rounds, because they are not in sync, do not agree.
+    pub async fn latest(&self) -> Result<RandomnessBeacon> {
+        let requests = self
+            .clients
+            .iter()
+            .enumerate()
+            .map(|(i, client)| async move {
+                match self.breakers.as_ref().map(|breakers| &breakers[i]) {
+                    Some(breaker) if !breaker.allow() => {
+                        Err(Error::Other(anyhow!("relay is failing")))
+                    }
+                    Some(breaker) => breaker.guard(client.latest()).await,
+                    None => client.latest().await,
+                }
+            });
+        self.quorum(futures::future::join_all(requests).await)
+    }
+
+    pub async fn get(&self, round_number: u64) -> Result<RandomnessBeacon> {
+        let requests = self
+            .clients
+            .iter()
+            .enumerate()
+            .map(|(i, client)| async move {
+                match self.breakers.as_ref().map(|breakers| &breakers[i]) {
+                    Some(breaker) if !breaker.allow() => {
+                        Err(Error::Other(anyhow!("relay is failing")))
+                    }
+                    Some(breaker) => breaker.guard(client.get(round_number)).await,
+                    None => client.get(round_number).await,
+                }
+            });
+        self.quorum(futures::future::join_all(requests).await)
+    }
+
+    fn quorum(&self, responses: Vec<Result<RandomnessBeacon>>) -> Result<RandomnessBeacon> {
+        let mut votes: HashMap<(u64, Vec<u8>), Vec<RandomnessBeacon>> = HashMap::new();
+        for beacon in responses.into_iter().flatten() {
+            votes
+                .entry((beacon.round(), beacon.signature().to_vec()))
+                .or_default()
+                .push(beacon);
+        }
+
+        let most_agreed = votes.into_values().max_by_key(|beacons| beacons.len());
+        match most_agreed {
+            Some(mut beacons) if beacons.len() >= self.threshold => Ok(beacons.remove(0)),
+            Some(beacons) => Err(Error::Other(anyhow!(
+                "no quorum: at most {} of {} relays agree, {} required",
+                beacons.len(),
+                self.clients.len(),
+                self.threshold
+            ))),
+            None => Err(Error::Other(anyhow!(
+                "no quorum: no relay returned a beacon"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, invalid_beacon};
+    use crate::beacon::ApiBeacon;
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    async fn relay(beacon: &ApiBeacon) -> mockito::ServerGuard {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", mockito::Matcher::Regex(r"^/public/.*$".to_string()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(beacon).unwrap())
+            .create_async()
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn quorum_client_works() {
+        let valid_server_1 = relay(&chained_beacon()).await;
+        let valid_server_2 = relay(&chained_beacon()).await;
+        let invalid_server = relay(&invalid_beacon()).await;
+        let base_urls = [
+            valid_server_1.url(),
+            invalid_server.url(),
+            valid_server_2.url(),
+        ];
+        let base_urls: Vec<&str> = base_urls.iter().map(|url| url.as_str()).collect();
+        // beacon verification is disabled, agreement is the only protection
+        let options = Some(ChainOptions::new(false, true, None));
+
+        let client = QuorumClient::new(&base_urls, 2, options.clone()).unwrap();
+        let beacon = match client.get(1000000).await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(beacon.beacon(), chained_beacon());
+
+        let client = QuorumClient::new(&base_urls, 3, options.clone()).unwrap();
+        match client.latest().await {
+            Ok(_beacon) => panic!("Relays should not agree"),
+            Err(_err) => (),
+        }
+
+        assert!(QuorumClient::new(&base_urls, 0, options.clone()).is_err());
+        assert!(QuorumClient::new(&base_urls, 4, options).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Latest beacon kept up to date in the background.
+//!
+//! Request handlers reading the latest beacon would otherwise each wait on a relay round trip, for a value which only changes once per period.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use tokio::task::JoinHandle;
+
+use crate::{beacon::RandomnessBeacon, error::Result, HttpClient};
+
+/// Delay before watching rounds again, after the watch failed.
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Handle to a background task retrieving every round as it is emitted.
+/// Beacons are verified by the client before they become current. The task stops when the handle is dropped.
+pub struct BeaconRefresher {
+    current: Arc<RwLock<RandomnessBeacon>>,
+    task: JoinHandle<()>,
+}
+
+impl BeaconRefresher {
+    /// Retrieve the latest beacon with `client`, then spawn a task keeping it current on the tokio runtime.
+    /// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+    pub async fn spawn(client: HttpClient) -> Result<Self> {
+        let current = Arc::new(RwLock::new(client.latest().await?));
+        let task = tokio::spawn(refresh(client, current.clone()));
+        Ok(Self { current, task })
+    }
+
+    /// Latest beacon retrieved, without waiting on the relay.
+    /// If the relay cannot be reached, it is the last beacon retrieved, and its round can lag behind.
+    pub fn current(&self) -> RandomnessBeacon {
+        self.current.read().unwrap().clone()
+    }
+}
+
+impl Drop for BeaconRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn refresh(client: HttpClient, current: Arc<RwLock<RandomnessBeacon>>) {
+    loop {
+        if let Ok(rounds) = client.watch().await {
+            futures::pin_mut!(rounds);
+            while let Some(beacon) = rounds.next().await {
+                match beacon {
+                    Ok(beacon) => *current.write().unwrap() = beacon,
+                    // the failed round is attempted again on the next poll
+                    Err(_) => futures_timer::Delay::new(WATCH_RETRY_DELAY).await,
+                }
+            }
+        }
+        futures_timer::Delay::new(WATCH_RETRY_DELAY).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::chained_chain_info;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn beacon_refresher_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        let latest_mock = server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let refresher = BeaconRefresher::spawn(client).await.unwrap();
+        // reads are served from memory
+        for _ in 0..3 {
+            assert_eq!(refresher.current().beacon(), chained_beacon());
+        }
+        latest_mock.assert_async().await;
+    }
+}
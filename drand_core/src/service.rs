@@ -0,0 +1,128 @@
+//! [`tower`] integration.
+//!
+//! [`TransportService`] exposes a [`Transport`] as a [`tower::Service`], taking the path of the relay document to retrieve. Retry, authentication, or observability layers
+//! can then be stacked on top of it, and the resulting service used as a transport again with [`ServiceTransport`].
+//!
+//! ```rust,ignore
+//! let service = tower::ServiceBuilder::new()
+//!     .timeout(Duration::from_secs(5))
+//!     .service(TransportService::new(transport));
+//! let client = HttpClient::with_transport(ServiceTransport::new(service, base_url), None);
+//! ```
+
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use tower::{BoxError, Service, ServiceExt};
+
+use crate::error::{Error, Result};
+use crate::transport::Transport;
+
+/// [`tower::Service`] retrieving the relay document at the requested path, such as `info` or `public/latest`.
+#[derive(Clone)]
+pub struct TransportService {
+    transport: Arc<dyn Transport>,
+}
+
+impl TransportService {
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self {
+            transport: Arc::new(transport),
+        }
+    }
+}
+
+impl Service<String> for TransportService {
+    type Response = serde_json::Value;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<serde_json::Value>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, path: String) -> Self::Future {
+        let transport = self.transport.clone();
+        Box::pin(async move { transport.get_json(&path).await })
+    }
+}
+
+/// Transport retrieving relay documents through a [`tower::Service`], such as a [`TransportService`] wrapped in middleware.
+/// `base_url` is only reported, requests are routed by the service.
+#[derive(Clone)]
+pub struct ServiceTransport<S> {
+    service: S,
+    base_url: String,
+}
+
+impl<S> ServiceTransport<S> {
+    pub fn new(service: S, base_url: &str) -> Self {
+        Self {
+            service,
+            base_url: base_url.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> Transport for ServiceTransport<S>
+where
+    S: Service<String, Response = serde_json::Value> + Clone + Send + Sync + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        self.service
+            .clone()
+            .oneshot(path.to_string())
+            .await
+            // recover the typed error of services wrapping a transport
+            .map_err(|err| match err.into().downcast::<Error>() {
+                Ok(err) => *err,
+                Err(err) => Error::Other(anyhow!(err)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::chained_chain_info;
+    use crate::transport::tests::StaticTransport;
+    use crate::HttpClient;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn service_transport_works() {
+        let transport = StaticTransport::new(&[
+            ("info", serde_json::to_value(chained_chain_info()).unwrap()),
+            (
+                "public/latest",
+                serde_json::to_value(chained_beacon()).unwrap(),
+            ),
+        ]);
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let service = TransportService::new(transport).map_request(move |path: String| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            path
+        });
+
+        let client = HttpClient::with_transport(ServiceTransport::new(service, "memory://"), None);
+        assert_eq!(client.latest().await.unwrap().beacon(), chained_beacon());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(client.get(1).await.is_err());
+    }
+}
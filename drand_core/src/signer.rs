@@ -0,0 +1,332 @@
+//! Beacon signing, to run private drand networks, and to generate valid chains in tests.
+//!
+//! A [`Signer`] holds the chain private key, and signs beacons under the chain scheme. A [`PartialSigner`] holds a share of it, as produced by a [key generation](crate::dkg).
+//! It signs partial beacons, which are [aggregated](crate::partial::aggregate) into a beacon once a threshold of nodes have signed the round.
+//!
+//! Chained beacons sign over the signature of the previous round. The first round signs over the chain genesis seed.
+
+use anyhow::anyhow;
+use ark_bls12_381::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::RngCore;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    beacon::RandomnessBeacon,
+    bls_signatures::{hash_to_g1, hash_to_g2},
+    chain::{Chain, ChainInfo, Scheme},
+    dkg::DistKeyShare,
+    error::{Error, Result},
+    partial::PartialBeacon,
+    point,
+};
+
+/// Private key of a chain.
+pub struct SecretKey {
+    scalar: Fr,
+}
+
+impl SecretKey {
+    /// New random key.
+    pub fn generate() -> Self {
+        let mut bytes = [0; 64];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let scalar = Fr::from_le_bytes_mod_order(&bytes);
+        bytes.zeroize();
+        Self { scalar }
+    }
+
+    /// Parse a key serialized as drand does, which is a 32 bytes big-endian scalar.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 32 {
+            return Err(Error::Other(anyhow!(
+                "secret key must be 32 bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let mut le_bytes = Zeroizing::new(bytes.to_vec());
+        le_bytes.reverse();
+        let scalar = Fr::deserialize_compressed(le_bytes.as_slice())
+            .map_err(|_| Error::Other(anyhow!("invalid secret key")))?;
+        Ok(Self { scalar })
+    }
+
+    /// Key serialized as drand does, which is a 32 bytes big-endian scalar. It is wiped from memory once dropped.
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(32));
+        self.scalar
+            .serialize_compressed(&mut *bytes)
+            .expect("scalars serialize in memory");
+        bytes.reverse();
+        bytes
+    }
+
+    /// Compressed public key of this key for `scheme`. It is on the group opposite to signatures.
+    pub fn public_key(&self, scheme: Scheme) -> Vec<u8> {
+        let mut public_key = vec![];
+        match is_key_on_g1(scheme) {
+            true => (G1Affine::generator() * self.scalar)
+                .into_affine()
+                .serialize_compressed(&mut public_key),
+            false => (G2Affine::generator() * self.scalar)
+                .into_affine()
+                .serialize_compressed(&mut public_key),
+        }
+        .expect("points serialize in memory");
+        public_key
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.scalar.zeroize();
+    }
+}
+
+/// Signer of the beacons of a chain, with its private key.
+pub struct Signer {
+    chain: Chain,
+    secret_key: SecretKey,
+}
+
+impl Signer {
+    /// Signer of the chain described by `info`. Fails if `secret_key` is not the chain private key.
+    pub fn new(info: ChainInfo, secret_key: SecretKey) -> Result<Self> {
+        let chain = Chain::new(info)?;
+        if secret_key.public_key(chain.scheme()) != point::compress(&chain.info().public_key())? {
+            return Err(Error::Other(anyhow!(
+                "secret key is not the one of the chain"
+            )));
+        }
+        Ok(Self { chain, secret_key })
+    }
+
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+
+    /// Beacon of `round`. Chained schemes need the `previous_signature`, except for round 1 which signs over the genesis seed.
+    /// Unchained schemes must not have one.
+    pub fn sign(
+        &self,
+        round: u64,
+        previous_signature: Option<Vec<u8>>,
+    ) -> Result<RandomnessBeacon> {
+        let unsigned = PartialBeacon::new(
+            round,
+            signed_previous_signature(&self.chain, round, previous_signature)?,
+            vec![],
+        );
+        let signature = sign_message(
+            &self.secret_key.scalar,
+            is_key_on_g1(self.chain.scheme()),
+            &unsigned.message()?,
+        )?;
+        Ok(self.chain.beacon(unsigned.beacon(signature)))
+    }
+}
+
+/// Signer of the partial beacons of a chain, with a share of its private key.
+pub struct PartialSigner {
+    chain: Chain,
+    share: DistKeyShare,
+}
+
+impl PartialSigner {
+    /// Signer of the chain described by `info`. Fails if `share` is not a share of the chain private key.
+    pub fn new(info: ChainInfo, share: DistKeyShare) -> Result<Self> {
+        let chain = Chain::new(info)?;
+        if share.public_polynomial().public_key() != point::compress(&chain.info().public_key())? {
+            return Err(Error::Other(anyhow!(
+                "share is not a share of the chain key"
+            )));
+        }
+        Ok(Self { chain, share })
+    }
+
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+
+    pub fn index(&self) -> u16 {
+        self.share.index()
+    }
+
+    /// Partial beacon of `round`, with the same `previous_signature` as [`Signer::sign`].
+    pub fn sign(&self, round: u64, previous_signature: Option<Vec<u8>>) -> Result<PartialBeacon> {
+        let previous_signature = signed_previous_signature(&self.chain, round, previous_signature)?;
+        let unsigned = PartialBeacon::new(round, previous_signature.clone(), vec![]);
+        let mut partial_signature = self.share.index().to_be_bytes().to_vec();
+        partial_signature.extend(sign_message(
+            self.share.share(),
+            is_key_on_g1(self.chain.scheme()),
+            &unsigned.message()?,
+        )?);
+        Ok(PartialBeacon::new(
+            round,
+            previous_signature,
+            partial_signature,
+        ))
+    }
+}
+
+/// Signatures are on G2 for pedersen-bls schemes, and on G1 otherwise. The public key is on the other group.
+fn is_key_on_g1(scheme: Scheme) -> bool {
+    scheme.signature_len() == 96
+}
+
+/// Previous signature `round` signs over, for the scheme of `chain`.
+fn signed_previous_signature(
+    chain: &Chain,
+    round: u64,
+    previous_signature: Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>> {
+    if round == 0 {
+        return Err(Error::Other(anyhow!(
+            "round 0 is the genesis, it is not signed"
+        )));
+    }
+    match (chain.scheme().is_unchained(), previous_signature) {
+        (true, None) => Ok(None),
+        (true, Some(_)) => Err(Error::Other(anyhow!(
+            "unchained beacons do not sign over a previous signature"
+        ))),
+        (false, Some(previous_signature)) => Ok(Some(previous_signature)),
+        (false, None) if round == 1 => Ok(Some(chain.info().genesis_seed())),
+        (false, None) => Err(Error::Other(anyhow!(
+            "chained beacon of round {round} needs the previous signature"
+        ))),
+    }
+}
+
+/// Compressed signature of `message` with `scalar`, on the group opposite to the public key.
+fn sign_message(scalar: &Fr, is_key_on_g1: bool, message: &[u8]) -> Result<Vec<u8>> {
+    let mut signature = vec![];
+    match is_key_on_g1 {
+        true => (hash_to_g2(message)? * *scalar)
+            .into_affine()
+            .serialize_compressed(&mut signature),
+        false => (hash_to_g1(message)? * *scalar)
+            .into_affine()
+            .serialize_compressed(&mut signature),
+    }
+    .expect("points serialize in memory");
+    Ok(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::{verify_batch, verify_chain};
+    use crate::dkg::tests::{new_group, run};
+    use crate::partial::aggregate;
+
+    use super::*;
+
+    fn chain_info(public_key: Vec<u8>, scheme: Scheme) -> ChainInfo {
+        ChainInfo::new(
+            public_key,
+            3,
+            1677685200,
+            vec![0; 32],
+            scheme.id().to_string(),
+            "private".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn signer_works() {
+        let schemes = [
+            Scheme::PedersenBlsChained,
+            Scheme::PedersenBlsUnchained,
+            Scheme::BlsUnchainedOnG1,
+        ];
+        for scheme in schemes {
+            let secret_key = SecretKey::generate();
+            let info = chain_info(secret_key.public_key(scheme), scheme);
+            let signer = Signer::new(info.clone(), secret_key).unwrap();
+
+            let mut beacons = vec![];
+            let mut previous_signature = None;
+            for round in 1..=3 {
+                let beacon = signer.sign(round, previous_signature).unwrap();
+                assert!(beacon.verify(info.clone()).unwrap());
+                assert_eq!(beacon.signature().len(), scheme.signature_len());
+                previous_signature = (!scheme.is_unchained()).then(|| beacon.signature().to_vec());
+                beacons.push(beacon);
+            }
+            assert!(verify_batch(&beacons, &info).unwrap());
+            if !scheme.is_unchained() {
+                assert!(verify_chain(&beacons, &info).unwrap());
+            }
+            assert!(signer.sign(0, None).is_err());
+        }
+    }
+
+    #[test]
+    fn signer_failure_works() {
+        let scheme = Scheme::PedersenBlsChained;
+        let info = chain_info(SecretKey::generate().public_key(scheme), scheme);
+        assert!(Signer::new(info.clone(), SecretKey::generate()).is_err());
+
+        let secret_key = SecretKey::generate();
+        let info = chain_info(secret_key.public_key(scheme), scheme);
+        let signer = Signer::new(info, secret_key).unwrap();
+        assert!(signer.sign(2, None).is_err());
+        let unchained = Scheme::PedersenBlsUnchained;
+        let secret_key = SecretKey::generate();
+        let info = chain_info(secret_key.public_key(unchained), unchained);
+        let signer = Signer::new(info, secret_key).unwrap();
+        assert!(signer.sign(2, Some(vec![0; 96])).is_err());
+    }
+
+    #[test]
+    fn secret_key_works() {
+        let secret_key = SecretKey::generate();
+        let bytes = secret_key.to_bytes();
+        assert_eq!(bytes.len(), 32);
+        let parsed = SecretKey::from_bytes(&bytes).unwrap();
+        let scheme = Scheme::BlsUnchainedOnG1;
+        assert_eq!(parsed.public_key(scheme), secret_key.public_key(scheme));
+        assert_eq!(secret_key.public_key(scheme).len(), 96);
+        assert!(SecretKey::from_bytes(&[0xff; 32]).is_err());
+        assert!(SecretKey::from_bytes(&[0; 31]).is_err());
+    }
+
+    #[test]
+    fn partial_signer_works() {
+        for scheme in [Scheme::PedersenBlsChained, Scheme::PedersenBlsUnchained] {
+            let shares: Vec<DistKeyShare> =
+                run(&mut new_group(3, 4)).into_iter().flatten().collect();
+            let polynomial = shares[0].public_polynomial();
+            let info = chain_info(polynomial.public_key(), scheme);
+            let signers: Vec<PartialSigner> = shares
+                .into_iter()
+                .map(|share| PartialSigner::new(info.clone(), share).unwrap())
+                .collect();
+
+            let partials: Vec<PartialBeacon> = signers
+                .iter()
+                .map(|signer| signer.sign(1, None).unwrap())
+                .collect();
+            assert!(partials
+                .iter()
+                .all(|partial| polynomial.verify_partial(partial).unwrap()));
+            let beacon = aggregate(&partials[1..], &polynomial, &info).unwrap();
+            assert_eq!(
+                beacon.previous_signature(),
+                (!scheme.is_unchained())
+                    .then(|| info.genesis_seed())
+                    .as_deref()
+            );
+            assert!(beacon.verify(info.clone()).unwrap());
+        }
+
+        let scheme = Scheme::PedersenBlsUnchained;
+        let share = run(&mut new_group(2, 2)).pop().unwrap().unwrap();
+        let info = chain_info(SecretKey::generate().public_key(scheme), scheme);
+        assert!(PartialSigner::new(info, share).is_err());
+    }
+}
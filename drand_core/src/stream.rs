@@ -0,0 +1,69 @@
+//! Combinators over streams of beacons.
+
+use futures::{stream::BoxStream, Stream, StreamExt};
+
+use crate::{beacon::RandomnessBeacon, error::Result};
+
+/// Merge beacon streams from multiple chains into a single stream.
+/// Each item is tagged with the hash of the chain it originates from, and is yielded as soon as it is available.
+/// Errors are isolated per chain: an error on one chain is forwarded, and does not interrupt the other chains.
+/// The merged stream ends once all chain streams have ended.
+pub fn merge<'a, S>(
+    streams: impl IntoIterator<Item = (Vec<u8>, S)>,
+) -> impl Stream<Item = (Vec<u8>, Result<RandomnessBeacon>)> + Send + 'a
+where
+    S: Stream<Item = Result<RandomnessBeacon>> + Send + 'a,
+{
+    let tagged: Vec<BoxStream<'a, (Vec<u8>, Result<RandomnessBeacon>)>> = streams
+        .into_iter()
+        .map(|(chain_hash, stream)| {
+            stream
+                .map(move |beacon| (chain_hash.clone(), beacon))
+                .boxed()
+        })
+        .collect();
+    futures::stream::select_all(tagged)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::beacon::tests::{chained_beacon, unchained_beacon};
+    use crate::chain::tests::{chained_chain_info, unchained_chain_info};
+    use crate::error::Error;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn merge_isolates_errors_works() {
+        let chained = futures::stream::iter(vec![
+            Err(Error::Other(anyhow!("relay is down"))),
+            Ok(RandomnessBeacon::new(chained_beacon(), 0)),
+        ]);
+        let unchained = futures::stream::iter(vec![
+            Ok(RandomnessBeacon::new(unchained_beacon(), 0)),
+            Ok(RandomnessBeacon::new(unchained_beacon(), 0)),
+        ]);
+
+        let items: Vec<(Vec<u8>, Result<RandomnessBeacon>)> = merge(vec![
+            (chained_chain_info().hash(), chained),
+            (unchained_chain_info().hash(), unchained),
+        ])
+        .collect()
+        .await;
+        assert_eq!(items.len(), 4);
+
+        let (chained_items, unchained_items): (Vec<_>, Vec<_>) = items
+            .into_iter()
+            .partition(|(hash, _)| *hash == chained_chain_info().hash());
+        // the error on the chained stream does not prevent its next beacon, nor the unchained ones
+        assert!(chained_items[0].1.is_err());
+        assert_eq!(
+            chained_items[1].1.as_ref().unwrap().beacon(),
+            chained_beacon()
+        );
+        assert_eq!(unchained_items.len(), 2);
+        assert!(unchained_items.iter().all(|(_, beacon)| beacon.is_ok()));
+    }
+}
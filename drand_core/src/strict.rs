@@ -0,0 +1,195 @@
+//! Strict parsing of relay responses, for deployments which would rather fail than accept output drand relays do not produce.
+//!
+//! A response has to be the exact encoding of the document it is parsed into: fields the document does not define are rejected, and so are byte fields which are not lowercase hex.
+//! Fields are then checked for consistency, such as the beacon randomness being derived from its signature, or the chain hash covering the rest of the info.
+
+use std::str::FromStr;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api_client::RelayHealth,
+    beacon::ApiBeacon,
+    chain::{ChainInfo, Scheme},
+    error::RelayError,
+};
+
+/// Schemes relays can serve beacons of.
+const SCHEMES: [Scheme; 4] = [
+    Scheme::PedersenBlsChained,
+    Scheme::PedersenBlsUnchained,
+    Scheme::BlsUnchainedOnG1,
+    Scheme::BlsBn254UnchainedOnG1,
+];
+
+/// Document whose fields can be checked for consistency, once parsed.
+pub(crate) trait Validate {
+    fn validate(&self) -> Result<(), String>;
+}
+
+impl Validate for ApiBeacon {
+    fn validate(&self) -> Result<(), String> {
+        let signature = self.signature();
+        if !SCHEMES
+            .iter()
+            .any(|scheme| scheme.is_conforming(self) && scheme.signature_len() == signature.len())
+        {
+            return Err(format!(
+                "beacon {} is not a compressed beacon of a known scheme",
+                self.round()
+            ));
+        }
+        if let Some(previous_signature) = self.previous_signature() {
+            // the first round signs over the 32 bytes genesis seed
+            if previous_signature.len() != if self.round() == 1 { 32 } else { 96 } {
+                return Err(format!(
+                    "beacon {} previous signature is not a compressed signature",
+                    self.round()
+                ));
+            }
+        }
+        if self.randomness() != Sha256::digest(signature).as_slice() {
+            return Err(format!(
+                "beacon {} randomness is not the hash of its signature",
+                self.round()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for ChainInfo {
+    fn validate(&self) -> Result<(), String> {
+        Scheme::from_str(&self.scheme_id()).map_err(|err| err.to_string())?;
+        if !self.verify_hash() {
+            return Err("chain hash does not match chain info".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validate for RelayHealth {
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Validate for Vec<String> {
+    /// Chain hashes, as listed by `/chains`.
+    fn validate(&self) -> Result<(), String> {
+        match self.iter().find(|chain_hash| {
+            chain_hash.len() != 64
+                || !chain_hash
+                    .bytes()
+                    .all(|c| c.is_ascii_digit() || (b'a'..=b'f').contains(&c))
+        }) {
+            Some(chain_hash) => Err(format!("{chain_hash} is not a chain hash")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse `value`, failing if it is not the exact encoding of a consistent document.
+pub(crate) fn from_value<T: DeserializeOwned + Serialize + Validate>(
+    value: Value,
+) -> Result<T, RelayError> {
+    let parsed: T = serde_json::from_value(value.clone())?;
+    let encoded = serde_json::to_value(&parsed)?;
+    if let Some(difference) = difference(&value, &encoded, "") {
+        return Err(invalid(difference));
+    }
+    parsed.validate().map_err(invalid)?;
+    Ok(parsed)
+}
+
+/// First field of `value` which is not encoded as in `encoded`, described for diagnostics.
+fn difference(value: &Value, encoded: &Value, path: &str) -> Option<String> {
+    match (value, encoded) {
+        (Value::Object(fields), Value::Object(encoded_fields)) => {
+            if let Some(name) = fields
+                .keys()
+                .find(|name| !encoded_fields.contains_key(*name))
+            {
+                return Some(format!("unknown field `{path}{name}`"));
+            }
+            if let Some(name) = encoded_fields
+                .keys()
+                .find(|name| !fields.contains_key(*name))
+            {
+                return Some(format!("missing field `{path}{name}`"));
+            }
+            fields.iter().find_map(|(name, field)| {
+                difference(field, &encoded_fields[name], &format!("{path}{name}."))
+            })
+        }
+        _ if value == encoded => None,
+        _ => Some(format!("malformed field `{}`", path.trim_end_matches('.'))),
+    }
+}
+
+fn invalid(reason: String) -> RelayError {
+    RelayError::Deserialization(serde::de::Error::custom(reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{chained_beacon, unchained_beacon_on_g1};
+    use crate::chain::tests::{chained_chain_info, rehash};
+
+    use super::*;
+
+    fn reason(err: RelayError) -> String {
+        match err {
+            RelayError::Deserialization(err) => err.to_string(),
+            err => panic!("Error should be a deserialization one, got {err}"),
+        }
+    }
+
+    #[test]
+    fn strict_works() {
+        for beacon in [chained_beacon(), unchained_beacon_on_g1()] {
+            let value = serde_json::to_value(&beacon).unwrap();
+            assert_eq!(from_value::<ApiBeacon>(value).unwrap(), beacon);
+        }
+        let info = chained_chain_info();
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(from_value::<ChainInfo>(value).unwrap(), info);
+
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["extra"] = "field".into();
+        assert!(reason(from_value::<ApiBeacon>(beacon).unwrap_err()).contains("`extra`"));
+
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        info["metadata"]["extra"] = 1.into();
+        assert!(reason(from_value::<ChainInfo>(info).unwrap_err()).contains("`metadata.extra`"));
+
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["signature"] = beacon["signature"].as_str().unwrap().to_uppercase().into();
+        assert!(reason(from_value::<ApiBeacon>(beacon).unwrap_err()).contains("`signature`"));
+    }
+
+    #[test]
+    fn strict_validation_works() {
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["randomness"] = hex::encode([0; 32]).into();
+        assert!(reason(from_value::<ApiBeacon>(beacon).unwrap_err()).contains("randomness"));
+        let mut beacon = serde_json::to_value(chained_beacon()).unwrap();
+        beacon["previous_signature"] = hex::encode([0; 32]).into();
+        assert!(from_value::<ApiBeacon>(beacon).is_err());
+
+        let mut info = serde_json::to_value(chained_chain_info()).unwrap();
+        info["period"] = 60.into();
+        assert!(from_value::<ChainInfo>(info.clone()).is_err());
+        rehash(&mut info);
+        assert!(from_value::<ChainInfo>(info.clone()).is_ok());
+        info["schemeID"] = "unknown".into();
+        assert!(from_value::<ChainInfo>(info).is_err());
+
+        let chains = vec![hex::encode(chained_chain_info().hash())];
+        assert!(from_value::<Vec<String>>(serde_json::to_value(&chains).unwrap()).is_ok());
+        let chains = vec![chains[0].to_uppercase()];
+        assert!(from_value::<Vec<String>>(serde_json::to_value(&chains).unwrap()).is_err());
+    }
+}
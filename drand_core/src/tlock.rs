@@ -0,0 +1,368 @@
+//! Timelock encryption toward a future round of an unchained chain.
+//!
+//! It follows drand [tlock](https://github.com/drand/tlock) construction: plaintext is encrypted with identity-based encryption against the chain public key, using the round as the identity.
+//! The beacon signature of that round is the decryption key, so that nobody, including the encrypter, can decrypt before the round is emitted.
+//! Decryption verifies the beacon against the chain first, so that a relay cannot feed a forged key.
+//! Ciphertexts are [age](https://age-encryption.org/v1) files, interoperable with the Go and JavaScript implementations of tlock, such as the `tle` command line tool.
+//! Ciphertexts can also be sealed to an age X25519 recipient, so that the plaintext is only recovered after the round, and by that recipient.
+//! Their header holds a single `tlock` recipient stanza, `-> tlock <round> <chain hash>`, followed by the IBE ciphertext of the age file key. They may be ASCII armored.
+
+use std::{io, iter, str::FromStr};
+
+use anyhow::anyhow;
+use zeroize::Zeroizing;
+
+use crate::{
+    beacon::{RandomnessBeacon, VerifiedBeacon},
+    chain::ChainInfo,
+    error::{Error, Result},
+    HttpClient,
+};
+
+/// Encrypt `src` into `dst`, so that it can only be decrypted once `round` of the chain described by `info` is emitted.
+/// Fails if the chain is chained, as the signature of a round depends on the previous one, and cannot be used as an identity ahead of time.
+pub fn encrypt<W: io::Write, R: io::Read>(
+    dst: W,
+    src: R,
+    info: &ChainInfo,
+    round: u64,
+) -> Result<()> {
+    check_unchained(info)?;
+    tlock_age::encrypt(dst, src, &info.hash(), &info.public_key(), round)?;
+    Ok(())
+}
+
+/// Encrypt as [`encrypt`] does, with an ASCII armored output.
+pub fn encrypt_armored<W: io::Write, R: io::Read>(
+    dst: W,
+    src: R,
+    info: &ChainInfo,
+    round: u64,
+) -> Result<()> {
+    check_unchained(info)?;
+    let mut dst = tlock_age::armor::ArmoredWriter::wrap_output(dst).map_err(anyhow::Error::from)?;
+    tlock_age::encrypt(&mut dst, src, &info.hash(), &info.public_key(), round)?;
+    dst.finish().map_err(anyhow::Error::from)?;
+    Ok(())
+}
+
+/// Encrypt `src` into `dst` toward `round`, as [`encrypt`] does, and to the age X25519 `recipient`, such as `age1...`.
+/// Decrypting requires both the beacon of `round` and the recipient identity, see [`decrypt_with_identity`].
+/// The timelocked ciphertext is itself encrypted to `recipient`, so that its `tlock` header is only visible to the recipient.
+pub fn encrypt_to_recipient<W: io::Write, R: io::Read>(
+    dst: W,
+    src: R,
+    info: &ChainInfo,
+    round: u64,
+    recipient: &str,
+) -> Result<()> {
+    check_unchained(info)?;
+    let recipient = age::x25519::Recipient::from_str(recipient)
+        .map_err(|e| anyhow!("invalid X25519 recipient: {e}"))?;
+    let encryptor =
+        age::Encryptor::with_recipients(
+            vec![Box::new(recipient) as Box<dyn age::Recipient + Send>],
+        )
+        .ok_or_else(|| anyhow!("a recipient is required"))?;
+    let mut dst = encryptor.wrap_output(dst).map_err(anyhow::Error::from)?;
+    encrypt(&mut dst, src, info, round)?;
+    dst.finish().map_err(anyhow::Error::from)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Recipient of a ciphertext, as stated by its `tlock` stanza.
+pub struct Header {
+    /// Round the ciphertext has been encrypted toward.
+    pub round: u64,
+    /// Hash of the chain the round is from.
+    pub chain_hash: Vec<u8>,
+}
+
+/// Read the header of a ciphertext, to tell which chain and round decrypt it, such as for files produced by other tlock tools.
+/// Fails if `src` is not an age file with a `tlock` stanza.
+pub fn read_header<R: io::Read>(src: R) -> Result<Header> {
+    let header = tlock_age::decrypt_header(src).map_err(anyhow::Error::from)?;
+    Ok(Header {
+        round: header.round(),
+        chain_hash: header.hash(),
+    })
+}
+
+/// Decrypt `src` into `dst`, with `beacon` of the round the ciphertext has been encrypted toward. Armored ciphertexts are accepted as well.
+/// `beacon` is verified against `info` first. Fails if the ciphertext is for another chain or round.
+pub fn decrypt<W: io::Write, R: io::Read>(
+    dst: W,
+    src: R,
+    beacon: &RandomnessBeacon,
+    info: &ChainInfo,
+) -> Result<()> {
+    let beacon = VerifiedBeacon::new(beacon.clone(), info.clone())?;
+    let mut src = Recorder::new(src);
+    let Header { round, chain_hash } = read_header(&mut src)?;
+    if chain_hash != info.hash() {
+        return Err(Error::Other(anyhow!(
+            "ciphertext is for chain {}, not {}",
+            hex::encode(&chain_hash),
+            hex::encode(info.hash())
+        )));
+    }
+    if round != beacon.round() {
+        return Err(Error::Other(anyhow!(
+            "ciphertext is for round {round}, not {}",
+            beacon.round()
+        )));
+    }
+    tlock_age::decrypt(dst, src.replay(), &chain_hash, beacon.signature())?;
+    Ok(())
+}
+
+/// Decrypt `src` into `dst`, produced by [`encrypt_to_recipient`], with the age X25519 `identity`, such as `AGE-SECRET-KEY-1...`, and `beacon` of the round.
+/// `beacon` is verified against `info` first.
+pub fn decrypt_with_identity<W: io::Write, R: io::Read>(
+    dst: W,
+    src: R,
+    beacon: &RandomnessBeacon,
+    info: &ChainInfo,
+    identity: &str,
+) -> Result<()> {
+    let identity = age::x25519::Identity::from_str(identity)
+        .map_err(|e| anyhow!("invalid X25519 identity: {e}"))?;
+    let age::Decryptor::Recipients(decryptor) =
+        age::Decryptor::new(src).map_err(anyhow::Error::from)?
+    else {
+        return Err(Error::Other(anyhow!(
+            "ciphertext is not encrypted to a recipient"
+        )));
+    };
+    let src = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(anyhow::Error::from)?;
+    decrypt(dst, src, beacon, info)
+}
+
+/// Decrypt `src` into `dst`, waiting for the round the ciphertext has been encrypted toward if it has not been emitted yet.
+/// The beacon is retrieved with `client`, whose chain must be the ciphertext one.
+/// This relies on [`std::time::SystemTime`], which is not available on wasm32-unknown-unknown.
+pub async fn decrypt_at_time<W: io::Write, R: io::Read>(
+    client: &HttpClient,
+    dst: W,
+    src: R,
+) -> Result<()> {
+    let info = client.chain_info().await?;
+    let mut src = Recorder::new(src);
+    let header = read_header(&mut src)?;
+    let beacon = client.get_when_emitted(header.round).await?;
+    decrypt(dst, src.replay(), &beacon, &info)
+}
+
+/// Reader keeping the bytes read, so that the ciphertext can be read again from the start once its header has been parsed.
+/// With [`decrypt_with_identity`], these bytes have been decrypted with the recipient identity, so they are wiped once dropped.
+struct Recorder<R> {
+    inner: R,
+    read: Zeroizing<Vec<u8>>,
+}
+
+impl<R: io::Read> Recorder<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            read: Zeroizing::new(vec![]),
+        }
+    }
+
+    /// The whole input: bytes read so far, followed by the rest of the inner reader.
+    fn replay(self) -> io::Chain<io::Cursor<Zeroizing<Vec<u8>>>, R> {
+        io::Read::chain(io::Cursor::new(self.read), self.inner)
+    }
+}
+
+impl<R: io::Read> io::Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+fn check_unchained(info: &ChainInfo) -> Result<()> {
+    if !info.is_unchained() {
+        return Err(Error::Other(anyhow!(
+            "scheme {} is chained, timelock encryption requires an unchained chain",
+            info.scheme_id()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beacon::tests::{invalid_beacon, unchained_beacon};
+    use crate::chain::{
+        tests::{chained_chain_info, unchained_chain_info},
+        Chain,
+    };
+
+    use super::*;
+
+    const PLAINTEXT: &[u8] = b"timelocked until round 1000000";
+
+    #[test]
+    fn tlock_encrypt_works() {
+        let info = unchained_chain_info();
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1000000).unwrap();
+
+        let header = read_header(ciphertext.as_slice()).unwrap();
+        assert_eq!(header.round, 1000000);
+        assert_eq!(header.chain_hash, info.hash());
+
+        // the signature of the round decrypts it
+        let mut plaintext = vec![];
+        tlock_age::decrypt(
+            &mut plaintext,
+            ciphertext.as_slice(),
+            &info.hash(),
+            unchained_beacon().signature(),
+        )
+        .unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+
+        let mut armored = vec![];
+        encrypt_armored(&mut armored, PLAINTEXT, &info, 1000000).unwrap();
+        assert!(armored.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"));
+    }
+
+    #[test]
+    fn tlock_encrypt_chained_fails_works() {
+        let mut ciphertext = vec![];
+        assert!(encrypt(&mut ciphertext, PLAINTEXT, &chained_chain_info(), 1000000).is_err());
+        assert!(ciphertext.is_empty());
+    }
+
+    #[test]
+    fn tlock_decrypt_works() {
+        let info = unchained_chain_info();
+        let beacon = Chain::new(info.clone()).unwrap().beacon(unchained_beacon());
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1000000).unwrap();
+        let mut plaintext = vec![];
+        decrypt(&mut plaintext, ciphertext.as_slice(), &beacon, &info).unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+
+        let mut armored = vec![];
+        encrypt_armored(&mut armored, PLAINTEXT, &info, 1000000).unwrap();
+        let mut plaintext = vec![];
+        decrypt(&mut plaintext, armored.as_slice(), &beacon, &info).unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+
+        // the beacon of another round is not the key
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1000001).unwrap();
+        assert!(decrypt(&mut vec![], ciphertext.as_slice(), &beacon, &info).is_err());
+    }
+
+    #[test]
+    fn tlock_decrypt_invalid_beacon_works() {
+        let info = unchained_chain_info();
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1234).unwrap();
+        let forged = Chain::new(info.clone()).unwrap().beacon(invalid_beacon());
+        assert!(matches!(
+            decrypt(&mut vec![], ciphertext.as_slice(), &forged, &info),
+            Err(Error::InvalidBeacon { round: 1234, .. })
+        ));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn tlock_decrypt_at_time_works() {
+        let info = unchained_chain_info();
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&info).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1000000")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&unchained_beacon()).unwrap())
+            .create_async()
+            .await;
+
+        // round 1000000 has been emitted already, so it is retrieved right away
+        let client = HttpClient::new(server.url().as_str(), None).unwrap();
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1000000).unwrap();
+        let mut plaintext = vec![];
+        decrypt_at_time(&client, &mut plaintext, ciphertext.as_slice())
+            .await
+            .unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn tlock_format_works() {
+        let info = unchained_chain_info();
+        let mut ciphertext = vec![];
+        encrypt(&mut ciphertext, PLAINTEXT, &info, 1000000).unwrap();
+        // header expected by tle, and the other tlock implementations
+        let stanza = format!(
+            "age-encryption.org/v1\n-> tlock 1000000 {}\n",
+            hex::encode(info.hash())
+        );
+        assert!(ciphertext.starts_with(stanza.as_bytes()));
+
+        let mut armored = vec![];
+        encrypt_armored(&mut armored, PLAINTEXT, &info, 1000000).unwrap();
+        assert!(armored.ends_with(b"-----END AGE ENCRYPTED FILE-----\n"));
+        assert_eq!(
+            read_header(armored.as_slice()).unwrap(),
+            read_header(ciphertext.as_slice()).unwrap()
+        );
+
+        assert!(read_header(PLAINTEXT).is_err());
+    }
+
+    #[test]
+    fn tlock_recipient_works() {
+        use age::secrecy::ExposeSecret;
+
+        let info = unchained_chain_info();
+        let beacon = Chain::new(info.clone()).unwrap().beacon(unchained_beacon());
+        let identity = age::x25519::Identity::generate();
+        let secret = identity.to_string().expose_secret().clone();
+        let recipient = identity.to_public().to_string();
+
+        let mut ciphertext = vec![];
+        encrypt_to_recipient(&mut ciphertext, PLAINTEXT, &info, 1000000, &recipient).unwrap();
+        // the round is hidden to anyone but the recipient
+        assert!(read_header(ciphertext.as_slice()).is_err());
+        let mut plaintext = vec![];
+        decrypt_with_identity(
+            &mut plaintext,
+            ciphertext.as_slice(),
+            &beacon,
+            &info,
+            &secret,
+        )
+        .unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+
+        // the beacon alone, or the identity of someone else, do not decrypt it
+        assert!(decrypt(&mut vec![], ciphertext.as_slice(), &beacon, &info).is_err());
+        let other = age::x25519::Identity::generate()
+            .to_string()
+            .expose_secret()
+            .clone();
+        assert!(
+            decrypt_with_identity(&mut vec![], ciphertext.as_slice(), &beacon, &info, &other)
+                .is_err()
+        );
+        assert!(encrypt_to_recipient(&mut vec![], PLAINTEXT, &info, 1000000, "age1").is_err());
+    }
+}
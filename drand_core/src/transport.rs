@@ -0,0 +1,816 @@
+//! Transports retrieving raw responses from a drand relay.
+//!
+//! By default, relays are queried over HTTP with [`HttpTransport`]. Implementing [`Transport`] allows for other HTTP stacks, test doubles, or non-HTTP sources.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+#[cfg(feature = "reqwest")]
+use futures::{Stream, StreamExt};
+#[cfg(any(feature = "hyper", all(feature = "unix", unix)))]
+use hyper::body::HttpBody;
+
+#[cfg(any(
+    feature = "reqwest",
+    feature = "hyper",
+    all(feature = "unix", unix),
+    feature = "blocking-ureq"
+))]
+use crate::error::{ensure_json, RelayError};
+use crate::error::{Error, Result};
+
+/// JSON documents pushed by a relay.
+#[cfg(not(target_arch = "wasm32"))]
+pub type JsonStream = futures::stream::BoxStream<'static, Result<serde_json::Value>>;
+/// JSON documents pushed by a relay.
+#[cfg(target_arch = "wasm32")]
+pub type JsonStream = futures::stream::LocalBoxStream<'static, Result<serde_json::Value>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Outcome of a conditional request.
+pub enum Conditional<T> {
+    /// Document changed, or no ETag was provided. Its new ETag is attached when the relay sets one.
+    Modified(T, Option<String>),
+    /// Document still matches the provided ETag, and has not been sent again.
+    NotModified,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+/// Source of JSON documents served by a drand relay.
+/// On wasm32, returned futures are not required to be `Send`, as browser requests are bound to the JavaScript event loop.
+pub trait Transport: Send + Sync {
+    /// Location of the relay the transport is bound to.
+    fn base_url(&self) -> String;
+
+    /// Retrieve the JSON document at `path`, relative to the relay base URL.
+    /// Paths are the ones defined by the drand HTTP API, such as `info` or `public/latest`.
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value>;
+
+    /// Retrieve the JSON document at `path`, unless it still matches `etag`.
+    /// Transports without conditional requests always retrieve the document.
+    async fn get_json_if_none_match(
+        &self,
+        path: &str,
+        _etag: Option<&str>,
+    ) -> Result<Conditional<serde_json::Value>> {
+        Ok(Conditional::Modified(self.get_json(path).await?, None))
+    }
+
+    /// Retrieve the JSON documents streamed at `path`, as they are pushed by the relay.
+    /// Transports without streaming support return an error, for clients to fall back to polling.
+    async fn stream_json(&self, path: &str) -> Result<JsonStream> {
+        Err(Error::Other(anyhow!(
+            "{} does not support streaming {path}",
+            self.base_url()
+        )))
+    }
+}
+
+/// Maximum size of a relay response, in bytes. drand documents are well under 1 KiB.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+/// HTTP transport, backed by reqwest.
+#[cfg(feature = "reqwest")]
+pub struct HttpTransport {
+    base_url: url::Url,
+    http_client: reqwest::Client,
+    max_response_size: usize,
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpTransport {
+    pub fn new(http_client: reqwest::Client, base_url: &str) -> Result<Self> {
+        Ok(Self {
+            base_url: parse_base_url(base_url)?,
+            http_client,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Stop reading responses larger than `max_response_size` bytes, so that a misbehaving relay cannot exhaust memory.
+    /// For streams, the limit applies to each event.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for HttpTransport {
+    fn base_url(&self) -> String {
+        self.base_url.to_string()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let response = self
+            .http_client
+            .get(self.base_url.join(path).map_err(anyhow::Error::from)?)
+            .send()
+            .await
+            .map_err(RelayError::network)?;
+        json_response(response, self.max_response_size).await
+    }
+
+    async fn get_json_if_none_match(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<serde_json::Value>> {
+        let mut request = self
+            .http_client
+            .get(self.base_url.join(path).map_err(anyhow::Error::from)?);
+        if let Some(etag) = etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await.map_err(RelayError::network)?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        Ok(Conditional::Modified(
+            json_response(response, self.max_response_size).await?,
+            etag,
+        ))
+    }
+
+    /// Server-sent events, each event carrying a JSON document.
+    async fn stream_json(&self, path: &str) -> Result<JsonStream> {
+        let response = self
+            .http_client
+            .get(self.base_url.join(path).map_err(anyhow::Error::from)?)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(RelayError::network)?;
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map_or(false, |content_type| {
+                content_type.starts_with("text/event-stream")
+            });
+        if !response.status().is_success() || !is_event_stream {
+            return Err(Error::Other(anyhow!("{path} is not an event stream")));
+        }
+        let events = server_sent_events(Box::pin(response.bytes_stream()), self.max_response_size);
+        #[cfg(not(target_arch = "wasm32"))]
+        let events = events.boxed();
+        #[cfg(target_arch = "wasm32")]
+        let events = events.boxed_local();
+        Ok(events)
+    }
+}
+
+/// HTTP transport, backed by hyper alone.
+/// Lighter than [`HttpTransport`] for applications minimising their dependencies and binary size.
+#[cfg(feature = "hyper")]
+pub struct HyperTransport {
+    base_url: url::Url,
+    http_client: hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
+    max_response_size: usize,
+}
+
+#[cfg(feature = "hyper")]
+impl HyperTransport {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self::with_http_client(hyper::Client::builder().build(connector), base_url)
+    }
+
+    /// Create a transport reusing an existing hyper client, with its connection pool.
+    pub fn with_http_client(
+        http_client: hyper::Client<hyper_rustls::HttpsConnector<hyper::client::HttpConnector>>,
+        base_url: &str,
+    ) -> Result<Self> {
+        Ok(Self {
+            base_url: parse_base_url(base_url)?,
+            http_client,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Stop reading responses larger than `max_response_size` bytes.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+}
+
+#[cfg(feature = "hyper")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for HyperTransport {
+    fn base_url(&self) -> String {
+        self.base_url.to_string()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let uri: hyper::Uri = self
+            .base_url
+            .join(path)
+            .map_err(anyhow::Error::from)?
+            .as_str()
+            .parse()
+            .map_err(anyhow::Error::from)?;
+        let response = self
+            .http_client
+            .get(uri)
+            .await
+            .map_err(RelayError::network)?;
+        hyper_json_response(response, self.max_response_size).await
+    }
+}
+
+#[cfg(any(feature = "hyper", all(feature = "unix", unix)))]
+async fn hyper_json_response(
+    response: hyper::Response<hyper::Body>,
+    max_response_size: usize,
+) -> Result<serde_json::Value> {
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .map(|content_type| String::from_utf8_lossy(content_type.as_bytes()).to_string());
+    let retry_after = response
+        .headers()
+        .get(hyper::header::RETRY_AFTER)
+        .map(|retry_after| String::from_utf8_lossy(retry_after.as_bytes()).to_string());
+    let mut body = response.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(RelayError::network)?;
+        if bytes.len() + chunk.len() > max_response_size {
+            return Err(RelayError::TooLarge {
+                limit: max_response_size,
+            }
+            .into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&bytes).to_string();
+    if !status.is_success() {
+        return Err(RelayError::from_status(status.as_u16(), body)
+            .with_retry_after(retry_after.as_deref())
+            .into());
+    }
+    ensure_json(content_type.as_deref(), &body)?;
+    Ok(serde_json::from_str(&body).map_err(RelayError::from)?)
+}
+
+/// HTTP transport over a Unix domain socket, to reach a co-located relay without exposing a TCP port.
+/// Base URLs look like `http+unix://%2Fvar%2Frun%2Fdrand.sock/`, with the percent-encoded socket path as host, optionally followed by a path prefix.
+#[cfg(all(feature = "unix", unix))]
+pub struct UnixTransport {
+    base_url: String,
+    socket_path: std::path::PathBuf,
+    base_path: String,
+    max_response_size: usize,
+}
+
+#[cfg(all(feature = "unix", unix))]
+impl UnixTransport {
+    pub fn new(base_url: &str) -> Result<Self> {
+        let Some(rest) = base_url.strip_prefix("http+unix://") else {
+            return Err(Error::Other(anyhow!(
+                "{base_url} is not a Unix socket URL. It should look like \"http+unix://%2Fvar%2Frun%2Fdrand.sock/\"."
+            )));
+        };
+        let (socket_path, base_path) = rest.split_once('/').unwrap_or((rest, ""));
+        let socket_path = percent_decode(socket_path)?;
+        if socket_path.is_empty() {
+            return Err(Error::Other(anyhow!("{base_url} has no socket path")));
+        }
+        let base_path = match base_path.trim_end_matches('/') {
+            "" => "/".to_string(),
+            prefix => format!("/{prefix}/"),
+        };
+        Ok(Self {
+            base_url: base_url.to_string(),
+            socket_path: socket_path.into(),
+            base_path,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Stop reading responses larger than `max_response_size` bytes.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+}
+
+#[cfg(all(feature = "unix", unix))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for UnixTransport {
+    fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let stream = tokio::net::UnixStream::connect(&self.socket_path)
+            .await
+            .map_err(RelayError::network)?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream)
+            .await
+            .map_err(RelayError::network)?;
+        // the connection is only used for this request, and closes once it is dropped
+        tokio::spawn(connection);
+        let request = hyper::Request::get(format!("{}{path}", self.base_path))
+            .header(hyper::header::HOST, "localhost")
+            .body(hyper::Body::empty())
+            .map_err(anyhow::Error::from)?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(RelayError::network)?;
+        hyper_json_response(response, self.max_response_size).await
+    }
+}
+
+/// Decode `%XX` escapes of `encoded`.
+#[cfg(all(feature = "unix", unix))]
+fn percent_decode(encoded: &str) -> anyhow::Result<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut chars = encoded.bytes();
+    while let Some(byte) = chars.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let hex = [chars.next(), chars.next()];
+        let [Some(high), Some(low)] = hex else {
+            return Err(anyhow!("{encoded}: truncated percent escape"));
+        };
+        bytes.push(u8::from_str_radix(std::str::from_utf8(&[high, low])?, 16)?);
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(feature = "reqwest")]
+async fn json_response(
+    mut response: reqwest::Response,
+    max_response_size: usize,
+) -> Result<serde_json::Value> {
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .map(|content_type| String::from_utf8_lossy(content_type.as_bytes()).to_string());
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .map(|retry_after| String::from_utf8_lossy(retry_after.as_bytes()).to_string());
+    let too_large = RelayError::TooLarge {
+        limit: max_response_size,
+    };
+    // fail early when the relay announces its size, and otherwise stop reading once the limit is reached
+    if response
+        .content_length()
+        .map_or(false, |len| len > max_response_size as u64)
+    {
+        return Err(too_large.into());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(RelayError::network)? {
+        if bytes.len() + chunk.len() > max_response_size {
+            return Err(too_large.into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&bytes).to_string();
+    if !status.is_success() {
+        return Err(RelayError::from_status(status.as_u16(), body)
+            .with_retry_after(retry_after.as_deref())
+            .into());
+    }
+    ensure_json(content_type.as_deref(), &body)?;
+    Ok(serde_json::from_str(&body).map_err(RelayError::from)?)
+}
+
+/// Blocking HTTP transport, backed by ureq.
+/// Requests are performed synchronously when the returned future is first polled. Clients using this transport
+/// can therefore be driven without an async runtime, for instance with [`futures::executor::block_on`].
+#[cfg(feature = "blocking-ureq")]
+pub struct UreqTransport {
+    base_url: url::Url,
+    agent: ureq::Agent,
+    max_response_size: usize,
+}
+
+#[cfg(feature = "blocking-ureq")]
+impl UreqTransport {
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_agent(ureq::Agent::new(), base_url)
+    }
+
+    /// Create a transport reusing an existing ureq agent, with its connection pool, proxy, and timeouts.
+    pub fn with_agent(agent: ureq::Agent, base_url: &str) -> Result<Self> {
+        Ok(Self {
+            base_url: parse_base_url(base_url)?,
+            agent,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Stop reading responses larger than `max_response_size` bytes.
+    pub fn with_max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    fn read_body(&self, response: ureq::Response) -> Result<String> {
+        use std::io::Read;
+
+        let mut bytes = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_response_size as u64 + 1)
+            .read_to_end(&mut bytes)
+            .map_err(RelayError::network)?;
+        if bytes.len() > self.max_response_size {
+            return Err(RelayError::TooLarge {
+                limit: self.max_response_size,
+            }
+            .into());
+        }
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+}
+
+#[cfg(feature = "blocking-ureq")]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for UreqTransport {
+    fn base_url(&self) -> String {
+        self.base_url.to_string()
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let url = self.base_url.join(path).map_err(anyhow::Error::from)?;
+        match self.agent.get(url.as_str()).call() {
+            Ok(response) => {
+                let content_type = response.header("content-type").map(str::to_string);
+                let body = self.read_body(response)?;
+                ensure_json(content_type.as_deref(), &body)?;
+                Ok(serde_json::from_str(&body).map_err(RelayError::from)?)
+            }
+            Err(ureq::Error::Status(status, response)) => {
+                let retry_after = response.header("retry-after").map(str::to_string);
+                let body = self.read_body(response)?;
+                Err(RelayError::from_status(status, body)
+                    .with_retry_after(retry_after.as_deref())
+                    .into())
+            }
+            Err(err) => Err(RelayError::network(err).into()),
+        }
+    }
+}
+
+/// Parse the `data` fields of server-sent events as JSON. Events without data, such as keep-alive comments, are skipped.
+/// The stream fails, and ends, on an event larger than `max_event_size`.
+#[cfg(feature = "reqwest")]
+fn server_sent_events<S, B, E>(
+    bytes: S,
+    max_event_size: usize,
+) -> impl Stream<Item = Result<serde_json::Value>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    futures::stream::unfold(Some((bytes, String::new())), move |state| async move {
+        let (mut bytes, mut buffer) = state?;
+        loop {
+            if let Some(end) = buffer.find("\n\n") {
+                let event: String = buffer.drain(..end + 2).collect();
+                let data = event
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data:"))
+                    .map(str::trim_start)
+                    .collect::<Vec<&str>>()
+                    .join("\n");
+                if data.is_empty() {
+                    continue;
+                }
+                let document = serde_json::from_str(&data).map_err(|e| RelayError::from(e).into());
+                return Some((document, Some((bytes, buffer))));
+            }
+            if buffer.len() > max_event_size {
+                let err = RelayError::TooLarge {
+                    limit: max_event_size,
+                };
+                return Some((Err(err.into()), None));
+            }
+            match bytes.next().await {
+                // drand documents are ASCII, and cannot be split within a character
+                Some(Ok(chunk)) => {
+                    buffer.push_str(&String::from_utf8_lossy(chunk.as_ref()).replace('\r', ""))
+                }
+                Some(Err(err)) => {
+                    return Some((Err(RelayError::network(err).into()), Some((bytes, buffer))))
+                }
+                None => return None,
+            }
+        }
+    })
+}
+
+/// Transport scoped to one of the chains served by a relay, sharing the relay transport and its connection pool.
+/// Paths are prefixed with the chain hash, such as `{chain_hash}/public/latest`.
+pub(crate) struct ChainTransport {
+    transport: Arc<dyn Transport>,
+    chain_hash: String,
+}
+
+impl ChainTransport {
+    pub fn new(transport: Arc<dyn Transport>, chain_hash: &[u8]) -> Self {
+        Self {
+            transport,
+            chain_hash: hex::encode(chain_hash),
+        }
+    }
+
+    fn path(&self, path: &str) -> String {
+        format!("{}/{path}", self.chain_hash)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for ChainTransport {
+    fn base_url(&self) -> String {
+        format!(
+            "{}/{}/",
+            self.transport.base_url().trim_end_matches('/'),
+            self.chain_hash
+        )
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        self.transport.get_json(&self.path(path)).await
+    }
+
+    async fn get_json_if_none_match(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<serde_json::Value>> {
+        self.transport
+            .get_json_if_none_match(&self.path(path), etag)
+            .await
+    }
+
+    async fn stream_json(&self, path: &str) -> Result<JsonStream> {
+        self.transport.stream_json(&self.path(path)).await
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "hyper", feature = "blocking-ureq"))]
+fn parse_base_url(base_url: &str) -> Result<url::Url> {
+    // The most common error is when user forget to add protocol in front of the provided URL string.
+    // The error provided by url::Url is rather obscure when that happens.
+    let mut url = url::Url::parse(base_url).map_err(|e| {
+        if e == url::ParseError::RelativeUrlWithoutBase {
+            anyhow!("{e}. You might need to add \"https://\" to the provided URL.")
+        } else {
+            anyhow!(e)
+        }
+    })?;
+    // Ensure base URL ends with a trailing slash.
+    // Given it's the base for API calls, it allows for easier joins in other methods.
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use crate::beacon::tests::chained_beacon;
+    use crate::chain::tests::chained_chain_info;
+    use crate::HttpClient;
+
+    use super::*;
+
+    /// Transport serving documents from memory.
+    pub struct StaticTransport {
+        documents: HashMap<String, serde_json::Value>,
+    }
+
+    impl StaticTransport {
+        pub fn new(documents: &[(&str, serde_json::Value)]) -> Self {
+            Self {
+                documents: documents
+                    .iter()
+                    .map(|(path, document)| (path.to_string(), document.clone()))
+                    .collect(),
+            }
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+    #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+    impl Transport for StaticTransport {
+        fn base_url(&self) -> String {
+            "memory://".to_string()
+        }
+
+        async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+            self.documents
+                .get(path)
+                .cloned()
+                .ok_or_else(|| Error::Other(anyhow!("not found: {path}")))
+        }
+    }
+
+    #[tokio::test]
+    async fn client_with_transport_works() {
+        let transport = StaticTransport::new(&[
+            ("info", serde_json::to_value(chained_chain_info()).unwrap()),
+            (
+                "public/latest",
+                serde_json::to_value(chained_beacon()).unwrap(),
+            ),
+        ]);
+        let client = HttpClient::with_transport(transport, None);
+
+        let latest = match client.latest().await {
+            Ok(beacon) => beacon,
+            Err(err) => panic!("fetch should have succeded {}", err),
+        };
+        assert_eq!(latest.beacon(), chained_beacon());
+        assert_eq!(client.base_url(), "memory://");
+        assert!(client.get(1).await.is_err());
+    }
+
+    #[cfg(all(feature = "unix", unix))]
+    #[tokio::test]
+    async fn unix_transport_works() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let socket_path = std::env::temp_dir().join(format!("drand-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        let body = serde_json::to_string(&chained_chain_info()).unwrap();
+        let relay = tokio::spawn(async move {
+            let (mut stream, _addr) = listener.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let len = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..len]).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            request
+        });
+
+        let base_url = format!(
+            "http+unix://{}/relay",
+            socket_path.to_str().unwrap().replace('/', "%2F")
+        );
+        let transport = UnixTransport::new(&base_url).unwrap();
+        let info = transport.get_json("info").await.unwrap();
+        assert_eq!(
+            serde_json::from_value::<crate::chain::ChainInfo>(info).unwrap(),
+            chained_chain_info()
+        );
+        assert!(relay.await.unwrap().starts_with("GET /relay/info HTTP/1.1"));
+        std::fs::remove_file(&socket_path).unwrap();
+
+        assert!(UnixTransport::new("https://api.drand.sh").is_err());
+        assert!(UnixTransport::new("http+unix://%2").is_err());
+    }
+
+    #[cfg(feature = "blocking-ureq")]
+    #[tokio::test]
+    async fn ureq_transport_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1")
+            .with_status(404)
+            .with_body("not found")
+            .create_async()
+            .await;
+        let base_url = server.url();
+
+        // no runtime is required to drive the client
+        let result = std::thread::spawn(move || {
+            let transport = UreqTransport::new(&base_url).unwrap();
+            let client = HttpClient::with_transport(transport, None);
+            let latest = futures::executor::block_on(client.latest());
+            let missing = futures::executor::block_on(client.get(1));
+            (latest, missing)
+        })
+        .join()
+        .unwrap();
+
+        match result.0 {
+            Ok(beacon) => assert_eq!(beacon.beacon(), chained_beacon()),
+            Err(err) => panic!("fetch should have succeded {}", err),
+        }
+        assert!(result.1.is_err());
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn hyper_transport_works() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/info")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_chain_info()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/latest")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&chained_beacon()).unwrap())
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/public/1")
+            .with_status(404)
+            .with_body("not found")
+            .create_async()
+            .await;
+
+        let transport = HyperTransport::new(&server.url()).unwrap();
+        let client = HttpClient::with_transport(transport, None);
+        match client.latest().await {
+            Ok(beacon) => assert_eq!(beacon.beacon(), chained_beacon()),
+            Err(err) => panic!("fetch should have succeded {}", err),
+        }
+        assert!(client.get(1).await.is_err());
+
+        // without reqwest, clients are built on hyper alone
+        #[cfg(not(feature = "reqwest"))]
+        {
+            let client = HttpClient::new(&server.url(), None).unwrap();
+            assert_eq!(client.latest().await.unwrap().beacon(), chained_beacon());
+            let client = HttpClient::builder()
+                .base_url(&server.url())
+                .max_response_size(16)
+                .build()
+                .unwrap();
+            assert!(client.latest().await.is_err());
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn server_sent_events_works() {
+        let beacon = serde_json::to_string(&chained_beacon()).unwrap();
+        let (head, tail) = beacon.split_at(10);
+        let chunks: Vec<std::result::Result<String, std::io::Error>> = vec![
+            Ok(": keep-alive\n\n".to_string()),
+            Ok(format!("event: beacon\r\ndata: {head}")),
+            Ok(format!("{tail}\r\n\r\ndata: not json\n\n")),
+        ];
+
+        let documents: Vec<Result<serde_json::Value>> =
+            server_sent_events(futures::stream::iter(chunks), DEFAULT_MAX_RESPONSE_SIZE)
+                .collect()
+                .await;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            serde_json::from_value::<crate::beacon::ApiBeacon>(
+                documents[0].as_ref().unwrap().clone()
+            )
+            .unwrap(),
+            chained_beacon()
+        );
+        assert!(documents[1].is_err());
+    }
+}